@@ -0,0 +1,231 @@
+//! Parsing and formatting of angles and epochs in the notations used by star
+//! catalogs.
+use std::fmt;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::time::J2000;
+
+/// Parse a sexagesimal angle such as `05h35m17.3s`, `-05°23'28"`, `5:35:17.3`
+/// or `-5 23 28` into its sign and its absolute value in the leading unit.
+/// The `h` marker is only accepted for `hours`.
+fn parse_sexagesimal(text: &str, hours: bool) -> Option<(bool, f64)> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let separators = ['d', 'm', 's', ':', '°', '\'', '"', '′', '″'];
+    let components: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || separators.contains(&c) || (hours && c == 'h'))
+        .filter(|component| !component.is_empty())
+        .collect();
+    if components.is_empty() || components.len() > 3 {
+        return None;
+    }
+
+    let mut value = 0.0;
+    for (i, component) in components.iter().enumerate() {
+        let component: f64 = component.parse().ok()?;
+        if component < 0.0 || (i > 0 && component >= 60.0) {
+            return None;
+        }
+        value += component / 60f64.powi(i as i32);
+    }
+    Some((negative, value))
+}
+
+/// Whether `text` is written in sexagesimal notation rather than as a plain
+/// decimal number.
+fn is_sexagesimal(text: &str) -> bool {
+    text.trim().parse::<f64>().is_err()
+}
+
+/// Parse a right ascension, either in decimal degrees or in hours, minutes
+/// and seconds, into degrees.
+pub fn parse_right_ascension(text: &str) -> Result<f64, String> {
+    let degrees = if is_sexagesimal(text) {
+        match parse_sexagesimal(text, true) {
+            Some((false, hours)) => hours * 15.0,
+            _ => return Err(format!("invalid right ascension \"{}\"", text)),
+        }
+    } else {
+        text.trim().parse::<f64>().unwrap()
+    };
+    if !(0.0..360.0).contains(&degrees) {
+        return Err(format!("right ascension \"{}\" is outside of 0h..24h", text));
+    }
+    Ok(degrees)
+}
+
+/// Parse a declination, either in decimal degrees or in degrees, minutes and
+/// seconds, into degrees.
+pub fn parse_declination(text: &str) -> Result<f64, String> {
+    parse_latitude(text, "declination")
+}
+
+/// Parse a galactic latitude, either in decimal degrees or in degrees,
+/// minutes and seconds, into degrees.
+pub fn parse_galactic_latitude(text: &str) -> Result<f64, String> {
+    parse_latitude(text, "galactic latitude")
+}
+
+/// Parse a galactic longitude, either in decimal degrees or in degrees,
+/// minutes and seconds, into degrees between 0 and 360.
+pub fn parse_galactic_longitude(text: &str) -> Result<f64, String> {
+    let degrees = if is_sexagesimal(text) {
+        match parse_sexagesimal(text, false) {
+            Some((negative, degrees)) if negative => -degrees,
+            Some((_, degrees)) => degrees,
+            None => return Err(format!("invalid galactic longitude \"{}\"", text)),
+        }
+    } else {
+        text.trim().parse::<f64>().unwrap()
+    };
+    if !(-360.0..=360.0).contains(&degrees) {
+        return Err(format!("galactic longitude \"{}\" is outside of -360°..360°", text));
+    }
+    Ok(degrees.rem_euclid(360.0))
+}
+
+/// Parse an angle between -90° and 90°, naming it `what` in errors.
+fn parse_latitude(text: &str, what: &str) -> Result<f64, String> {
+    let degrees = if is_sexagesimal(text) {
+        match parse_sexagesimal(text, false) {
+            Some((negative, degrees)) if negative => -degrees,
+            Some((_, degrees)) => degrees,
+            None => return Err(format!("invalid {} \"{}\"", what, text)),
+        }
+    } else {
+        text.trim().parse::<f64>().unwrap()
+    };
+    if !(-90.0..=90.0).contains(&degrees) {
+        return Err(format!("{} \"{}\" is outside of -90°..90°", what, text));
+    }
+    Ok(degrees)
+}
+
+/// Split a positive value into whole units, minutes and seconds rounded to
+/// `decimals` decimal places, carrying over when the seconds round up to 60.
+fn split_sexagesimal(value: f64, decimals: usize) -> (u32, u32, f64) {
+    let scale = 10f64.powi(decimals as i32);
+    let total_seconds = (value * 3600.0 * scale).round() / scale;
+    let units = (total_seconds / 3600.0).floor();
+    let minutes = ((total_seconds - units * 3600.0) / 60.0).floor();
+    let seconds = total_seconds - units * 3600.0 - minutes * 60.0;
+    (units as u32, minutes as u32, seconds)
+}
+
+/// Format a right ascension in degrees as `05h35m17.30s`.
+pub fn format_right_ascension(degrees: f64) -> String {
+    let (hours, minutes, seconds) = split_sexagesimal(degrees.rem_euclid(360.0) / 15.0, 2);
+    format!("{:02}h{:02}m{:05.2}s", hours % 24, minutes, seconds)
+}
+
+/// Format a declination in degrees as `-05°23'28.0"`.
+pub fn format_declination(degrees: f64) -> String {
+    let sign = if degrees < 0.0 { '-' } else { '+' };
+    let (degrees, minutes, seconds) = split_sexagesimal(degrees.abs(), 1);
+    format!("{}{:02}°{:02}'{:04.1}\"", sign, degrees, minutes, seconds)
+}
+
+/// Reference epoch of catalog coordinates.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Epoch {
+    /// Julian epoch, such as J2000.0.
+    Julian(f64),
+    /// Besselian epoch, such as B1950.0, used by older catalogs.
+    Besselian(f64),
+}
+
+impl Default for Epoch {
+    fn default() -> Self {
+        Epoch::Julian(2000.0)
+    }
+}
+
+impl Epoch {
+    /// Parse an epoch written as `J2000`, `B1950.0` or as a plain Julian
+    /// year such as `2000`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let invalid = || format!("invalid epoch \"{}\"", text);
+        if let Some(year) = text.strip_prefix(['J', 'j']) {
+            year.parse().map(Epoch::Julian).map_err(|_| invalid())
+        } else if let Some(year) = text.strip_prefix(['B', 'b']) {
+            year.parse().map(Epoch::Besselian).map_err(|_| invalid())
+        } else {
+            text.parse().map(Epoch::Julian).map_err(|_| invalid())
+        }
+    }
+
+    pub fn julian_date(&self) -> f64 {
+        match *self {
+            Epoch::Julian(year) => J2000 + (year - 2000.0) * 365.25,
+            Epoch::Besselian(year) => 2_415_020.313_52 + (year - 1900.0) * 365.242_198_781,
+        }
+    }
+}
+
+impl fmt::Display for Epoch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Epoch::Julian(year) => write!(f, "J{:.1}", year),
+            Epoch::Besselian(year) => write!(f, "B{:.1}", year),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    #[test]
+    fn parses_every_separator_style() {
+        let ra = 5.0 * 15.0 + 35.0 / 4.0 + 17.3 / 240.0;
+        for text in ["05h35m17.3s", "5:35:17.3", "5 35 17.3", " +05h 35m 17.3s "] {
+            assert!((parse_right_ascension(text).unwrap() - ra).abs() < 1e-9, "{}", text);
+        }
+        assert_eq!(parse_right_ascension("83.82"), Ok(83.82));
+
+        let dec = -(5.0 + 23.0 / 60.0 + 28.0 / 3600.0);
+        for text in ["-05°23'28\"", "-5d23m28s", "-5:23:28", "-5 23 28", "-05°23′28″"] {
+            assert!((parse_declination(text).unwrap() - dec).abs() < 1e-9, "{}", text);
+        }
+        assert_close(parse_galactic_longitude("120d09m").unwrap(), 120.15, 1e-9);
+        assert_close(parse_galactic_longitude("-10").unwrap(), 350.0, 1e-9);
+    }
+
+    #[test]
+    fn keeps_the_sign_of_zero_degrees() {
+        assert_eq!(parse_declination("-00°30'"), Ok(-0.5));
+        assert_eq!(parse_declination("-0 30"), Ok(-0.5));
+        assert_eq!(parse_galactic_latitude("+00°30'"), Ok(0.5));
+    }
+
+    #[test]
+    fn rejects_invalid_angles() {
+        for text in ["24h", "-1h", "360", "5:60:00", "5:35:17:3", "", "abc"] {
+            assert!(parse_right_ascension(text).is_err(), "{}", text);
+        }
+        for text in ["91", "-90°30'", "10°75'", "5h", "-5h30m"] {
+            assert!(parse_declination(text).is_err(), "{}", text);
+        }
+        assert!(parse_galactic_latitude("5h").is_err());
+        assert!(parse_galactic_longitude("120h").is_err());
+        assert!(parse_galactic_longitude("361").is_err());
+    }
+
+    #[test]
+    fn carries_seconds_that_round_to_sixty() {
+        assert_eq!(split_sexagesimal(0.999_999_9, 1), (1, 0, 0.0));
+        assert_eq!(format_declination(-0.999_999_9), "-01°00'00.0\"");
+        assert_eq!(format_right_ascension(359.999_999_9), "00h00m00.00s");
+        assert_eq!(format_right_ascension(83.822_083_3), "05h35m17.30s");
+    }
+}
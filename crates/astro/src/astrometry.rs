@@ -0,0 +1,517 @@
+//! Conversion of catalog positions to apparent places.
+//!
+//! The reductions follow Meeus, "Astronomical Algorithms" (2nd ed.): IAU 1976
+//! precession, IAU 1980 nutation, annual aberration including the E-terms of
+//! the Earth's orbit, plus proper motion and annual parallax. The result is
+//! good to a fraction of an arcsecond, far below the beam of a small radio
+//! telescope.
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::angle::Epoch;
+use crate::time;
+
+const ARCSECOND: f64 = 1.0 / 3600.0;
+
+/// Constant of aberration, in arcseconds.
+const ABERRATION_CONSTANT: f64 = 20.495_52;
+
+/// Position of a fixed object as given by a star catalog.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CatalogPosition {
+    /// Right ascension in degrees, referred to `epoch`.
+    pub ra: f64,
+    /// Declination in degrees, referred to `epoch`.
+    pub dec: f64,
+    pub epoch: Epoch,
+    /// Proper motion in right ascension times the cosine of the declination,
+    /// in milliarcseconds per year.
+    #[serde(default)]
+    pub pm_ra: f64,
+    /// Proper motion in declination, in milliarcseconds per year.
+    #[serde(default)]
+    pub pm_dec: f64,
+    /// Annual parallax, in milliarcseconds.
+    #[serde(default)]
+    pub parallax: f64,
+}
+
+impl CatalogPosition {
+    /// J2000.0 position of an object without proper motion or parallax.
+    pub fn j2000(ra: f64, dec: f64) -> Self {
+        CatalogPosition {
+            ra,
+            dec,
+            epoch: Epoch::default(),
+            pm_ra: 0.0,
+            pm_dec: 0.0,
+            parallax: 0.0,
+        }
+    }
+}
+
+/// Precess equatorial coordinates, in degrees, from the mean equinox of one
+/// Julian date to the mean equinox of another.
+pub fn precess(ra: f64, dec: f64, from: f64, to: f64) -> (f64, f64) {
+    let big_t = time::julian_centuries(from);
+    let t = (to - from) / time::JULIAN_CENTURY;
+
+    let common = 2306.2181 + 1.39656 * big_t - 0.000139 * big_t * big_t;
+    let zeta = (common * t
+        + (0.30188 - 0.000344 * big_t) * t * t
+        + 0.017998 * t * t * t) * ARCSECOND;
+    let z = (common * t
+        + (1.09468 + 0.000066 * big_t) * t * t
+        + 0.018203 * t * t * t) * ARCSECOND;
+    let theta = ((2004.3109 - 0.85330 * big_t - 0.000217 * big_t * big_t) * t
+        - (0.42665 + 0.000217 * big_t) * t * t
+        - 0.041833 * t * t * t) * ARCSECOND;
+
+    let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+    let (sin_ra_zeta, cos_ra_zeta) = (ra + zeta).to_radians().sin_cos();
+    let (sin_theta, cos_theta) = theta.to_radians().sin_cos();
+
+    let a = cos_dec * sin_ra_zeta;
+    let b = cos_theta * cos_dec * cos_ra_zeta - sin_theta * sin_dec;
+    let c = sin_theta * cos_dec * cos_ra_zeta + cos_theta * sin_dec;
+
+    (
+        (a.atan2(b).to_degrees() + z).rem_euclid(360.0),
+        c.clamp(-1.0, 1.0).asin().to_degrees(),
+    )
+}
+
+/// Precess ecliptic coordinates, in degrees, from the mean ecliptic and
+/// equinox of one Julian date to those of another (Meeus 21.5).
+pub fn precess_ecliptic(longitude: f64, latitude: f64, from: f64, to: f64) -> (f64, f64) {
+    let big_t = time::julian_centuries(from);
+    let t = (to - from) / time::JULIAN_CENTURY;
+
+    let eta = ((47.0029 - 0.06603 * big_t + 0.000598 * big_t * big_t) * t
+        + (-0.03302 + 0.000598 * big_t) * t * t
+        + 0.000060 * t * t * t) * ARCSECOND;
+    let pi = 174.876384
+        + (3289.4789 * big_t + 0.60622 * big_t * big_t) * ARCSECOND
+        - ((869.8089 + 0.50491 * big_t) * t - 0.03536 * t * t) * ARCSECOND;
+    let p = ((5029.0966 + 2.22226 * big_t - 0.000042 * big_t * big_t) * t
+        + (1.11113 - 0.000042 * big_t) * t * t
+        - 0.000006 * t * t * t) * ARCSECOND;
+
+    let (sin_eta, cos_eta) = eta.to_radians().sin_cos();
+    let (sin_latitude, cos_latitude) = latitude.to_radians().sin_cos();
+    let (sin_node, cos_node) = (pi - longitude).to_radians().sin_cos();
+    let a = cos_eta * cos_latitude * sin_node - sin_eta * sin_latitude;
+    let b = cos_latitude * cos_node;
+    let c = cos_eta * sin_latitude + sin_eta * cos_latitude * sin_node;
+
+    (
+        (p + pi - a.atan2(b).to_degrees()).rem_euclid(360.0),
+        c.clamp(-1.0, 1.0).asin().to_degrees(),
+    )
+}
+
+/// E-terms of aberration included in FK4 positions (Standish 1982).
+const E_TERMS: [f64; 3] = [-1.62557e-6, -0.31919e-6, -0.13843e-6];
+
+/// Rotation from the FK4 frame at B1950.0 to the FK5 frame at J2000.0.
+const FK4_TO_FK5: [[f64; 3]; 3] = [
+    [0.999_925_678_2, -0.011_182_061_1, -0.004_857_947_7],
+    [0.011_182_061_0, 0.999_937_478_4, -0.000_027_176_5],
+    [0.004_857_947_9, -0.000_027_147_4, 0.999_988_199_7],
+];
+
+/// Convert an FK4 position, in degrees, referred to the Besselian epoch
+/// `year`, to the FK5 frame at J2000.0.
+pub fn fk4_to_fk5(ra: f64, dec: f64, year: f64) -> (f64, f64) {
+    let b1950 = Epoch::Besselian(1950.0).julian_date();
+    let (ra, dec) = precess(ra, dec, Epoch::Besselian(year).julian_date(), b1950);
+    let r0 = unit_vector(ra, dec);
+    let dot: f64 = r0.iter().zip(E_TERMS).map(|(r, a)| r * a).sum();
+    let r1: Vec<f64> = (0..3).map(|i| r0[i] - E_TERMS[i] + dot * r0[i]).collect();
+    spherical(FK4_TO_FK5.map(|row| row.iter().zip(&r1).map(|(m, r)| m * r).sum()))
+}
+
+/// Position, in degrees, referred to the mean equinox of J2000.0 of
+/// coordinates given at `epoch`.
+pub fn to_j2000(ra: f64, dec: f64, epoch: Epoch) -> (f64, f64) {
+    match epoch {
+        Epoch::Julian(_) => precess(ra, dec, epoch.julian_date(), time::J2000),
+        Epoch::Besselian(year) => fk4_to_fk5(ra, dec, year),
+    }
+}
+
+/// Multipliers of D, M, M', F and Ω, then the coefficients of sin in Δψ and
+/// of cos in Δε, in units of 0.0001", with their rates per Julian century.
+const NUTATION_TERMS: [([i8; 5], f64, f64, f64, f64); 63] = [
+    ([0, 0, 0, 0, 1], -171996.0, -174.2, 92025.0, 8.9),
+    ([-2, 0, 0, 2, 2], -13187.0, -1.6, 5736.0, -3.1),
+    ([0, 0, 0, 2, 2], -2274.0, -0.2, 977.0, -0.5),
+    ([0, 0, 0, 0, 2], 2062.0, 0.2, -895.0, 0.5),
+    ([0, 1, 0, 0, 0], 1426.0, -3.4, 54.0, -0.1),
+    ([0, 0, 1, 0, 0], 712.0, 0.1, -7.0, 0.0),
+    ([-2, 1, 0, 2, 2], -517.0, 1.2, 224.0, -0.6),
+    ([0, 0, 0, 2, 1], -386.0, -0.4, 200.0, 0.0),
+    ([0, 0, 1, 2, 2], -301.0, 0.0, 129.0, -0.1),
+    ([-2, -1, 0, 2, 2], 217.0, -0.5, -95.0, 0.3),
+    ([-2, 0, 1, 0, 0], -158.0, 0.0, 0.0, 0.0),
+    ([-2, 0, 0, 2, 1], 129.0, 0.1, -70.0, 0.0),
+    ([0, 0, -1, 2, 2], 123.0, 0.0, -53.0, 0.0),
+    ([2, 0, 0, 0, 0], 63.0, 0.0, 0.0, 0.0),
+    ([0, 0, 1, 0, 1], 63.0, 0.1, -33.0, 0.0),
+    ([2, 0, -1, 2, 2], -59.0, 0.0, 26.0, 0.0),
+    ([0, 0, -1, 0, 1], -58.0, -0.1, 32.0, 0.0),
+    ([0, 0, 1, 2, 1], -51.0, 0.0, 27.0, 0.0),
+    ([-2, 0, 2, 0, 0], 48.0, 0.0, 0.0, 0.0),
+    ([0, 0, -2, 2, 1], 46.0, 0.0, -24.0, 0.0),
+    ([2, 0, 0, 2, 2], -38.0, 0.0, 16.0, 0.0),
+    ([0, 0, 2, 2, 2], -31.0, 0.0, 13.0, 0.0),
+    ([0, 0, 2, 0, 0], 29.0, 0.0, 0.0, 0.0),
+    ([-2, 0, 1, 2, 2], 29.0, 0.0, -12.0, 0.0),
+    ([0, 0, 0, 2, 0], 26.0, 0.0, 0.0, 0.0),
+    ([-2, 0, 0, 2, 0], -22.0, 0.0, 0.0, 0.0),
+    ([0, 0, -1, 2, 1], 21.0, 0.0, -10.0, 0.0),
+    ([0, 2, 0, 0, 0], 17.0, -0.1, 0.0, 0.0),
+    ([2, 0, -1, 0, 1], 16.0, 0.0, -8.0, 0.0),
+    ([-2, 2, 0, 2, 2], -16.0, 0.1, 7.0, 0.0),
+    ([0, 1, 0, 0, 1], -15.0, 0.0, 9.0, 0.0),
+    ([-2, 0, 1, 0, 1], -13.0, 0.0, 7.0, 0.0),
+    ([0, -1, 0, 0, 1], -12.0, 0.0, 6.0, 0.0),
+    ([0, 0, 2, -2, 0], 11.0, 0.0, 0.0, 0.0),
+    ([2, 0, -1, 2, 1], -10.0, 0.0, 5.0, 0.0),
+    ([2, 0, 1, 2, 2], -8.0, 0.0, 3.0, 0.0),
+    ([0, 1, 0, 2, 2], 7.0, 0.0, -3.0, 0.0),
+    ([-2, 1, 1, 0, 0], -7.0, 0.0, 0.0, 0.0),
+    ([0, -1, 0, 2, 2], -7.0, 0.0, 3.0, 0.0),
+    ([2, 0, 0, 2, 1], -7.0, 0.0, 3.0, 0.0),
+    ([2, 0, 1, 0, 0], 6.0, 0.0, 0.0, 0.0),
+    ([-2, 0, 2, 2, 2], 6.0, 0.0, -3.0, 0.0),
+    ([-2, 0, 1, 2, 1], 6.0, 0.0, -3.0, 0.0),
+    ([2, 0, -2, 0, 1], -6.0, 0.0, 3.0, 0.0),
+    ([2, 0, 0, 0, 1], -6.0, 0.0, 3.0, 0.0),
+    ([0, -1, 1, 0, 0], 5.0, 0.0, 0.0, 0.0),
+    ([-2, -1, 0, 2, 1], -5.0, 0.0, 3.0, 0.0),
+    ([-2, 0, 0, 0, 1], -5.0, 0.0, 3.0, 0.0),
+    ([0, 0, 2, 2, 1], -5.0, 0.0, 3.0, 0.0),
+    ([-2, 0, 2, 0, 1], 4.0, 0.0, 0.0, 0.0),
+    ([-2, 1, 0, 2, 1], 4.0, 0.0, 0.0, 0.0),
+    ([0, 0, 1, -2, 0], 4.0, 0.0, 0.0, 0.0),
+    ([-1, 0, 1, 0, 0], -4.0, 0.0, 0.0, 0.0),
+    ([-2, 1, 0, 0, 0], -4.0, 0.0, 0.0, 0.0),
+    ([1, 0, 0, 0, 0], -4.0, 0.0, 0.0, 0.0),
+    ([0, 0, 1, 2, 0], 3.0, 0.0, 0.0, 0.0),
+    ([0, 0, -2, 2, 2], -3.0, 0.0, 0.0, 0.0),
+    ([-1, -1, 1, 0, 0], -3.0, 0.0, 0.0, 0.0),
+    ([0, 1, 1, 0, 0], -3.0, 0.0, 0.0, 0.0),
+    ([0, -1, 1, 2, 2], -3.0, 0.0, 0.0, 0.0),
+    ([2, -1, -1, 2, 2], -3.0, 0.0, 0.0, 0.0),
+    ([0, 0, 3, 2, 2], -3.0, 0.0, 0.0, 0.0),
+    ([2, -1, 0, 2, 2], -3.0, 0.0, 0.0, 0.0),
+];
+
+/// Nutation in longitude and obliquity, in degrees, at a Julian date in TT.
+pub fn nutation(julian_date: f64) -> (f64, f64) {
+    let t = time::julian_centuries(julian_date);
+    let elongation = 297.85036 + 445267.111480 * t - 0.0019142 * t * t + t * t * t / 189474.0;
+    let sun_anomaly = 357.52772 + 35999.050340 * t - 0.0001603 * t * t - t * t * t / 300000.0;
+    let moon_anomaly = 134.96298 + 477198.867398 * t + 0.0086972 * t * t + t * t * t / 56250.0;
+    let moon_latitude = 93.27191 + 483202.017538 * t - 0.0036825 * t * t + t * t * t / 327270.0;
+    let node = 125.04452 - 1934.136261 * t + 0.0020708 * t * t + t * t * t / 450000.0;
+    let arguments = [elongation, sun_anomaly, moon_anomaly, moon_latitude, node];
+
+    let mut longitude = 0.0;
+    let mut obliquity = 0.0;
+    for (multipliers, sin, sin_rate, cos, cos_rate) in NUTATION_TERMS {
+        let argument: f64 = multipliers
+            .iter()
+            .zip(arguments)
+            .map(|(multiplier, argument)| *multiplier as f64 * argument)
+            .sum::<f64>()
+            .to_radians();
+        longitude += (sin + sin_rate * t) * argument.sin();
+        obliquity += (cos + cos_rate * t) * argument.cos();
+    }
+    (longitude * 0.0001 * ARCSECOND, obliquity * 0.0001 * ARCSECOND)
+}
+
+/// Mean obliquity of the ecliptic, in degrees, at a Julian date in TT.
+pub fn mean_obliquity(julian_date: f64) -> f64 {
+    let t = time::julian_centuries(julian_date);
+    23.0 + 26.0 / 60.0
+        + (21.448 - 46.8150 * t - 0.00059 * t * t + 0.001813 * t * t * t) * ARCSECOND
+}
+
+/// Geometric ecliptic longitude, in degrees, and distance, in astronomical
+/// units, of the Sun at a Julian date in TT. Accurate to about 0.01°.
+pub fn sun_longitude_and_distance(julian_date: f64) -> (f64, f64) {
+    let t = time::julian_centuries(julian_date);
+    let mean_longitude = 280.46646 + 36000.76983 * t + 0.0003032 * t * t;
+    let mean_anomaly = 357.52911 + 35999.05029 * t - 0.0001537 * t * t;
+    let eccentricity = 0.016708634 - 0.000042037 * t - 0.0000001267 * t * t;
+    let m = mean_anomaly.to_radians();
+    let center = (1.914602 - 0.004817 * t - 0.000014 * t * t) * m.sin()
+        + (0.019993 - 0.000101 * t) * (2.0 * m).sin()
+        + 0.000289 * (3.0 * m).sin();
+    let true_anomaly = (mean_anomaly + center).to_radians();
+    let distance = 1.000001018 * (1.0 - eccentricity * eccentricity)
+        / (1.0 + eccentricity * true_anomaly.cos());
+    ((mean_longitude + center).rem_euclid(360.0), distance)
+}
+
+/// Everything needed to compute apparent places at a given instant that does
+/// not depend on the object, so it can be computed once for many objects.
+pub struct ApparentFrame {
+    /// Julian date in TT.
+    pub julian_date: f64,
+    /// Nutation in longitude, in degrees.
+    pub nutation_longitude: f64,
+    /// Nutation in obliquity, in degrees.
+    pub nutation_obliquity: f64,
+    /// True obliquity of the ecliptic, in degrees.
+    pub obliquity: f64,
+    /// Geometric longitude of the Sun, referred to the mean equinox of date,
+    /// in degrees.
+    pub sun_longitude: f64,
+    /// Distance between the Sun and the Earth, in astronomical units.
+    pub sun_distance: f64,
+    eccentricity: f64,
+    perihelion: f64,
+}
+
+impl ApparentFrame {
+    pub fn new(julian_date: f64) -> Self {
+        let t = time::julian_centuries(julian_date);
+        let (nutation_longitude, nutation_obliquity) = nutation(julian_date);
+        let (sun_longitude, sun_distance) = sun_longitude_and_distance(julian_date);
+        ApparentFrame {
+            julian_date,
+            nutation_longitude,
+            nutation_obliquity,
+            obliquity: mean_obliquity(julian_date) + nutation_obliquity,
+            sun_longitude,
+            sun_distance,
+            eccentricity: 0.016708634 - 0.000042037 * t - 0.0000001267 * t * t,
+            perihelion: 102.93735 + 1.71946 * t + 0.00046 * t * t,
+        }
+    }
+
+    /// Apparent geocentric right ascension and declination, in degrees, of a
+    /// catalog position.
+    pub fn apparent_place(&self, position: &CatalogPosition) -> (f64, f64) {
+        let epoch = position.epoch.julian_date();
+        let years = (self.julian_date - epoch) / 365.25;
+        let (mut ra, mut dec) = (position.ra, position.dec);
+
+        // Proper motion, in the frame of the catalog.
+        if position.pm_ra != 0.0 || position.pm_dec != 0.0 {
+            dec += position.pm_dec * years / 3_600_000.0;
+            ra += position.pm_ra * years / 3_600_000.0 / position.dec.to_radians().cos();
+        }
+
+        // Besselian positions are in the FK4 frame, which is converted first.
+        let (ra, dec, epoch) = match position.epoch {
+            Epoch::Besselian(year) => {
+                let (ra, dec) = fk4_to_fk5(ra, dec, year);
+                (ra, dec, time::J2000)
+            }
+            Epoch::Julian(_) => (ra, dec, epoch),
+        };
+        let (ra, dec) = precess(ra, dec, epoch, self.julian_date);
+        let (ra, dec) = self.apply_parallax(ra, dec, position.parallax);
+        let (ra, dec) = self.apply_nutation(ra, dec);
+        self.apply_aberration(ra, dec)
+    }
+
+    /// Shift a position of date by the annual parallax, given in
+    /// milliarcseconds, as seen from the Earth instead of the Sun.
+    fn apply_parallax(&self, ra: f64, dec: f64, parallax: f64) -> (f64, f64) {
+        if parallax == 0.0 {
+            return (ra, dec);
+        }
+        let (sin_sun, cos_sun) = self.sun_longitude.to_radians().sin_cos();
+        let (sin_obliquity, cos_obliquity) = self.obliquity.to_radians().sin_cos();
+        let sun = [
+            self.sun_distance * cos_sun,
+            self.sun_distance * sin_sun * cos_obliquity,
+            self.sun_distance * sin_sun * sin_obliquity,
+        ];
+        let parallax = (parallax / 3_600_000.0).to_radians();
+        let direction = unit_vector(ra, dec);
+        spherical([
+            direction[0] + parallax * sun[0],
+            direction[1] + parallax * sun[1],
+            direction[2] + parallax * sun[2],
+        ])
+    }
+
+    fn apply_nutation(&self, ra: f64, dec: f64) -> (f64, f64) {
+        let (sin_ra, cos_ra) = ra.to_radians().sin_cos();
+        let tan_dec = dec.to_radians().tan();
+        let (sin_obliquity, cos_obliquity) = self.obliquity.to_radians().sin_cos();
+        let delta_ra = (cos_obliquity + sin_obliquity * sin_ra * tan_dec) * self.nutation_longitude
+            - cos_ra * tan_dec * self.nutation_obliquity;
+        let delta_dec = sin_obliquity * cos_ra * self.nutation_longitude
+            + sin_ra * self.nutation_obliquity;
+        ((ra + delta_ra).rem_euclid(360.0), dec + delta_dec)
+    }
+
+    fn apply_aberration(&self, ra: f64, dec: f64) -> (f64, f64) {
+        let (sin_ra, cos_ra) = ra.to_radians().sin_cos();
+        let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+        let obliquity = self.obliquity.to_radians();
+        let cos_obliquity = obliquity.cos();
+        let (sin_sun, cos_sun) = self.sun_longitude.to_radians().sin_cos();
+        let (sin_perihelion, cos_perihelion) = self.perihelion.to_radians().sin_cos();
+        let kappa = ABERRATION_CONSTANT * ARCSECOND;
+        let e_kappa = self.eccentricity * kappa;
+
+        let delta_ra = (-kappa * (cos_ra * cos_sun * cos_obliquity + sin_ra * sin_sun)
+            + e_kappa * (cos_ra * cos_perihelion * cos_obliquity + sin_ra * sin_perihelion))
+            / cos_dec;
+        let tan_term = obliquity.tan() * cos_dec - sin_ra * sin_dec;
+        let delta_dec = -kappa * (cos_sun * cos_obliquity * tan_term + cos_ra * sin_dec * sin_sun)
+            + e_kappa * (cos_perihelion * cos_obliquity * tan_term
+                + cos_ra * sin_dec * sin_perihelion);
+        ((ra + delta_ra).rem_euclid(360.0), dec + delta_dec)
+    }
+
+    /// Greenwich apparent sidereal time, in degrees, for a Julian date in
+    /// universal time close to this frame's.
+    pub fn apparent_sidereal_time(&self, julian_date_ut: f64) -> f64 {
+        (time::greenwich_mean_sidereal_time(julian_date_ut)
+            + self.nutation_longitude * self.obliquity.to_radians().cos())
+            .rem_euclid(360.0)
+    }
+}
+
+pub(crate) fn unit_vector(ra: f64, dec: f64) -> [f64; 3] {
+    let (sin_ra, cos_ra) = ra.to_radians().sin_cos();
+    let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+    [cos_dec * cos_ra, cos_dec * sin_ra, sin_dec]
+}
+
+pub(crate) fn spherical(vector: [f64; 3]) -> (f64, f64) {
+    let [x, y, z] = vector;
+    (
+        y.atan2(x).to_degrees().rem_euclid(360.0),
+        z.atan2((x * x + y * y).sqrt()).to_degrees(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    /// 2028 November 13.19 TD, used in Meeus examples 21.b and 23.a.
+    const EXAMPLE_DATE: f64 = 2_462_088.69;
+
+    /// θ Persei, J2000.0, from Meeus example 21.b.
+    fn theta_persei() -> CatalogPosition {
+        let dec = 49.0 + 13.0 / 60.0 + 42.48 / 3600.0;
+        CatalogPosition {
+            ra: (2.0 + 44.0 / 60.0 + 11.986 / 3600.0) * 15.0,
+            dec,
+            epoch: Epoch::Julian(2000.0),
+            pm_ra: 0.03425 * 15.0 * 1000.0 * dec.to_radians().cos(),
+            pm_dec: -89.5,
+            parallax: 0.0,
+        }
+    }
+
+    #[test]
+    fn mean_sidereal_time_matches_meeus_12a_and_12b() {
+        // 1987 April 10, 0h UT: 13h10m46.3668s.
+        let gmst = time::greenwich_mean_sidereal_time(2_446_895.5);
+        assert_close(gmst, (13.0 + 10.0 / 60.0 + 46.3668 / 3600.0) * 15.0, 0.0001 / 240.0);
+
+        // 1987 April 10, 19h21m00s UT: 8h34m57.0896s.
+        let gmst = time::greenwich_mean_sidereal_time(2_446_896.306_25);
+        assert_close(gmst, (8.0 + 34.0 / 60.0 + 57.0896 / 3600.0) * 15.0, 0.0001 / 240.0);
+    }
+
+    #[test]
+    fn nutation_matches_meeus_22a() {
+        // 1987 April 10, 0h TD.
+        let (longitude, obliquity) = nutation(2_446_895.5);
+        assert_close(longitude / ARCSECOND, -3.788, 0.001);
+        assert_close(obliquity / ARCSECOND, 9.443, 0.001);
+
+        let mean = mean_obliquity(2_446_895.5);
+        assert_close(mean, 23.0 + 26.0 / 60.0 + 27.407 / 3600.0, 0.001 * ARCSECOND);
+    }
+
+    #[test]
+    fn precession_matches_meeus_21b() {
+        let star = theta_persei();
+        let years = (EXAMPLE_DATE - time::J2000) / 365.25;
+        let ra = star.ra + star.pm_ra * years / 3_600_000.0 / star.dec.to_radians().cos();
+        let dec = star.dec + star.pm_dec * years / 3_600_000.0;
+
+        // 2h46m11.331s, +49°20'54.54".
+        let (ra, dec) = precess(ra, dec, time::J2000, EXAMPLE_DATE);
+        assert_close(ra, (2.0 + 46.0 / 60.0 + 11.331 / 3600.0) * 15.0, 0.001 * 15.0 / 3600.0);
+        assert_close(dec, 49.0 + 20.0 / 60.0 + 54.54 / 3600.0, 0.01 * ARCSECOND);
+    }
+
+    #[test]
+    fn apparent_place_matches_meeus_23a() {
+        let frame = ApparentFrame::new(EXAMPLE_DATE);
+        let (ra, dec) = frame.apparent_place(&theta_persei());
+
+        // 2h46m14.390s, +49°21'07.45".
+        assert_close(ra, (2.0 + 46.0 / 60.0 + 14.390 / 3600.0) * 15.0, 0.002 * 15.0 / 3600.0);
+        assert_close(dec, 49.0 + 21.0 / 60.0 + 7.45 / 3600.0, 0.02 * ARCSECOND);
+    }
+
+    #[test]
+    fn parallax_moves_stars_toward_the_sun() {
+        let frame = ApparentFrame::new(EXAMPLE_DATE);
+        let (sun_longitude, _) = sun_longitude_and_distance(EXAMPLE_DATE);
+        let sun = unit_vector_ecliptic(sun_longitude, frame.obliquity);
+        let separation = |a: [f64; 3], b: [f64; 3]| -> f64 {
+            (a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>())
+                .clamp(-1.0, 1.0)
+                .acos()
+                .to_degrees()
+        };
+        // A star on the ecliptic 90° ahead of the Sun.
+        let (ra, dec) = spherical(unit_vector_ecliptic(sun_longitude + 90.0, frame.obliquity));
+        let (shifted_ra, shifted_dec) = frame.apply_parallax(ra, dec, 1000.0);
+        let star = unit_vector(ra, dec);
+        let shifted = unit_vector(shifted_ra, shifted_dec);
+
+        // The whole shift of one parallax, all of it toward the Sun.
+        assert_close(separation(star, shifted) / ARCSECOND, 1.0, 0.05);
+        assert_close((separation(star, sun) - separation(shifted, sun)) / ARCSECOND, 1.0, 0.05);
+    }
+
+    #[test]
+    fn ecliptic_precession_matches_meeus_21c() {
+        // From J2000.0 to -214 June 30.0 TD.
+        let (longitude, latitude) = precess_ecliptic(149.481_94, 1.765_49, time::J2000, 1_643_074.5);
+        assert_close(longitude, 118.704, 0.001);
+        assert_close(latitude, 1.615, 0.001);
+    }
+
+    #[test]
+    fn fk4_to_fk5_matches_3c_273() {
+        // B1950.0 12h26m33.246s +02°19'43.29" is J2000.0 12h29m06.6997s +02°03'08.598".
+        let (ra, dec) = to_j2000(
+            (12.0 + 26.0 / 60.0 + 33.246 / 3600.0) * 15.0,
+            2.0 + 19.0 / 60.0 + 43.29 / 3600.0,
+            Epoch::Besselian(1950.0),
+        );
+        // The J2000.0 position is the ICRF one, which the FK4 rotation reaches
+        // to better than 0.1".
+        assert_close(ra, (12.0 + 29.0 / 60.0 + 6.6997 / 3600.0) * 15.0, 0.1 * ARCSECOND);
+        assert_close(dec, 2.0 + 3.0 / 60.0 + 8.598 / 3600.0, 0.1 * ARCSECOND);
+    }
+
+    fn unit_vector_ecliptic(longitude: f64, obliquity: f64) -> [f64; 3] {
+        let (sin_longitude, cos_longitude) = longitude.to_radians().sin_cos();
+        let (sin_obliquity, cos_obliquity) = obliquity.to_radians().sin_cos();
+        [cos_longitude, sin_longitude * cos_obliquity, sin_longitude * sin_obliquity]
+    }
+}
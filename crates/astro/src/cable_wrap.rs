@@ -0,0 +1,130 @@
+//! Azimuth range of a mount whose cables stop it from turning freely.
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+/// Cumulative azimuths, in degrees from north through east, between which
+/// the mount can turn without winding its cables past their stops. The
+/// range may span more than a full turn, such as -270 to 270, in which case
+/// some directions are reached at two positions of the mount.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CableWrap {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl CableWrap {
+    /// Check that the stops are in order.
+    pub fn check(&self) -> Result<(), String> {
+        if self.min < self.max {
+            Ok(())
+        } else {
+            Err(format!("The cable wrap goes from {} to {}, its minimum must be under its maximum",
+                self.min, self.max))
+        }
+    }
+
+    pub fn contains(&self, azimuth: f64) -> bool {
+        self.margin(azimuth) >= 0.0
+    }
+
+    /// Lowest position of the mount pointing at `azimuth` that is not under
+    /// the minimum stop.
+    fn first_position(&self, azimuth: f64) -> f64 {
+        self.min + (azimuth - self.min).rem_euclid(360.0)
+    }
+
+    /// Positions of the mount pointing at `azimuth`, in increasing order.
+    fn positions(&self, azimuth: f64) -> impl Iterator<Item = f64> + '_ {
+        let first = self.first_position(azimuth);
+        (0..)
+            .map(move |turns| first + 360.0 * turns as f64)
+            .take_while(|position| *position <= self.max)
+    }
+
+    /// Position of the mount pointing at `azimuth` that is the nearest to
+    /// its `current` position, or `None` when the stops keep it from
+    /// pointing there.
+    pub fn position(&self, azimuth: f64, current: f64) -> Option<f64> {
+        self.positions(azimuth)
+            .min_by(|a, b| (a - current).abs().total_cmp(&(b - current).abs()))
+    }
+
+    /// Position reached by turning the shortest way from `current` to
+    /// `azimuth`, or `None` when that crosses a stop.
+    pub fn follow(&self, current: f64, azimuth: f64) -> Option<f64> {
+        let position = current + (azimuth - current + 180.0).rem_euclid(360.0) - 180.0;
+        (self.min..=self.max).contains(&position).then_some(position)
+    }
+
+    /// Distance, in degrees of azimuth, to the nearest stop from the
+    /// position pointing at `azimuth` that is the farthest from them:
+    /// positive inside the range and negative outside.
+    pub fn margin(&self, azimuth: f64) -> f64 {
+        let inside = self.positions(azimuth)
+            .map(|position| (position - self.min).min(self.max - position))
+            .reduce(f64::max);
+        inside.unwrap_or_else(|| {
+            let first = self.first_position(azimuth);
+            -(first - self.max).min(self.min + 360.0 - first)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    #[test]
+    fn wraps_around_north() {
+        // Stops in the north, at 20° on either side.
+        let wrap = CableWrap {
+            min: 20.0,
+            max: 340.0,
+        };
+        assert!(wrap.contains(180.0) && wrap.contains(20.0) && !wrap.contains(0.0));
+        assert_close(wrap.margin(30.0), 10.0, 1e-9);
+        assert_close(wrap.margin(350.0), -10.0, 1e-9);
+        assert_eq!(wrap.position(0.0, 30.0), None);
+        // Going from 30° to 330° the short way would cross the stops.
+        assert_eq!(wrap.follow(30.0, 330.0), None);
+        assert_eq!(wrap.position(330.0, 30.0), Some(330.0));
+
+        // Stops in the south, given across north.
+        let wrap = CableWrap {
+            min: -150.0,
+            max: 150.0,
+        };
+        assert!(wrap.contains(0.0) && wrap.contains(210.0) && !wrap.contains(180.0));
+        assert_close(wrap.margin(180.0), -30.0, 1e-9);
+        assert_eq!(wrap.position(210.0, 100.0), Some(-150.0));
+        assert_eq!(wrap.follow(-10.0, 10.0), Some(10.0));
+
+        assert!(CableWrap { min: 10.0, max: 10.0 }.check().is_err());
+        assert!(CableWrap { min: 270.0, max: -270.0 }.check().is_err());
+    }
+
+    #[test]
+    fn spans_more_than_a_turn() {
+        let wrap = CableWrap {
+            min: -270.0,
+            max: 270.0,
+        };
+        assert!(wrap.check().is_ok());
+        // East is reached at 90° and at -270°, the nearest being taken.
+        assert_eq!(wrap.position(90.0, 0.0), Some(90.0));
+        assert_eq!(wrap.position(90.0, -200.0), Some(-270.0));
+        assert_eq!(wrap.position(100.0, -200.0), Some(-260.0));
+        // The margin is that of the position farthest from the stops.
+        assert_close(wrap.margin(100.0), 170.0, 1e-9);
+        assert_close(wrap.margin(180.0), 90.0, 1e-9);
+        assert!(wrap.contains(0.0) && wrap.contains(270.0));
+        // Past 270° the short way crosses the stop, and the mount has to
+        // unwind to -80°.
+        assert_eq!(wrap.follow(260.0, 280.0), None);
+        assert_eq!(wrap.position(280.0, 260.0), Some(-80.0));
+        assert_eq!(wrap.follow(260.0, 265.0), Some(265.0));
+    }
+}
@@ -0,0 +1,421 @@
+//! Importers for local catalog files: bright-star tables such as HYG and
+//! radio source catalogs such as 3C/3CR and NVSS extracts.
+use std::fmt;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::angle;
+use crate::angle::Epoch;
+use crate::astrometry::CatalogPosition;
+
+/// Layout of a catalog file, which tells which columns hold each quantity.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogFormat {
+    /// HYG database: right ascension in decimal hours, visual magnitude,
+    /// spectral type, proper motions and distance.
+    Hyg,
+    /// Third Cambridge catalog (3C or 3CR), with flux densities at 178 MHz.
+    #[serde(rename = "3c")]
+    ThreeC,
+    /// NRAO VLA Sky Survey extract, with flux densities at 1.4 GHz in mJy.
+    Nvss,
+    /// Any table with `name`, `ra`, `dec` and optional `flux` (Jy),
+    /// `frequency` (MHz), `spectral_index`, `mag` and `type` columns.
+    #[default]
+    Csv,
+}
+
+impl CatalogFormat {
+    pub const ALL: [CatalogFormat; 4] = [
+        CatalogFormat::Hyg,
+        CatalogFormat::ThreeC,
+        CatalogFormat::Nvss,
+        CatalogFormat::Csv,
+    ];
+
+    /// Accepted names of the name, right ascension, declination, flux,
+    /// magnitude, type, frequency and spectral index columns, lowercase.
+    fn columns(&self) -> [&'static [&'static str]; 8] {
+        match self {
+            CatalogFormat::Hyg => [
+                &["proper"],
+                &["ra"],
+                &["dec"],
+                &[],
+                &["mag"],
+                &["spect"],
+                &[],
+                &[],
+            ],
+            CatalogFormat::ThreeC => [
+                &["3cr", "3c", "name"],
+                &["rab1950", "ra1950", "_rab1950", "raj2000", "_raj2000", "ra"],
+                &["deb1950", "de1950", "dec1950", "_deb1950", "dej2000", "_dej2000", "dec", "de"],
+                &["s178mhz", "s178", "flux"],
+                &[],
+                &["type", "class", "otype", "id"],
+                &[],
+                &["alpha", "spectral_index", "si"],
+            ],
+            CatalogFormat::Nvss => [
+                &["nvss", "name"],
+                &["raj2000", "_raj2000", "ra"],
+                &["dej2000", "_dej2000", "dec", "de"],
+                &["s1.4", "s1.4ghz", "s1400", "flux"],
+                &[],
+                &["type", "class", "otype"],
+                &[],
+                &["alpha", "spectral_index", "si"],
+            ],
+            CatalogFormat::Csv => [
+                &["name"],
+                &["ra", "raj2000"],
+                &["dec", "dej2000", "de"],
+                &["flux"],
+                &["mag", "magnitude"],
+                &["type", "class"],
+                &["frequency", "freq"],
+                &["spectral_index", "alpha", "si"],
+            ],
+        }
+    }
+
+    /// Factor from the flux densities of the file to janskys.
+    fn flux_scale(&self) -> f64 {
+        match self {
+            CatalogFormat::Nvss => 0.001,
+            _ => 1.0,
+        }
+    }
+
+    /// Frequency of the flux densities of the file, in megahertz, unless
+    /// given by a column.
+    fn frequency(&self) -> Option<f64> {
+        match self {
+            CatalogFormat::ThreeC => Some(178.0),
+            CatalogFormat::Nvss => Some(1400.0),
+            _ => None,
+        }
+    }
+
+    /// Prefix of names given as bare numbers or coordinates.
+    fn name_prefix(&self) -> &'static str {
+        match self {
+            CatalogFormat::ThreeC => "3C ",
+            CatalogFormat::Nvss => "NVSS J",
+            _ => "",
+        }
+    }
+}
+
+impl fmt::Display for CatalogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogFormat::Hyg => write!(f, "HYG"),
+            CatalogFormat::ThreeC => write!(f, "3C/3CR"),
+            CatalogFormat::Nvss => write!(f, "NVSS"),
+            CatalogFormat::Csv => write!(f, "CSV"),
+        }
+    }
+}
+
+/// Kind of astronomical object, which sets how a source is drawn.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceType {
+    Star,
+    Pulsar,
+    SupernovaRemnant,
+    HiiRegion,
+    Galaxy,
+    Quasar,
+    Maser,
+    #[default]
+    Unknown,
+}
+
+impl SourceType {
+    pub const ALL: [SourceType; 8] = [
+        SourceType::Star,
+        SourceType::Pulsar,
+        SourceType::SupernovaRemnant,
+        SourceType::HiiRegion,
+        SourceType::Galaxy,
+        SourceType::Quasar,
+        SourceType::Maser,
+        SourceType::Unknown,
+    ];
+
+    /// Read an object type as written in catalogs (`PSR`, `SNR`, `HII`,
+    /// `G`, `QSO`...), ignoring case. Anything else is unknown.
+    pub fn parse(text: &str) -> SourceType {
+        let text = text.trim().to_lowercase();
+        match text.as_str() {
+            "star" | "*" => SourceType::Star,
+            "pulsar" | "psr" => SourceType::Pulsar,
+            "snr" | "supernova remnant" | "supernova_remnant" => SourceType::SupernovaRemnant,
+            "hii" | "h ii" | "hii region" | "hii_region" | "h2" => SourceType::HiiRegion,
+            "galaxy" | "gal" | "g" | "radio galaxy" | "rg" => SourceType::Galaxy,
+            "quasar" | "qso" | "q" => SourceType::Quasar,
+            "maser" | "mas" => SourceType::Maser,
+            _ => SourceType::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for SourceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SourceType::Star => "Star",
+            SourceType::Pulsar => "Pulsar",
+            SourceType::SupernovaRemnant => "Supernova remnant",
+            SourceType::HiiRegion => "HII region",
+            SourceType::Galaxy => "Galaxy",
+            SourceType::Quasar => "Quasar",
+            SourceType::Maser => "Maser",
+            SourceType::Unknown => "Unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What is known of a source besides its position.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct SourceInfo {
+    /// Flux density at `frequency`, in janskys.
+    pub flux: Option<f64>,
+    /// Reference frequency of `flux`, in megahertz.
+    pub frequency: Option<f64>,
+    /// Spectral index α of the flux density, which goes as ν^α.
+    pub spectral_index: Option<f64>,
+    /// Visual magnitude.
+    pub magnitude: Option<f64>,
+    pub kind: SourceType,
+}
+
+impl SourceInfo {
+    /// Flux density at another frequency, in megahertz, extrapolated along
+    /// the spectral index. Without a spectral index or a reference
+    /// frequency the spectrum is taken to be flat.
+    pub fn flux_at(&self, frequency: f64) -> Option<f64> {
+        let flux = self.flux?;
+        match (self.frequency, self.spectral_index) {
+            (Some(reference), Some(index)) if reference > 0.0 => Some(flux * (frequency / reference).powf(index)),
+            _ => Some(flux),
+        }
+    }
+}
+
+/// An object of an imported catalog.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CatalogSource {
+    pub name: String,
+    pub position: CatalogPosition,
+    pub info: SourceInfo,
+    /// Object type or spectral type, as written in the catalog.
+    pub kind: Option<String>,
+}
+
+/// Sources read from a catalog file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Catalog {
+    pub sources: Vec<CatalogSource>,
+    /// Rows that could not be read, such as unit lines of VizieR tables.
+    pub skipped: usize,
+}
+
+/// Split a line on the delimiter, without the quotes around fields.
+fn split_fields(line: &str, delimiter: char) -> Vec<&str> {
+    line.split(delimiter)
+        .map(|field| field.trim().trim_matches('"').trim())
+        .collect()
+}
+
+impl Catalog {
+    /// Read a catalog file, separated by commas, tabs, semicolons or `|`
+    /// with a header line naming the columns. Lines starting with `#` are
+    /// ignored, and so are rows that cannot be read, which are counted in
+    /// `skipped`.
+    pub fn parse(format: CatalogFormat, content: &str) -> Result<Catalog, String> {
+        let mut lines = content
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+        let header = lines.next().ok_or("empty catalog file")?;
+        let delimiter = [',', '\t', ';', '|']
+            .into_iter()
+            .max_by_key(|delimiter| header.matches(*delimiter).count())
+            .unwrap();
+        let names: Vec<String> = split_fields(header, delimiter)
+            .iter()
+            .map(|name| name.to_lowercase())
+            .collect();
+
+        let [name, ra, dec, flux, magnitude, kind, frequency, spectral_index] = format
+            .columns()
+            .map(|aliases| aliases.iter().find_map(|alias| names.iter().position(|name| name == alias)));
+        let ra = ra.ok_or("missing right ascension column")?;
+        let dec = dec.ok_or("missing declination column")?;
+        let epoch = if names[ra].contains("1950") {
+            Epoch::Besselian(1950.0)
+        } else {
+            Epoch::default()
+        };
+        let column = |alias: &str| names.iter().position(|name| name == alias);
+        let (pm_ra, pm_dec, distance) = (column("pmra"), column("pmdec"), column("dist"));
+        let fallback_names = [("bf", ""), ("hip", "HIP "), ("hd", "HD "), ("id", "HYG ")]
+            .map(|(alias, prefix)| (column(alias), prefix));
+
+        let mut catalog = Catalog::default();
+        for (index, line) in lines.enumerate() {
+            let fields = split_fields(line, delimiter);
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| fields.get(column).copied())
+                    .filter(|field| !field.is_empty())
+            };
+            let number = |column: Option<usize>| field(column).and_then(|field| field.parse::<f64>().ok());
+
+            let position = match format {
+                // HYG gives decimal hours, and the Sun at distance 0.
+                CatalogFormat::Hyg => {
+                    let (Some(hours), Some(dec)) = (number(Some(ra)), number(Some(dec))) else {
+                        catalog.skipped += 1;
+                        continue;
+                    };
+                    let distance = number(distance).unwrap_or(0.0);
+                    if distance <= 0.0 {
+                        continue;
+                    }
+                    CatalogPosition {
+                        ra: hours * 15.0,
+                        dec,
+                        epoch,
+                        pm_ra: number(pm_ra).unwrap_or(0.0),
+                        pm_dec: number(pm_dec).unwrap_or(0.0),
+                        // HYG marks unknown distances with 100000 pc.
+                        parallax: if distance < 100_000.0 { 1000.0 / distance } else { 0.0 },
+                    }
+                },
+                _ => {
+                    let ra = field(Some(ra)).map(angle::parse_right_ascension);
+                    let dec = field(Some(dec)).map(angle::parse_declination);
+                    let (Some(Ok(ra)), Some(Ok(dec))) = (ra, dec) else {
+                        catalog.skipped += 1;
+                        continue;
+                    };
+                    CatalogPosition {
+                        epoch,
+                        ..CatalogPosition::j2000(ra, dec)
+                    }
+                },
+            };
+
+            let name = match field(name) {
+                Some(name) if name.parse::<f64>().is_ok() || name.starts_with(|c: char| c.is_ascii_digit()) => {
+                    format!("{}{}", format.name_prefix(), name)
+                },
+                Some(name) => String::from(name),
+                None => fallback_names
+                    .iter()
+                    .find_map(|(column, prefix)| field(*column).map(|name| format!("{}{}", prefix, name)))
+                    .unwrap_or_else(|| format!("Row {}", index + 1)),
+            };
+            let kind = field(kind).map(String::from);
+            catalog.sources.push(CatalogSource {
+                name,
+                position,
+                info: SourceInfo {
+                    flux: number(flux).map(|flux| flux * format.flux_scale()),
+                    frequency: number(frequency).or(format.frequency()),
+                    spectral_index: number(spectral_index),
+                    magnitude: number(magnitude),
+                    // HYG types are spectral types.
+                    kind: match format {
+                        CatalogFormat::Hyg => SourceType::Star,
+                        _ => kind.as_deref().map_or(SourceType::Unknown, SourceType::parse),
+                    },
+                },
+                kind,
+            });
+        }
+        if catalog.sources.is_empty() && catalog.skipped > 0 {
+            return Err(format!("none of the {} rows could be read", catalog.skipped));
+        }
+        Ok(catalog)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hyg_table() {
+        let catalog = Catalog::parse(CatalogFormat::Hyg, "\
+\"id\",\"hip\",\"hd\",\"hr\",\"gl\",\"bf\",\"proper\",\"ra\",\"dec\",\"dist\",\"pmra\",\"pmdec\",\"rv\",\"mag\",\"absmag\",\"spect\"
+0,,,,,,Sol,0.000000,0.000000,0.0000,0.00,0.00,0.0,-26.700,4.850,G2V
+32263,32349,48915,2491,Gl 244A,9Alp CMa,Sirius,6.752481,-16.716116,2.6371,-546.01,-1223.08,-9.0,-1.440,1.454,A0m...
+27919,27989,39801,2061,Gl 222,58Alp Ori,Betelgeuse,5.919529,7.407063,152.6718,27.33,10.86,21.0,0.450,-5.469,M2Ib
+7,7,225003,,,,,0.001500,-19.498837,134.9528,-5.47,-4.21,,9.270,3.619,F0
+").unwrap();
+        assert_eq!(catalog.sources.len(), 3);
+        let sirius = &catalog.sources[0];
+        assert_eq!(sirius.name, "Sirius");
+        assert!((sirius.position.ra - 101.287_215).abs() < 1e-6);
+        assert!((sirius.position.parallax - 379.2).abs() < 0.1);
+        assert_eq!(sirius.info.magnitude, Some(-1.44));
+        assert_eq!(sirius.info.kind, SourceType::Star);
+        assert_eq!(sirius.kind.as_deref(), Some("A0m..."));
+        assert_eq!(catalog.sources[2].name, "HIP 7");
+    }
+
+    #[test]
+    fn parses_radio_catalogs() {
+        let catalog = Catalog::parse(CatalogFormat::ThreeC, "\
+# VizieR extract of 3CR
+3CR\tRA1950\tDE1950\tS178MHz\tType
+\th m s\td m s\tJy\t
+273\t12 26 33.3\t+02 19 43\t66.8\tQSO
+461\t23 21 07\t+58 32 48\t11900\tSNR
+").unwrap();
+        assert_eq!(catalog.skipped, 1);
+        assert_eq!(catalog.sources[0].name, "3C 273");
+        assert_eq!(catalog.sources[0].position.epoch, Epoch::Besselian(1950.0));
+        assert_eq!(catalog.sources[1].info.flux, Some(11900.0));
+        assert_eq!(catalog.sources[1].info.frequency, Some(178.0));
+        assert_eq!(catalog.sources[1].info.kind, SourceType::SupernovaRemnant);
+        assert_eq!(catalog.sources[0].info.kind, SourceType::Quasar);
+
+        let catalog = Catalog::parse(CatalogFormat::Nvss, "\
+NVSS,RAJ2000,DEJ2000,S1.4
+122906+020308,12 29 06.41,+02 03 08.6,54991.2
+").unwrap();
+        assert_eq!(catalog.sources[0].name, "NVSS J122906+020308");
+        assert!((catalog.sources[0].info.flux.unwrap() - 54.9912).abs() < 1e-9);
+
+        assert!(Catalog::parse(CatalogFormat::Csv, "name,flux\nA,1\n").is_err());
+    }
+
+    #[test]
+    fn extrapolates_flux_along_spectral_index() {
+        // Cassiopeia A, about 11000 Jy at 178 MHz with α = -0.77.
+        let info = SourceInfo {
+            flux: Some(11_000.0),
+            frequency: Some(178.0),
+            spectral_index: Some(-0.77),
+            ..SourceInfo::default()
+        };
+        assert!((info.flux_at(1420.0).unwrap() - 2223.0).abs() < 1.0);
+        let flat = SourceInfo {
+            spectral_index: None,
+            ..info
+        };
+        assert_eq!(flat.flux_at(1420.0), Some(11_000.0));
+        assert_eq!(SourceType::parse(" PSR "), SourceType::Pulsar);
+    }
+}
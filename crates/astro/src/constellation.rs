@@ -0,0 +1,223 @@
+//! Constellation stick figures and IAU constellation boundaries, bundled
+//! with the crate. Boundaries can also be read from the CDS catalogue VI/49.
+use crate::angle;
+
+/// Stick figures of the 88 constellations.
+const BUNDLED_FIGURES: &str = include_str!("../data/constellations.txt");
+
+/// Outlines of the 88 constellations, in the format of the CDS catalogue
+/// VI/49.
+const BUNDLED_BOUNDARIES: &str = include_str!("../data/boundaries.txt");
+
+/// A constellation and the lines of its stick figure.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constellation {
+    /// IAU abbreviation, such as `Ori`.
+    pub abbreviation: String,
+    pub name: String,
+    /// Polylines through the J2000.0 right ascension and declination of the
+    /// stars of the figure, in degrees.
+    pub figure: Vec<Vec<(f64, f64)>>,
+}
+
+impl Constellation {
+    /// J2000.0 right ascension and declination, in degrees, of the centre of
+    /// the stars of the figure, where its name is written.
+    pub fn label_position(&self) -> (f64, f64) {
+        let mut sum = [0.0; 3];
+        for (ra, dec) in self.figure.iter().flatten() {
+            let (sin_ra, cos_ra) = ra.to_radians().sin_cos();
+            let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+            sum[0] += cos_dec * cos_ra;
+            sum[1] += cos_dec * sin_ra;
+            sum[2] += sin_dec;
+        }
+        let [x, y, z] = sum;
+        (y.atan2(x).to_degrees().rem_euclid(360.0), z.atan2(x.hypot(y)).to_degrees())
+    }
+}
+
+/// The stick figures bundled with the crate.
+pub fn bundled_figures() -> Vec<Constellation> {
+    parse_figures(BUNDLED_FIGURES).expect("The bundled constellation figures are valid.")
+}
+
+/// Parse stick figures. A line starting with a letter opens a constellation
+/// with its abbreviation and name, and each indented line below is a
+/// polyline of comma separated sexagesimal right ascension and declination
+/// pairs. Lines starting with `#` are comments.
+pub fn parse_figures(text: &str) -> Result<Vec<Constellation>, String> {
+    let mut constellations: Vec<Constellation> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            let (abbreviation, name) = content
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("Line {}: missing constellation name", number + 1))?;
+            constellations.push(Constellation {
+                abbreviation: String::from(abbreviation),
+                name: String::from(name.trim()),
+                figure: Vec::new(),
+            });
+            continue;
+        }
+
+        let constellation = constellations
+            .last_mut()
+            .ok_or_else(|| format!("Line {}: figure line before any constellation", number + 1))?;
+        let polyline = content
+            .split(',')
+            .map(|point| {
+                let mut fields = point.split_whitespace();
+                match (fields.next(), fields.next(), fields.next()) {
+                    (Some(ra), Some(dec), None) => Ok((
+                        angle::parse_right_ascension(ra)?,
+                        angle::parse_declination(dec)?,
+                    )),
+                    _ => Err(format!("expected a right ascension and a declination, found \"{}\"", point.trim())),
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+        if polyline.len() < 2 {
+            return Err(format!("Line {}: a figure line needs at least two stars", number + 1));
+        }
+        constellation.figure.push(polyline);
+    }
+    Ok(constellations)
+}
+
+/// Outline of a constellation, closed back to its first point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Boundary {
+    /// Abbreviation as written in the boundary file, in upper case, with
+    /// the two parts of Serpens apart.
+    pub abbreviation: String,
+    /// J2000.0 right ascension and declination of the vertices, in degrees.
+    pub points: Vec<(f64, f64)>,
+}
+
+/// The constellation boundaries bundled with the crate.
+pub fn bundled_boundaries() -> Vec<Boundary> {
+    parse_boundaries(BUNDLED_BOUNDARIES).expect("The bundled constellation boundaries are valid.")
+}
+
+/// Parse the J2000.0 constellation boundaries of the CDS catalogue VI/49
+/// (`bound_20.dat`): one vertex per line, with the right ascension in
+/// decimal hours, the declination in decimal degrees and the constellation
+/// abbreviation, optionally followed by the type of the vertex. Consecutive
+/// vertices of the same constellation form its outline.
+pub fn parse_boundaries(text: &str) -> Result<Vec<Boundary>, String> {
+    let mut boundaries: Vec<Boundary> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() || fields[0].starts_with('#') {
+            continue;
+        }
+        if fields.len() < 3 {
+            return Err(format!("Line {}: expected right ascension, declination and constellation", number + 1));
+        }
+        let ra: f64 = fields[0]
+            .parse()
+            .map_err(|_| format!("Line {}: invalid right ascension \"{}\"", number + 1, fields[0]))?;
+        let dec: f64 = fields[1]
+            .parse()
+            .map_err(|_| format!("Line {}: invalid declination \"{}\"", number + 1, fields[1]))?;
+        if !(0.0..=24.0).contains(&ra) || !(-90.0..=90.0).contains(&dec) {
+            return Err(format!("Line {}: coordinates out of range", number + 1));
+        }
+
+        let abbreviation = fields[2].to_uppercase();
+        match boundaries.last_mut() {
+            Some(boundary) if boundary.abbreviation == abbreviation => boundary.points.push((ra * 15.0, dec)),
+            _ => boundaries.push(Boundary {
+                abbreviation,
+                points: vec![(ra * 15.0, dec)],
+            }),
+        }
+    }
+    Ok(boundaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_figures_parse() {
+        let constellations = bundled_figures();
+        let orion = constellations
+            .iter()
+            .find(|constellation| constellation.abbreviation == "Ori")
+            .expect("Orion is bundled");
+        assert_eq!(orion.name, "Orion");
+        assert_eq!(orion.figure.len(), 3);
+        // Betelgeuse, the second star of the first line.
+        let (ra, dec) = orion.figure[0][1];
+        assert!((ra - 88.7917).abs() < 0.01 && (dec - 7.4069).abs() < 0.01);
+
+        // The figure of Orion straddles the equator around 5h30m.
+        let (ra, dec) = orion.label_position();
+        assert!((ra - 83.0).abs() < 2.0 && dec.abs() < 2.0, "{} {}", ra, dec);
+
+        assert_eq!(constellations.len(), 88);
+        for constellation in &constellations {
+            assert!(!constellation.figure.is_empty(), "{} has no figure", constellation.name);
+        }
+    }
+
+    #[test]
+    fn bundled_boundaries_parse() {
+        let boundaries = bundled_boundaries();
+        // Serpens is in two parts.
+        assert_eq!(boundaries.len(), 89);
+        let mut abbreviations: Vec<&str> = boundaries
+            .iter()
+            .map(|boundary| boundary.abbreviation.trim_end_matches(['1', '2']))
+            .collect();
+        abbreviations.dedup();
+        assert_eq!(abbreviations.len(), 88);
+
+        // Every figure has a boundary of the same abbreviation.
+        for constellation in bundled_figures() {
+            let abbreviation = constellation.abbreviation.to_uppercase();
+            assert!(abbreviations.contains(&abbreviation.as_str()), "no boundary for {}", abbreviation);
+        }
+
+        // The southern edge of Orion runs along -11° of B1875.0 from 5h05m
+        // to 5h50m, drawn a few arcminutes off once precessed to J2000.0.
+        let orion = boundaries.iter().find(|boundary| boundary.abbreviation == "ORI").unwrap();
+        let lowest = orion.points.iter().map(|(_, dec)| *dec).fold(f64::INFINITY, f64::min);
+        assert!((lowest + 11.0).abs() < 0.1, "{}", lowest);
+    }
+
+    #[test]
+    fn figure_errors() {
+        assert!(parse_figures("    05:00:00 +10:00:00, 06:00:00 +10:00:00").is_err());
+        assert!(parse_figures("Ori Orion\n    05:00:00 +10:00:00").is_err());
+        assert!(parse_figures("Ori Orion\n    05:00:00, 06:00:00 +10:00:00").is_err());
+    }
+
+    #[test]
+    fn boundaries() {
+        let text = "\
+            22.8666667 +35.1666667 AND O\n\
+            22.8666667 +52.5000000 AND O\n\
+            23.0000000 +52.5000000 AND I\n\
+            \n\
+            5.0000000 -11.0000000 LEP O\n\
+            6.0000000 -11.0000000 LEP O\n";
+        let boundaries = parse_boundaries(text).unwrap();
+        assert_eq!(boundaries.len(), 2);
+        assert_eq!(boundaries[0].abbreviation, "AND");
+        assert_eq!(boundaries[0].points.len(), 3);
+        assert!((boundaries[0].points[2].0 - 345.0).abs() < 1e-9);
+        assert_eq!(boundaries[1].points, vec![(75.0, -11.0), (90.0, -11.0)]);
+
+        assert!(parse_boundaries("25.0 +10.0 AND").is_err());
+        assert!(parse_boundaries("1.0 AND").is_err());
+    }
+}
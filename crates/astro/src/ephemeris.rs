@@ -0,0 +1,472 @@
+//! Positions of the Sun, the Moon and the major planets.
+//!
+//! The Sun follows Meeus chapter 25 (about 0.01°) and the Moon the truncated
+//! ELP-2000/82 series of Meeus chapter 47 (about 10"). The planets use the
+//! truncated VSOP87 series of Meeus chapter 32, good to a few arcseconds.
+use std::fmt;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::astrometry::ApparentFrame;
+use crate::astrometry::CatalogPosition;
+use crate::astrometry::precess_ecliptic;
+use crate::astrometry::spherical;
+use crate::time;
+use crate::vsop87;
+
+/// Light travel time over one astronomical unit, in days.
+const LIGHT_TIME_PER_AU: f64 = 0.005_775_518_3;
+
+/// Kilometres in an astronomical unit.
+pub const ASTRONOMICAL_UNIT: f64 = 149_597_870.7;
+
+/// Equatorial radius of the Earth, in kilometres.
+pub const EARTH_RADIUS: f64 = 6378.14;
+
+/// Obliquity of the ecliptic at J2000.0, in degrees.
+const J2000_OBLIQUITY: f64 = 23.439_28;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Body {
+    Sun,
+    Moon,
+    Mercury,
+    Venus,
+    Mars,
+    Jupiter,
+    Saturn,
+    Uranus,
+    Neptune,
+}
+
+impl Body {
+    pub const ALL: [Body; 9] = [
+        Body::Sun,
+        Body::Moon,
+        Body::Mercury,
+        Body::Venus,
+        Body::Mars,
+        Body::Jupiter,
+        Body::Saturn,
+        Body::Uranus,
+        Body::Neptune,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Body::Sun => "Sun",
+            Body::Moon => "Moon",
+            Body::Mercury => "Mercury",
+            Body::Venus => "Venus",
+            Body::Mars => "Mars",
+            Body::Jupiter => "Jupiter",
+            Body::Saturn => "Saturn",
+            Body::Uranus => "Uranus",
+            Body::Neptune => "Neptune",
+        }
+    }
+
+    /// Find a body by its name, ignoring case.
+    pub fn parse(name: &str) -> Option<Body> {
+        Body::ALL.into_iter().find(|body| body.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Apparent geocentric position of the body at the instant of `frame`.
+    pub fn apparent_position(&self, frame: &ApparentFrame) -> BodyPosition {
+        match self {
+            Body::Sun => sun_apparent_position(frame),
+            Body::Moon => moon_apparent_position(frame),
+            planet => planet_apparent_position(planet, frame),
+        }
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Apparent geocentric place of a solar system body.
+#[derive(Clone, Copy, Debug)]
+pub struct BodyPosition {
+    /// Apparent right ascension, in degrees.
+    pub ra: f64,
+    /// Apparent declination, in degrees.
+    pub dec: f64,
+    /// Distance from the centre of the Earth, in astronomical units.
+    pub distance: f64,
+}
+
+impl BodyPosition {
+    /// Equatorial horizontal parallax, in degrees.
+    pub fn horizontal_parallax(&self) -> f64 {
+        (EARTH_RADIUS / (self.distance * ASTRONOMICAL_UNIT)).asin().to_degrees()
+    }
+}
+
+/// Right ascension and declination, in degrees, of ecliptic coordinates.
+pub fn ecliptic_to_equatorial(longitude: f64, latitude: f64, obliquity: f64) -> (f64, f64) {
+    let (sin_longitude, cos_longitude) = longitude.to_radians().sin_cos();
+    let (sin_latitude, cos_latitude) = latitude.to_radians().sin_cos();
+    let (sin_obliquity, cos_obliquity) = obliquity.to_radians().sin_cos();
+    spherical([
+        cos_latitude * cos_longitude,
+        cos_latitude * sin_longitude * cos_obliquity - sin_latitude * sin_obliquity,
+        cos_latitude * sin_longitude * sin_obliquity + sin_latitude * cos_obliquity,
+    ])
+}
+
+/// Meeus chapter 25, low accuracy.
+fn sun_apparent_position(frame: &ApparentFrame) -> BodyPosition {
+    // Constant of aberration divided by the distance, in degrees.
+    let aberration = -20.4898 / 3600.0 / frame.sun_distance;
+    let longitude = frame.sun_longitude + frame.nutation_longitude + aberration;
+    let (ra, dec) = ecliptic_to_equatorial(longitude, 0.0, frame.obliquity);
+    BodyPosition {
+        ra,
+        dec,
+        distance: frame.sun_distance,
+    }
+}
+
+/// Multiples of D, M, M' and F, and coefficients of the longitude, in 10⁻⁶
+/// degrees, and of the distance, in metres, from Meeus table 47.A.
+const MOON_LONGITUDE_DISTANCE_TERMS: [(i8, i8, i8, i8, f64, f64); 60] = [
+    (0, 0, 1, 0, 6_288_774.0, -20_905_355.0),
+    (2, 0, -1, 0, 1_274_027.0, -3_699_111.0),
+    (2, 0, 0, 0, 658_314.0, -2_955_968.0),
+    (0, 0, 2, 0, 213_618.0, -569_925.0),
+    (0, 1, 0, 0, -185_116.0, 48_888.0),
+    (0, 0, 0, 2, -114_332.0, -3_149.0),
+    (2, 0, -2, 0, 58_793.0, 246_158.0),
+    (2, -1, -1, 0, 57_066.0, -152_138.0),
+    (2, 0, 1, 0, 53_322.0, -170_733.0),
+    (2, -1, 0, 0, 45_758.0, -204_586.0),
+    (0, 1, -1, 0, -40_923.0, -129_620.0),
+    (1, 0, 0, 0, -34_720.0, 108_743.0),
+    (0, 1, 1, 0, -30_383.0, 104_755.0),
+    (2, 0, 0, -2, 15_327.0, 10_321.0),
+    (0, 0, 1, 2, -12_528.0, 0.0),
+    (0, 0, 1, -2, 10_980.0, 79_661.0),
+    (4, 0, -1, 0, 10_675.0, -34_782.0),
+    (0, 0, 3, 0, 10_034.0, -23_210.0),
+    (4, 0, -2, 0, 8_548.0, -21_636.0),
+    (2, 1, -1, 0, -7_888.0, 24_208.0),
+    (2, 1, 0, 0, -6_766.0, 30_824.0),
+    (1, 0, -1, 0, -5_163.0, -8_379.0),
+    (1, 1, 0, 0, 4_987.0, -16_675.0),
+    (2, -1, 1, 0, 4_036.0, -12_831.0),
+    (2, 0, 2, 0, 3_994.0, -10_445.0),
+    (4, 0, 0, 0, 3_861.0, -11_650.0),
+    (2, 0, -3, 0, 3_665.0, 14_403.0),
+    (0, 1, -2, 0, -2_689.0, -7_003.0),
+    (2, 0, -1, 2, -2_602.0, 0.0),
+    (2, -1, -2, 0, 2_390.0, 10_056.0),
+    (1, 0, 1, 0, -2_348.0, 6_322.0),
+    (2, -2, 0, 0, 2_236.0, -9_884.0),
+    (0, 1, 2, 0, -2_120.0, 5_751.0),
+    (0, 2, 0, 0, -2_069.0, 0.0),
+    (2, -2, -1, 0, 2_048.0, -4_950.0),
+    (2, 0, 1, -2, -1_773.0, 4_130.0),
+    (2, 0, 0, 2, -1_595.0, 0.0),
+    (4, -1, -1, 0, 1_215.0, -3_958.0),
+    (0, 0, 2, 2, -1_110.0, 0.0),
+    (3, 0, -1, 0, -892.0, 3_258.0),
+    (2, 1, 1, 0, -810.0, 2_616.0),
+    (4, -1, -2, 0, 759.0, -1_897.0),
+    (0, 2, -1, 0, -713.0, -2_117.0),
+    (2, 2, -1, 0, -700.0, 2_354.0),
+    (2, 1, -2, 0, 691.0, 0.0),
+    (2, -1, 0, -2, 596.0, 0.0),
+    (4, 0, 1, 0, 549.0, -1_423.0),
+    (0, 0, 4, 0, 537.0, -1_117.0),
+    (4, -1, 0, 0, 520.0, -1_571.0),
+    (1, 0, -2, 0, -487.0, -1_739.0),
+    (2, 1, 0, -2, -399.0, 0.0),
+    (0, 0, 2, -2, -381.0, -4_421.0),
+    (1, 1, 1, 0, 351.0, 0.0),
+    (3, 0, -2, 0, -340.0, 0.0),
+    (4, 0, -3, 0, 330.0, 0.0),
+    (2, -1, 2, 0, 327.0, 0.0),
+    (0, 2, 1, 0, -323.0, 1_165.0),
+    (1, 1, -1, 0, 299.0, 0.0),
+    (2, 0, 3, 0, 294.0, 0.0),
+    (2, 0, -1, -2, 0.0, 8_752.0),
+];
+
+/// Multiples of D, M, M' and F, and coefficients of the latitude, in 10⁻⁶
+/// degrees, from Meeus table 47.B.
+const MOON_LATITUDE_TERMS: [(i8, i8, i8, i8, f64); 60] = [
+    (0, 0, 0, 1, 5_128_122.0),
+    (0, 0, 1, 1, 280_602.0),
+    (0, 0, 1, -1, 277_693.0),
+    (2, 0, 0, -1, 173_237.0),
+    (2, 0, -1, 1, 55_413.0),
+    (2, 0, -1, -1, 46_271.0),
+    (2, 0, 0, 1, 32_573.0),
+    (0, 0, 2, 1, 17_198.0),
+    (2, 0, 1, -1, 9_266.0),
+    (0, 0, 2, -1, 8_822.0),
+    (2, -1, 0, -1, 8_216.0),
+    (2, 0, -2, -1, 4_324.0),
+    (2, 0, 1, 1, 4_200.0),
+    (2, 1, 0, -1, -3_359.0),
+    (2, -1, -1, 1, 2_463.0),
+    (2, -1, 0, 1, 2_211.0),
+    (2, -1, -1, -1, 2_065.0),
+    (0, 1, -1, -1, -1_870.0),
+    (4, 0, -1, -1, 1_828.0),
+    (0, 1, 0, 1, -1_794.0),
+    (0, 0, 0, 3, -1_749.0),
+    (0, 1, -1, 1, -1_565.0),
+    (1, 0, 0, 1, -1_491.0),
+    (0, 1, 1, 1, -1_475.0),
+    (0, 1, 1, -1, -1_410.0),
+    (0, 1, 0, -1, -1_344.0),
+    (1, 0, 0, -1, -1_335.0),
+    (0, 0, 3, 1, 1_107.0),
+    (4, 0, 0, -1, 1_021.0),
+    (4, 0, -1, 1, 833.0),
+    (0, 0, 1, -3, 777.0),
+    (4, 0, -2, 1, 671.0),
+    (2, 0, 0, -3, 607.0),
+    (2, 0, 2, -1, 596.0),
+    (2, -1, 1, -1, 491.0),
+    (2, 0, -2, 1, -451.0),
+    (0, 0, 3, -1, 439.0),
+    (2, 0, 2, 1, 422.0),
+    (2, 0, -3, -1, 421.0),
+    (2, 1, -1, 1, -366.0),
+    (2, 1, 0, 1, -351.0),
+    (4, 0, 0, 1, 331.0),
+    (2, -1, 1, 1, 315.0),
+    (2, -2, 0, -1, 302.0),
+    (0, 0, 1, 3, -283.0),
+    (2, 1, 1, -1, -229.0),
+    (1, 1, 0, -1, 223.0),
+    (1, 1, 0, 1, 223.0),
+    (0, 1, -2, -1, -220.0),
+    (2, 1, -1, -1, -220.0),
+    (1, 0, 1, 1, -185.0),
+    (2, -1, -2, -1, 181.0),
+    (0, 1, 2, 1, -177.0),
+    (4, 0, -2, -1, 176.0),
+    (4, -1, -1, -1, 166.0),
+    (1, 0, 1, -1, -164.0),
+    (4, 0, 1, -1, 132.0),
+    (1, 0, -1, -1, -119.0),
+    (4, -1, 0, -1, 115.0),
+    (2, -2, 0, 1, 107.0),
+];
+
+/// Geocentric ecliptic longitude and latitude, in degrees, referred to the
+/// mean equinox of date, and distance, in kilometres, of the Moon (Meeus
+/// chapter 47).
+pub fn moon_ecliptic_position(julian_date: f64) -> (f64, f64, f64) {
+    let t = time::julian_centuries(julian_date);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+
+    let mean_longitude = 218.316_447_7 + 481_267.881_234_21 * t - 0.001_578_6 * t2
+        + t3 / 538_841.0 - t4 / 65_194_000.0;
+    let elongation = 297.850_192_1 + 445_267.111_403_4 * t - 0.001_881_9 * t2
+        + t3 / 545_868.0 - t4 / 113_065_000.0;
+    let sun_anomaly = 357.529_109_2 + 35_999.050_290_9 * t - 0.000_153_6 * t2
+        + t3 / 24_490_000.0;
+    let moon_anomaly = 134.963_396_4 + 477_198.867_505_5 * t + 0.008_741_4 * t2
+        + t3 / 69_699.0 - t4 / 14_712_000.0;
+    let latitude_argument = 93.272_095 + 483_202.017_523_3 * t - 0.003_653_9 * t2
+        - t3 / 3_526_000.0 + t4 / 863_310_000.0;
+    let a1 = (119.75 + 131.849 * t).to_radians();
+    let a2 = (53.09 + 479_264.29 * t).to_radians();
+    let a3 = (313.45 + 481_266.484 * t).to_radians();
+    // Decreasing eccentricity of the Earth's orbit.
+    let e = 1.0 - 0.002_516 * t - 0.000_007_4 * t2;
+
+    let argument = |d: i8, m: i8, mp: i8, f: i8| -> (f64, f64) {
+        let angle = d as f64 * elongation + m as f64 * sun_anomaly
+            + mp as f64 * moon_anomaly + f as f64 * latitude_argument;
+        (angle.to_radians(), e.powi(m.abs() as i32))
+    };
+
+    let mut longitude = 0.0;
+    let mut distance = 0.0;
+    for (d, m, mp, f, longitude_term, distance_term) in MOON_LONGITUDE_DISTANCE_TERMS {
+        let (angle, factor) = argument(d, m, mp, f);
+        longitude += longitude_term * factor * angle.sin();
+        distance += distance_term * factor * angle.cos();
+    }
+    let mut latitude = 0.0;
+    for (d, m, mp, f, latitude_term) in MOON_LATITUDE_TERMS {
+        let (angle, factor) = argument(d, m, mp, f);
+        latitude += latitude_term * factor * angle.sin();
+    }
+
+    let l = mean_longitude.to_radians();
+    let f = latitude_argument.to_radians();
+    let mp = moon_anomaly.to_radians();
+    longitude += 3958.0 * a1.sin() + 1962.0 * (l - f).sin() + 318.0 * a2.sin();
+    latitude += -2235.0 * l.sin() + 382.0 * a3.sin() + 175.0 * (a1 - f).sin()
+        + 175.0 * (a1 + f).sin() + 127.0 * (l - mp).sin() - 115.0 * (l + mp).sin();
+
+    (
+        (mean_longitude + longitude / 1e6).rem_euclid(360.0),
+        latitude / 1e6,
+        385_000.56 + distance / 1000.0,
+    )
+}
+
+fn moon_apparent_position(frame: &ApparentFrame) -> BodyPosition {
+    let (longitude, latitude, distance) = moon_ecliptic_position(frame.julian_date);
+    // The series already gives the place as seen from the Earth, so only
+    // nutation is left.
+    let (ra, dec) = ecliptic_to_equatorial(
+        longitude + frame.nutation_longitude,
+        latitude,
+        frame.obliquity,
+    );
+    BodyPosition {
+        ra,
+        dec,
+        distance: distance / ASTRONOMICAL_UNIT,
+    }
+}
+
+/// Solve Kepler's equation for the eccentric anomaly, in radians, given a
+/// mean anomaly between -π and π.
+pub fn eccentric_anomaly(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    // Danby's starting value converges for eccentricities close to 1 too.
+    let mut anomaly = mean_anomaly + 0.85 * eccentricity * mean_anomaly.sin().signum();
+    for _ in 0..100 {
+        let delta = (anomaly - eccentricity * anomaly.sin() - mean_anomaly)
+            / (1.0 - eccentricity * anomaly.cos());
+        anomaly -= delta;
+        if delta.abs() < 1e-12 {
+            break;
+        }
+    }
+    anomaly
+}
+
+/// Rectangular heliocentric coordinates, in astronomical units, of a point
+/// of an orbit given its position in the orbital plane, with the x axis
+/// towards perihelion, and the orientation of the orbit in degrees.
+pub fn orbit_to_ecliptic(
+    x: f64,
+    y: f64,
+    perihelion_argument: f64,
+    node: f64,
+    inclination: f64,
+) -> [f64; 3] {
+    let (sin_w, cos_w) = perihelion_argument.to_radians().sin_cos();
+    let (sin_node, cos_node) = node.to_radians().sin_cos();
+    let (sin_i, cos_i) = inclination.to_radians().sin_cos();
+    [
+        (cos_w * cos_node - sin_w * sin_node * cos_i) * x
+            + (-sin_w * cos_node - cos_w * sin_node * cos_i) * y,
+        (cos_w * sin_node + sin_w * cos_node * cos_i) * x
+            + (-sin_w * sin_node + cos_w * cos_node * cos_i) * y,
+        sin_w * sin_i * x + cos_w * sin_i * y,
+    ]
+}
+
+/// Rotate rectangular coordinates from the ecliptic to the equator of
+/// J2000.0.
+pub fn ecliptic_to_equatorial_j2000(vector: [f64; 3]) -> [f64; 3] {
+    let (sin_obliquity, cos_obliquity) = J2000_OBLIQUITY.to_radians().sin_cos();
+    [
+        vector[0],
+        vector[1] * cos_obliquity - vector[2] * sin_obliquity,
+        vector[1] * sin_obliquity + vector[2] * cos_obliquity,
+    ]
+}
+
+/// Heliocentric rectangular coordinates, in astronomical units, referred to
+/// the ecliptic and equinox of J2000.0, of a planet at a Julian date in TT.
+/// The Sun and the Moon give the Earth.
+fn heliocentric_position(body: &Body, julian_date: f64) -> [f64; 3] {
+    let (longitude, latitude, distance) = vsop87::heliocentric_position(body, julian_date);
+    let (longitude, latitude) = precess_ecliptic(longitude, latitude, julian_date, time::J2000);
+    let (sin_longitude, cos_longitude) = longitude.to_radians().sin_cos();
+    let (sin_latitude, cos_latitude) = latitude.to_radians().sin_cos();
+    [
+        distance * cos_latitude * cos_longitude,
+        distance * cos_latitude * sin_longitude,
+        distance * sin_latitude,
+    ]
+}
+
+/// Astrometric J2000.0 place of an object given a function returning its
+/// heliocentric ecliptic J2000.0 coordinates at a Julian date in TT,
+/// corrected for light time. Annual aberration and nutation are then applied
+/// by `frame` as for a star.
+pub fn apparent_from_heliocentric(
+    frame: &ApparentFrame,
+    heliocentric: impl Fn(f64) -> [f64; 3],
+) -> BodyPosition {
+    let earth = heliocentric_position(&Body::Sun, frame.julian_date);
+    let mut geocentric = [0.0; 3];
+    let mut distance = 0.0;
+    let mut light_time = 0.0;
+    for _ in 0..3 {
+        let object = heliocentric(frame.julian_date - light_time);
+        geocentric = [object[0] - earth[0], object[1] - earth[1], object[2] - earth[2]];
+        distance = geocentric.iter().map(|x| x * x).sum::<f64>().sqrt();
+        light_time = distance * LIGHT_TIME_PER_AU;
+    }
+
+    let (ra, dec) = spherical(ecliptic_to_equatorial_j2000(geocentric));
+    let (ra, dec) = frame.apparent_place(&CatalogPosition::j2000(ra, dec));
+    BodyPosition {
+        ra,
+        dec,
+        distance,
+    }
+}
+
+fn planet_apparent_position(body: &Body, frame: &ApparentFrame) -> BodyPosition {
+    apparent_from_heliocentric(frame, |julian_date| heliocentric_position(body, julian_date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    #[test]
+    fn sun_matches_meeus_25a() {
+        // 1992 October 13.0 TD.
+        let sun = Body::Sun.apparent_position(&ApparentFrame::new(2_448_908.5));
+        assert_close(sun.ra, 198.380_83, 0.01);
+        assert_close(sun.dec, -7.785_07, 0.01);
+        assert_close(sun.distance, 0.997_66, 0.0001);
+    }
+
+    #[test]
+    fn moon_matches_meeus_47a() {
+        // 1992 April 12.0 TD.
+        let (longitude, latitude, distance) = moon_ecliptic_position(2_448_724.5);
+        assert_close(longitude, 133.162_655, 1e-5);
+        assert_close(latitude, -3.229_126, 1e-5);
+        assert_close(distance, 368_409.7, 0.1);
+
+        let moon = Body::Moon.apparent_position(&ApparentFrame::new(2_448_724.5));
+        assert_close(moon.ra, 134.688_47, 1e-3);
+        assert_close(moon.dec, 13.768_368, 1e-3);
+    }
+
+    #[test]
+    fn venus_matches_meeus_33a() {
+        // 1992 December 20.0 TD.
+        let venus = Body::Venus.apparent_position(&ApparentFrame::new(2_448_976.5));
+        assert_close(venus.ra, (21.0 + 4.0 / 60.0 + 41.454 / 3600.0) * 15.0, 1.0 / 3600.0);
+        assert_close(venus.dec, -(18.0 + 53.0 / 60.0 + 16.84 / 3600.0), 1.0 / 3600.0);
+        assert_close(venus.distance, 0.910_947, 1e-6);
+    }
+}
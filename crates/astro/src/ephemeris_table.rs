@@ -0,0 +1,345 @@
+//! Tabulated ephemerides, such as JPL Horizons observer tables, for
+//! spacecraft and other objects without a model of their own.
+use chrono::{
+    DateTime,
+    NaiveDateTime,
+    Utc,
+};
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::angle;
+use crate::astrometry::ApparentFrame;
+use crate::astrometry::CatalogPosition;
+use crate::astrometry::spherical;
+use crate::astrometry::unit_vector;
+use crate::ephemeris::BodyPosition;
+use crate::time;
+
+/// Date formats of Horizons tables and plain CSV files, tried in order after
+/// RFC 3339.
+const DATE_FORMATS: [&str; 6] = [
+    "%Y-%b-%d %H:%M:%S%.f",
+    "%Y-%b-%d %H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// A row of an ephemeris table.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TableRow {
+    /// Julian date, in UTC.
+    pub julian_date: f64,
+    /// Right ascension, in degrees.
+    pub ra: f64,
+    /// Declination, in degrees.
+    pub dec: f64,
+    /// Distance from the observer, in astronomical units.
+    pub distance: Option<f64>,
+}
+
+/// Positions of an object at increasing times, interpolated in between.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EphemerisTable {
+    pub name: String,
+    pub rows: Vec<TableRow>,
+    /// Whether the positions are apparent, referred to the equator and
+    /// equinox of date, rather than astrometric J2000.0.
+    pub apparent: bool,
+    /// Whether the positions are seen from an observing site rather than
+    /// from the centre of the Earth.
+    pub topocentric: bool,
+}
+
+/// Columns of the table holding each quantity.
+struct Columns {
+    time: usize,
+    /// Whether the times are Julian dates rather than calendar dates.
+    julian: bool,
+    ra: usize,
+    dec: usize,
+    distance: Option<usize>,
+}
+
+impl Columns {
+    /// Find the columns from the names of a header line, as written by
+    /// Horizons (`Date__(UT)__HR:MN`, `R.A._(ICRF)`, `DEC_(ICRF)`, `delta`)
+    /// or in a plain CSV file (`time`, `ra`, `dec`, `distance`).
+    fn find(header: &[&str]) -> Result<Columns, String> {
+        let names: Vec<String> = header.iter().map(|name| name.trim().to_lowercase()).collect();
+        let find = |matches: &dyn Fn(&str) -> bool| names.iter().position(|name| matches(name));
+        let time = find(&|name| name.starts_with("date") || name == "time" || name == "jd")
+            .ok_or("missing time column")?;
+        Ok(Columns {
+            time,
+            julian: names[time].contains("jd"),
+            ra: find(&|name| name.starts_with("r.a.") || name == "ra").ok_or("missing right ascension column")?,
+            dec: find(&|name| name.starts_with("dec")).ok_or("missing declination column")?,
+            distance: find(&|name| name == "delta" || name == "distance"),
+        })
+    }
+}
+
+fn parse_time(text: &str, julian: bool) -> Result<f64, String> {
+    let text = text.trim();
+    if julian {
+        return text.parse().map_err(|_| format!("invalid Julian date '{}'", text));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time::julian_date(time.with_timezone(&Utc)));
+    }
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|time| time::julian_date(time.and_utc()))
+        .ok_or_else(|| format!("invalid date '{}'", text))
+}
+
+/// Cubic Hermite interpolation between `p0` at 0 and `p1` at 1, with slopes
+/// `m0` and `m1` per unit of `t`.
+fn hermite(p0: f64, p1: f64, m0: f64, m1: f64, t: f64) -> f64 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * t3 - 3.0 * t2 + 1.0) * p0 + (t3 - 2.0 * t2 + t) * m0
+        + (-2.0 * t3 + 3.0 * t2) * p1 + (t3 - t2) * m1
+}
+
+impl EphemerisTable {
+    /// Parse a Horizons observer table exported as CSV, or a CSV file with a
+    /// `time,ra,dec[,distance]` header. Times are UTC, angles are degrees or
+    /// sexagesimal and distances are astronomical units. Plain CSV
+    /// positions are geocentric and astrometric J2000.0, and the table is
+    /// named `name` unless Horizons gives the target name.
+    pub fn parse(name: &str, content: &str) -> Result<EphemerisTable, String> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut table = EphemerisTable {
+            name: String::from(name),
+            rows: Vec::new(),
+            apparent: false,
+            topocentric: false,
+        };
+
+        let (header, data) = match lines.iter().position(|line| line.trim() == "$$SOE") {
+            Some(start) => {
+                let end = lines.iter().position(|line| line.trim() == "$$EOE").unwrap_or(lines.len());
+                if end < start {
+                    return Err(String::from("$$EOE before $$SOE"));
+                }
+                for line in &lines[..start] {
+                    if let Some(target) = line.trim().strip_prefix("Target body name:") {
+                        // "Voyager 1 (spacecraft) (-31)   {source: ...}"
+                        let target = target.split("  ").next().unwrap_or("").trim();
+                        if !target.is_empty() {
+                            table.name = String::from(target);
+                        }
+                    }
+                    if let Some(site) = line.trim().strip_prefix("Center-site name:") {
+                        table.topocentric = !site.trim().eq_ignore_ascii_case("GEOCENTRIC");
+                    }
+                }
+                let header = lines[..start]
+                    .iter()
+                    .rev()
+                    .find(|line| line.contains(','))
+                    .ok_or("missing column header, export the table as CSV")?;
+                (*header, lines[start + 1..end].to_vec())
+            },
+            None => {
+                let mut lines = lines
+                    .into_iter()
+                    .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+                let header = lines.next().ok_or("empty ephemeris table")?;
+                (header, lines.collect())
+            },
+        };
+        let columns = Columns::find(&header.split(',').collect::<Vec<&str>>())?;
+        table.apparent = header.to_lowercase().contains("apparent");
+
+        for (index, line) in data.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').collect();
+            let field = |column: usize| -> Result<&str, String> {
+                fields.get(column).copied().ok_or_else(|| format!("row {}: missing column {}", index + 1, column + 1))
+            };
+            let row = TableRow {
+                julian_date: parse_time(field(columns.time)?, columns.julian)
+                    .map_err(|e| format!("row {}: {}", index + 1, e))?,
+                ra: angle::parse_right_ascension(field(columns.ra)?)
+                    .map_err(|e| format!("row {}: {}", index + 1, e))?,
+                dec: angle::parse_declination(field(columns.dec)?)
+                    .map_err(|e| format!("row {}: {}", index + 1, e))?,
+                distance: match columns.distance {
+                    Some(column) => Some(field(column)?.trim().parse()
+                        .map_err(|_| format!("row {}: invalid distance", index + 1))?),
+                    None => None,
+                },
+            };
+            if table.rows.last().is_some_and(|last| last.julian_date >= row.julian_date) {
+                return Err(format!("row {}: times must increase", index + 1));
+            }
+            table.rows.push(row);
+        }
+        if table.rows.len() < 2 {
+            return Err(String::from("an ephemeris table needs at least two rows"));
+        }
+        Ok(table)
+    }
+
+    /// First and last time of the table.
+    pub fn span(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let first = self.rows.first().map_or(0.0, |row| row.julian_date);
+        let last = self.rows.last().map_or(0.0, |row| row.julian_date);
+        (time::from_julian_date(first), time::from_julian_date(last))
+    }
+
+    /// Fail unless `time` is within the span of the table.
+    pub fn check_time(&self, time: DateTime<Utc>) -> Result<(), String> {
+        self.check_interval(time, time)
+    }
+
+    /// Fail unless the whole interval from `start` to `end` is within the
+    /// span of the table.
+    pub fn check_interval(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<(), String> {
+        let (first, last) = self.span();
+        let outside = if start < first {
+            start
+        } else if end > last {
+            end
+        } else {
+            return Ok(());
+        };
+        Err(format!(
+            "{} is outside of the ephemeris table of {}, from {} to {}",
+            outside.format("%Y-%m-%d %H:%M:%S"),
+            self.name,
+            first.format("%Y-%m-%d %H:%M:%S"),
+            last.format("%Y-%m-%d %H:%M:%S"),
+        ))
+    }
+
+    /// Tabulated right ascension, declination and distance at `time`. The
+    /// direction is interpolated as a unit vector, so that it stays smooth
+    /// across 0h and near the poles, with cubic Hermite splines whose slopes
+    /// come from the neighbouring rows.
+    pub fn interpolate(&self, time: DateTime<Utc>) -> Result<TableRow, String> {
+        self.check_time(time)?;
+        let julian_date = time::julian_date(time);
+        let rows = &self.rows;
+        let index = rows
+            .partition_point(|row| row.julian_date <= julian_date)
+            .clamp(1, rows.len() - 1) - 1;
+
+        let vector = |row: &TableRow| {
+            let [x, y, z] = unit_vector(row.ra, row.dec);
+            [x, y, z, row.distance.unwrap_or(0.0)]
+        };
+        // Slope at a row, from its neighbours or one-sided at the ends.
+        let slope = |row: usize| -> [f64; 4] {
+            let before = row.saturating_sub(1);
+            let after = (row + 1).min(rows.len() - 1);
+            let (from, to) = (vector(&rows[before]), vector(&rows[after]));
+            let days = rows[after].julian_date - rows[before].julian_date;
+            std::array::from_fn(|i| (to[i] - from[i]) / days)
+        };
+
+        let days = rows[index + 1].julian_date - rows[index].julian_date;
+        let (p0, p1) = (vector(&rows[index]), vector(&rows[index + 1]));
+        let (m0, m1) = (slope(index), slope(index + 1));
+        let t = (julian_date - rows[index].julian_date) / days;
+        let [x, y, z, distance] = std::array::from_fn(|i| hermite(p0[i], p1[i], m0[i] * days, m1[i] * days, t));
+
+        let (ra, dec) = spherical([x, y, z]);
+        Ok(TableRow {
+            julian_date,
+            ra,
+            dec,
+            distance: rows[index].distance.map(|_| distance),
+        })
+    }
+
+    /// Apparent position at `time`, as seen from where the table was
+    /// computed. Without distances the object is taken to be far enough for
+    /// its parallax to be negligible.
+    pub fn apparent_position(&self, frame: &ApparentFrame, time: DateTime<Utc>) -> Result<BodyPosition, String> {
+        let row = self.interpolate(time)?;
+        let (ra, dec) = if self.apparent {
+            (row.ra, row.dec)
+        } else {
+            frame.apparent_place(&CatalogPosition::j2000(row.ra, row.dec))
+        };
+        Ok(BodyPosition {
+            ra,
+            dec,
+            distance: row.distance.unwrap_or(f64::INFINITY),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HORIZONS: &str = "\
+*******************************************************************************
+Target body name: Voyager 1 (spacecraft) (-31)    {source: Voyager_1_ST+refit2022_m}
+Center body name: Earth (399)                     {source: DE441}
+Center-site name: GEOCENTRIC
+*******************************************************************************
+ Date__(UT)__HR:MN, , , R.A.___(ICRF)___, DEC____(ICRF)___,             delta,     deldot,
+*******************************************************************************
+$$SOE
+ 2024-Oct-18 00:00, , , 17 13 08.71, +12 01 33.9, 165.180935, 25.8,
+ 2024-Oct-19 00:00, , , 17 13 09.06, +12 01 32.6, 165.196105, 25.7,
+ 2024-Oct-20 00:00, , , 17 13 09.40, +12 01 31.3, 165.211261, 25.6,
+$$EOE
+*******************************************************************************
+";
+
+    #[test]
+    fn parses_horizons_csv() {
+        let table = EphemerisTable::parse("voyager.txt", HORIZONS).unwrap();
+        assert_eq!(table.name, "Voyager 1 (spacecraft) (-31)");
+        assert_eq!(table.rows.len(), 3);
+        assert!(!table.apparent && !table.topocentric);
+        assert!((table.rows[0].ra - 258.286_29).abs() < 1e-5);
+        assert_eq!(table.rows[2].distance, Some(165.211_261));
+
+        let (first, last) = table.span();
+        assert_eq!(first.to_rfc3339(), "2024-10-18T00:00:00+00:00");
+        assert!(table.check_time(last + chrono::Duration::seconds(1)).is_err());
+        assert!(table.check_interval(first, last).is_ok());
+        let error = table.check_interval(first, last + chrono::Duration::hours(1)).unwrap_err();
+        assert!(error.starts_with("2024-10-20 01:00:00 is outside"), "{}", error);
+        assert!(table.check_interval(first - chrono::Duration::hours(1), first).is_err());
+    }
+
+    #[test]
+    fn interpolation_passes_through_rows_and_across_0h() {
+        let table = EphemerisTable::parse("test", "\
+time,ra,dec
+2024-10-18 00:00,359.0,10.0
+2024-10-18 01:00,359.5,10.5
+2024-10-18 02:00,0.0,11.0
+2024-10-18 03:00,0.5,11.5
+").unwrap();
+        let row = table.interpolate(time::from_julian_date(table.rows[1].julian_date)).unwrap();
+        assert!((row.ra - 359.5).abs() < 1e-9 && (row.dec - 10.5).abs() < 1e-9);
+        let (first, _) = table.span();
+        let row = table.interpolate(first + chrono::Duration::minutes(150)).unwrap();
+        assert!((row.ra - 0.25).abs() < 1e-3, "{}", row.ra);
+        assert!((row.dec - 11.25).abs() < 1e-3, "{}", row.dec);
+        assert_eq!(row.distance, None);
+    }
+
+    #[test]
+    fn rejects_unordered_rows() {
+        let error = EphemerisTable::parse("test", "time,ra,dec\n2024-10-18 01:00,1,1\n2024-10-18 00:00,2,2\n");
+        assert_eq!(error.unwrap_err(), "row 2: times must increase");
+    }
+}
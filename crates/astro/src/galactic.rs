@@ -0,0 +1,85 @@
+//! Galactic coordinates, with the north galactic pole and the origin of
+//! longitude of the IAU 1958 system referred to J2000.0, as adopted for the
+//! Hipparcos catalogue.
+use crate::astrometry;
+use crate::time;
+
+/// J2000.0 right ascension of the north galactic pole, in degrees.
+const NORTH_POLE_RA: f64 = 192.859_48;
+/// J2000.0 declination of the north galactic pole, in degrees.
+const NORTH_POLE_DEC: f64 = 27.128_25;
+/// Galactic longitude of the north celestial pole, in degrees.
+const CELESTIAL_POLE_LONGITUDE: f64 = 122.931_92;
+
+/// Galactic longitude and latitude, in degrees, of J2000.0 equatorial
+/// coordinates.
+pub fn equatorial_to_galactic(ra: f64, dec: f64) -> (f64, f64) {
+    let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+    let (sin_pole, cos_pole) = NORTH_POLE_DEC.to_radians().sin_cos();
+    let (sin_ra, cos_ra) = (ra - NORTH_POLE_RA).to_radians().sin_cos();
+
+    let latitude = (sin_dec * sin_pole + cos_dec * cos_pole * cos_ra)
+        .clamp(-1.0, 1.0)
+        .asin();
+    let longitude = CELESTIAL_POLE_LONGITUDE
+        - (cos_dec * sin_ra).atan2(sin_dec * cos_pole - cos_dec * sin_pole * cos_ra).to_degrees();
+    (longitude.rem_euclid(360.0), latitude.to_degrees())
+}
+
+/// J2000.0 right ascension and declination, in degrees, of galactic
+/// coordinates.
+pub fn galactic_to_equatorial(longitude: f64, latitude: f64) -> (f64, f64) {
+    let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+    let (sin_pole, cos_pole) = NORTH_POLE_DEC.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = (CELESTIAL_POLE_LONGITUDE - longitude).to_radians().sin_cos();
+
+    let dec = (sin_lat * sin_pole + cos_lat * cos_pole * cos_lon)
+        .clamp(-1.0, 1.0)
+        .asin();
+    let ra = NORTH_POLE_RA
+        + (cos_lat * sin_lon).atan2(sin_lat * cos_pole - cos_lat * sin_pole * cos_lon).to_degrees();
+    (ra.rem_euclid(360.0), dec.to_degrees())
+}
+
+/// Galactic coordinates of equatorial coordinates referred to the mean
+/// equinox of a Julian date. Apparent places can be passed as well: nutation
+/// and aberration shift them by less than a minute of arc.
+pub fn of_date_to_galactic(ra: f64, dec: f64, julian_date: f64) -> (f64, f64) {
+    let (ra, dec) = astrometry::precess(ra, dec, julian_date, time::J2000);
+    equatorial_to_galactic(ra, dec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    #[test]
+    fn galactic_centre_and_pole() {
+        // The origin lies at 17h45m37.2s −28°56′10″ (J2000.0).
+        let (ra, dec) = galactic_to_equatorial(0.0, 0.0);
+        assert_close(ra, 266.4051, 0.001);
+        assert_close(dec, -28.9362, 0.001);
+
+        let (longitude, latitude) = equatorial_to_galactic(266.4051, -28.9362);
+        assert_close((longitude + 180.0).rem_euclid(360.0), 180.0, 0.001);
+        assert_close(latitude, 0.0, 0.001);
+
+        let (_, latitude) = equatorial_to_galactic(NORTH_POLE_RA, NORTH_POLE_DEC);
+        assert_close(latitude, 90.0, 1e-9);
+    }
+
+    #[test]
+    fn round_trip() {
+        for (ra, dec) in [(83.8221, -5.3911), (350.85, 58.815), (10.0, 89.0), (201.365, -43.019)] {
+            let (longitude, latitude) = equatorial_to_galactic(ra, dec);
+            let (back_ra, back_dec) = galactic_to_equatorial(longitude, latitude);
+            assert_close(back_ra, ra, 1e-9);
+            assert_close(back_dec, dec, 1e-9);
+        }
+        // Cassiopeia A lies at l = 111.74°, b = −2.13°.
+        let (longitude, latitude) = equatorial_to_galactic(350.85, 58.815);
+        assert_close(longitude, 111.74, 0.02);
+        assert_close(latitude, -2.13, 0.02);
+    }
+}
@@ -0,0 +1,228 @@
+//! Local horizon profile: the lowest usable elevation in each direction, set
+//! by the terrain, buildings or trees around the site.
+use serde::{
+    Deserializer,
+    Serialize,
+    Deserialize,
+};
+
+/// Minimum elevation as a function of azimuth, interpolated linearly between
+/// measured points and raised to an optional elevation limit. Without points
+/// the terrain is flat at zero elevation.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct HorizonProfile {
+    /// Azimuth, measured from north through east, and minimum elevation, in
+    /// degrees. Sorted by azimuth from 0 to 360, as [`HorizonProfile::parse`]
+    /// and deserialising leave them.
+    #[serde(deserialize_with = "deserialize_points")]
+    pub points: Vec<[f64; 2]>,
+    /// File of azimuth and elevation pairs, one per line, replacing `points`
+    /// when read.
+    pub file: Option<String>,
+    /// Lowest elevation in every direction, such as the limit of the mount,
+    /// in degrees.
+    pub limit: Option<f64>,
+}
+
+impl HorizonProfile {
+    /// Parse a profile file: an azimuth and an elevation in degrees per line,
+    /// separated by spaces, tabs or a comma. Lines starting with `#` are
+    /// comments.
+    pub fn parse(text: &str) -> Result<Vec<[f64; 2]>, String> {
+        let mut points = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|field| !field.is_empty())
+                .collect();
+            let [azimuth, elevation] = fields[..] else {
+                return Err(format!("Line {}: expected an azimuth and an elevation", number + 1));
+            };
+            let azimuth: f64 = azimuth
+                .parse()
+                .map_err(|_| format!("Line {}: invalid azimuth \"{}\"", number + 1, azimuth))?;
+            let elevation: f64 = elevation
+                .parse()
+                .map_err(|_| format!("Line {}: invalid elevation \"{}\"", number + 1, elevation))?;
+            if !(-90.0..=90.0).contains(&elevation) {
+                return Err(format!("Line {}: elevation {} is outside of -90..90", number + 1, elevation));
+            }
+            points.push([azimuth, elevation]);
+        }
+        sort_points(&mut points);
+        Ok(points)
+    }
+
+    /// Replace the points with the content of `file`, if there is one.
+    pub fn read_file(&mut self) -> Result<(), String> {
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path, e))?;
+        self.points = HorizonProfile::parse(&content)
+            .map_err(|e| format!("invalid horizon file {}: {}", path, e))?;
+        Ok(())
+    }
+
+    /// Whether the profile is the flat horizon at zero elevation.
+    pub fn is_flat(&self) -> bool {
+        self.points.is_empty() && self.limit.is_none_or(|limit| limit <= 0.0)
+    }
+
+    /// Minimum elevation, in degrees, at an azimuth: the terrain or the
+    /// limit, whichever is higher.
+    pub fn min_elevation(&self, azimuth: f64) -> f64 {
+        let terrain = self.terrain_elevation(azimuth);
+        self.limit.map_or(terrain, |limit| terrain.max(limit))
+    }
+
+    /// Elevation of the terrain, in degrees, at an azimuth, without the
+    /// limit.
+    pub fn terrain_elevation(&self, azimuth: f64) -> f64 {
+        let count = self.points.len();
+        if count == 0 {
+            return 0.0;
+        }
+        let azimuth = azimuth.rem_euclid(360.0);
+        // The points around the azimuth, wrapping around north.
+        let index = self.points.partition_point(|point| point[0] <= azimuth);
+        let before = self.points[(index + count - 1) % count];
+        let after = self.points[index % count];
+
+        let span = (after[0] - before[0]).rem_euclid(360.0);
+        if span == 0.0 {
+            return before[1];
+        }
+        let fraction = (azimuth - before[0]).rem_euclid(360.0) / span;
+        before[1] + (after[1] - before[1]) * fraction
+    }
+
+    /// Whether a direction is above the profile.
+    pub fn is_visible(&self, azimuth: f64, elevation: f64) -> bool {
+        elevation >= self.min_elevation(azimuth)
+    }
+
+    /// Azimuths of the measured points, in degrees from 0 to 360.
+    pub fn azimuths(&self) -> Vec<f64> {
+        self.points.iter().map(|point| point[0]).collect()
+    }
+}
+
+/// Bring the azimuths of `points` between 0 and 360 and sort them, so that
+/// the points around an azimuth are found by bisection.
+fn sort_points(points: &mut [[f64; 2]]) {
+    for point in points.iter_mut() {
+        point[0] = point[0].rem_euclid(360.0);
+    }
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+}
+
+fn deserialize_points<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[f64; 2]>, D::Error> {
+    let mut points = Vec::<[f64; 2]>::deserialize(deserializer)?;
+    sort_points(&mut points);
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    use super::*;
+    use crate::LocalSky;
+    use crate::test_util::assert_close;
+    use crate::RiseSet;
+
+    #[test]
+    fn interpolates_around_north() {
+        assert_eq!(HorizonProfile::default().min_elevation(123.0), 0.0);
+
+        let profile = HorizonProfile {
+            points: vec![[10.0, 4.0], [90.0, 12.0], [350.0, 2.0]],
+            file: None,
+            limit: None,
+        };
+        assert_close(profile.min_elevation(50.0), 8.0, 1e-9);
+        assert_close(profile.min_elevation(90.0), 12.0, 1e-9);
+        // Between 350° and 10°, across north.
+        assert_close(profile.min_elevation(0.0), 3.0, 1e-9);
+        assert_close(profile.min_elevation(360.0), 3.0, 1e-9);
+        assert_close(profile.min_elevation(355.0), 2.5, 1e-9);
+        // Between 90° and 350°.
+        assert_close(profile.min_elevation(220.0), 7.0, 1e-9);
+        assert!(profile.is_visible(90.0, 12.5) && !profile.is_visible(90.0, 11.5));
+
+        let limited = HorizonProfile {
+            limit: Some(5.0),
+            ..profile
+        };
+        assert_close(limited.min_elevation(0.0), 5.0, 1e-9);
+        assert_close(limited.min_elevation(90.0), 12.0, 1e-9);
+        assert_close(limited.terrain_elevation(0.0), 3.0, 1e-9);
+    }
+
+    #[test]
+    fn parses_profile_files() {
+        let points = HorizonProfile::parse("# az el\n0 5.5\n90,\t7\n\n180 -1\n").unwrap();
+        assert_eq!(points, vec![[0.0, 5.5], [90.0, 7.0], [180.0, -1.0]]);
+        // Points are sorted by azimuth, from 0 to 360.
+        let points = HorizonProfile::parse("270 3\n-10 2\n90 1\n").unwrap();
+        assert_eq!(points, vec![[90.0, 1.0], [270.0, 3.0], [350.0, 2.0]]);
+        let profile: HorizonProfile = toml::from_str("points = [[90, 1], [-10, 2]]").unwrap();
+        assert_eq!(profile.points, vec![[90.0, 1.0], [350.0, 2.0]]);
+        assert!(HorizonProfile::parse("0").is_err());
+        assert!(HorizonProfile::parse("0 95").is_err());
+        assert!(HorizonProfile::parse("north 5").is_err());
+    }
+
+    #[test]
+    fn profile_delays_rise_and_advances_set() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let sky = LocalSky::new(42.6, -8.7, time);
+        let times = |sky: &LocalSky| match sky.apparent_rise_transit_set(83.8, -5.4) {
+            RiseSet::Rises { rise, transit, set } => (rise, transit, set),
+            _ => panic!("Orion rises at this latitude"),
+        };
+
+        // A flat profile crosses where the plain horizon does.
+        let (rise, transit, set) = times(&sky);
+        let flat = HorizonProfile {
+            points: vec![[0.0, 0.0]],
+            file: None,
+            limit: None,
+        };
+        let (flat_rise, flat_transit, flat_set) = times(&LocalSky::new(42.6, -8.7, time).with_horizon(&flat));
+        assert!((flat_rise - rise).num_seconds().abs() < 2);
+        assert!((flat_set - set).num_seconds().abs() < 2);
+        assert_eq!(flat_transit, transit);
+
+        // Hills in the east and the west delay the rise and advance the set
+        // by 45 to 90 minutes.
+        let hills = HorizonProfile {
+            points: vec![[0.0, 0.0], [90.0, 10.0], [180.0, 0.0], [270.0, 10.0]],
+            file: None,
+            limit: None,
+        };
+        let (hills_rise, _, hills_set) = times(&LocalSky::new(42.6, -8.7, time).with_horizon(&hills));
+        let delay = (hills_rise - rise).num_minutes();
+        let advance = (set - hills_set).num_minutes();
+        assert!((45..90).contains(&delay), "rise delayed by {} minutes", delay);
+        assert!((45..90).contains(&advance), "set advanced by {} minutes", advance);
+
+        let wall = HorizonProfile {
+            points: vec![[0.0, 60.0]],
+            file: None,
+            limit: None,
+        };
+        assert!(matches!(
+            LocalSky::new(42.6, -8.7, time).with_horizon(&wall).apparent_rise_transit_set(83.8, -5.4),
+            RiseSet::NeverRises,
+        ));
+    }
+}
@@ -0,0 +1,313 @@
+//! Astronomy core of the radio telescope controller: time scales, reference
+//! frames, coordinate transforms and ephemerides. It has no dependency on the
+//! user interface so that the daemon, the GUI, the command-line client and
+//! the tests share the same computations.
+use chrono::{
+    DateTime,
+    Duration,
+    Utc,
+};
+
+pub mod angle;
+pub mod astrometry;
+pub mod cable_wrap;
+pub mod catalog;
+pub mod constellation;
+pub mod ephemeris;
+pub mod ephemeris_table;
+pub mod galactic;
+pub mod horizon;
+pub mod minor_body;
+pub mod observability;
+pub mod refraction;
+pub mod satellite;
+pub mod target;
+#[cfg(test)]
+mod test_util;
+pub mod time;
+pub mod vsop87;
+
+use crate::astrometry::ApparentFrame;
+use crate::astrometry::CatalogPosition;
+use crate::ephemeris::BodyPosition;
+use crate::horizon::HorizonProfile;
+use crate::refraction::Atmosphere;
+use crate::target::Target;
+
+/// Rate at which the sky turns, in degrees per hour of mean solar time.
+const SIDEREAL_RATE: f64 = 15.041_068_64;
+
+/// Azimuth, measured from north through east, and altitude, in degrees, of
+/// an object at the given hour angle and declination.
+pub fn hour_angle_to_horizontal(hour_angle: f64, dec: f64, latitude: f64) -> (f64, f64) {
+    let (sin_hour_angle, cos_hour_angle) = hour_angle.to_radians().sin_cos();
+    let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+    let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+
+    let altitude = (sin_dec * sin_lat + cos_dec * cos_lat * cos_hour_angle)
+        .clamp(-1.0, 1.0)
+        .asin();
+    let azimuth = (-cos_dec * sin_hour_angle)
+        .atan2(sin_dec * cos_lat - cos_dec * cos_hour_angle * sin_lat);
+    (azimuth.to_degrees().rem_euclid(360.0), altitude.to_degrees())
+}
+
+/// Hour angle and declination, in degrees, of an object at the given
+/// azimuth, measured from north through east, and altitude.
+pub fn horizontal_to_hour_angle(azimuth: f64, altitude: f64, latitude: f64) -> (f64, f64) {
+    let (sin_azimuth, cos_azimuth) = azimuth.to_radians().sin_cos();
+    let (sin_alt, cos_alt) = altitude.to_radians().sin_cos();
+    let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+
+    let dec = (sin_alt * sin_lat + cos_alt * cos_lat * cos_azimuth)
+        .clamp(-1.0, 1.0)
+        .asin();
+    let hour_angle = (-cos_alt * sin_azimuth)
+        .atan2(sin_alt * cos_lat - cos_alt * cos_azimuth * sin_lat);
+    (hour_angle.to_degrees().rem_euclid(360.0), dec.to_degrees())
+}
+
+/// Next horizon crossings of a fixed object.
+pub enum RiseSet {
+    /// The object never goes below the horizon.
+    Circumpolar {
+        transit: DateTime<Utc>,
+    },
+    /// The object never goes above the horizon.
+    NeverRises,
+    Rises {
+        rise: DateTime<Utc>,
+        transit: DateTime<Utc>,
+        set: DateTime<Utc>,
+    },
+}
+
+/// The sky as seen from a site at a given instant.
+pub struct LocalSky {
+    pub time: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub frame: ApparentFrame,
+    /// Local apparent sidereal time, in degrees.
+    pub local_sidereal_time: f64,
+    /// Atmosphere refracting horizontal coordinates. Without one, geometric
+    /// altitudes are returned.
+    pub atmosphere: Option<Atmosphere>,
+    /// Local horizon that objects rise above and set below. Without one, the
+    /// horizon is flat.
+    pub horizon: Option<HorizonProfile>,
+}
+
+impl LocalSky {
+    /// Sky at `latitude` and `longitude` (degrees, east positive) at `time`.
+    pub fn new(latitude: f64, longitude: f64, time: DateTime<Utc>) -> Self {
+        let frame = ApparentFrame::new(time::julian_date_tt(time));
+        let local_sidereal_time = (frame.apparent_sidereal_time(time::julian_date(time))
+            + longitude).rem_euclid(360.0);
+        LocalSky {
+            time,
+            latitude,
+            longitude,
+            frame,
+            local_sidereal_time,
+            atmosphere: None,
+            horizon: None,
+        }
+    }
+
+    /// Refract horizontal coordinates through the given atmosphere.
+    pub fn with_atmosphere(mut self, atmosphere: &Atmosphere) -> Self {
+        self.atmosphere = Some(atmosphere.clone());
+        self
+    }
+
+    /// The same site, with the same atmosphere and horizon, at another time.
+    pub fn at(&self, time: DateTime<Utc>) -> Self {
+        LocalSky {
+            atmosphere: self.atmosphere.clone(),
+            horizon: self.horizon.clone(),
+            ..LocalSky::new(self.latitude, self.longitude, time)
+        }
+    }
+
+    /// Rise above and set below the given horizon profile.
+    pub fn with_horizon(mut self, horizon: &HorizonProfile) -> Self {
+        self.horizon = Some(horizon.clone());
+        self
+    }
+
+    /// Refraction, in degrees, at the given geometric altitude.
+    pub fn refraction(&self, altitude: f64) -> f64 {
+        self.atmosphere.as_ref().map_or(0.0, |atmosphere| atmosphere.refraction(altitude))
+    }
+
+    /// Apparent right ascension and declination of a catalog position.
+    pub fn apparent_place(&self, position: &CatalogPosition) -> (f64, f64) {
+        self.frame.apparent_place(position)
+    }
+
+    /// Azimuth and observed altitude, in degrees, of apparent equatorial
+    /// coordinates.
+    pub fn apparent_to_horizontal(&self, ra: f64, dec: f64) -> (f64, f64) {
+        let (azimuth, altitude) =
+            hour_angle_to_horizontal(self.local_sidereal_time - ra, dec, self.latitude);
+        (azimuth, altitude + self.refraction(altitude))
+    }
+
+    /// Apparent right ascension and declination, in degrees, of an azimuth
+    /// and observed altitude, removing the refraction.
+    pub fn horizontal_to_apparent(&self, azimuth: f64, altitude: f64) -> (f64, f64) {
+        // The refraction is a function of the geometric altitude, which
+        // a few iterations recover from the observed one.
+        let mut geometric = altitude;
+        for _ in 0..3 {
+            geometric = altitude - self.refraction(geometric);
+        }
+        let (hour_angle, dec) = horizontal_to_hour_angle(azimuth, geometric, self.latitude);
+        ((self.local_sidereal_time - hour_angle).rem_euclid(360.0), dec)
+    }
+
+    /// Azimuth and altitude, in degrees, of a catalog position.
+    pub fn horizontal(&self, position: &CatalogPosition) -> (f64, f64) {
+        let (ra, dec) = self.apparent_place(position);
+        self.apparent_to_horizontal(ra, dec)
+    }
+
+    /// Topocentric azimuth and apparent altitude of a solar system body
+    /// from its geocentric position, corrected for parallax.
+    pub fn body_horizontal(&self, position: &BodyPosition) -> (f64, f64) {
+        let (azimuth, altitude) = hour_angle_to_horizontal(
+            self.local_sidereal_time - position.ra,
+            position.dec,
+            self.latitude,
+        );
+        let parallax = (position.horizontal_parallax().to_radians().sin()
+            * altitude.to_radians().cos()).asin().to_degrees();
+        let altitude = altitude - parallax;
+        (azimuth, altitude + self.refraction(altitude))
+    }
+
+    /// Azimuth and observed altitude, in degrees, of a target. Solar system
+    /// bodies and satellites are seen from the surface of the Earth rather
+    /// than its centre, which lowers the Moon by up to a degree.
+    pub fn target_horizontal(&self, target: &Target) -> (f64, f64) {
+        match target {
+            Target::Catalog { position, .. } => self.horizontal(position),
+            Target::Body { body } => self.body_horizontal(&body.apparent_position(&self.frame)),
+            Target::MinorBody { body } => self.body_horizontal(&body.apparent_position(&self.frame)),
+            // Outside of its table, an object is reported below the horizon.
+            Target::Table { table } => match table.apparent_position(&self.frame, self.time) {
+                Ok(position) if table.topocentric => self.apparent_to_horizontal(position.ra, position.dec),
+                Ok(position) => self.body_horizontal(&position),
+                Err(_) => (0.0, -90.0),
+            },
+            // A satellite that cannot be propagated, for instance because it
+            // has decayed, is reported below the horizon.
+            Target::Satellite { satellite } => {
+                match satellite.topocentric(self.latitude, self.longitude, self.time) {
+                    Ok((azimuth, altitude, _)) => (azimuth, altitude + self.refraction(altitude)),
+                    Err(_) => (0.0, -90.0),
+                }
+            },
+        }
+    }
+
+    /// Next rise, transit and set of a catalog position. Each of the returned
+    /// events is the first one after `self.time`, so the set can come before
+    /// the rise when the object is already up. Refraction lifts the object
+    /// above the horizon while its geometric altitude is still negative, and
+    /// a horizon profile delays the rise and advances the set.
+    pub fn rise_transit_set(&self, position: &CatalogPosition) -> RiseSet {
+        let (ra, dec) = self.apparent_place(position);
+        self.apparent_rise_transit_set(ra, dec)
+    }
+
+    /// Next rise, transit and set of an object held at the given apparent
+    /// equatorial coordinates, in degrees. For a moving object such as the
+    /// Moon the times are only approximate.
+    pub fn apparent_rise_transit_set(&self, ra: f64, dec: f64) -> RiseSet {
+        let time_until = |hour_angle: f64| -> DateTime<Utc> {
+            let hours = (ra + hour_angle - self.local_sidereal_time).rem_euclid(360.0) / SIDEREAL_RATE;
+            self.time + Duration::milliseconds((hours * 3_600_000.0) as i64)
+        };
+
+        if let Some(horizon) = self.horizon.as_ref().filter(|horizon| !horizon.is_flat()) {
+            return self.profile_rise_set(ra, dec, horizon, time_until(0.0));
+        }
+
+        let (lat_sin, lat_cos) = self.latitude.to_radians().sin_cos();
+        let (dec_sin, dec_cos) = dec.to_radians().sin_cos();
+        let horizon = -self.refraction(0.0);
+        let cos_hour_angle =
+            (horizon.to_radians().sin() - lat_sin * dec_sin) / (lat_cos * dec_cos);
+
+        if cos_hour_angle < -1.0 {
+            RiseSet::Circumpolar {
+                transit: time_until(0.0),
+            }
+        } else if cos_hour_angle > 1.0 {
+            RiseSet::NeverRises
+        } else {
+            let hour_angle = cos_hour_angle.acos().to_degrees();
+            RiseSet::Rises {
+                rise: time_until(-hour_angle),
+                transit: time_until(0.0),
+                set: time_until(hour_angle),
+            }
+        }
+    }
+
+    /// Crossings of a horizon profile, found by sampling one turn of the sky
+    /// and refining each sign change of the height above the profile.
+    fn profile_rise_set(&self, ra: f64, dec: f64, horizon: &HorizonProfile, transit: DateTime<Utc>) -> RiseSet {
+        let height = |hours: f64| -> f64 {
+            let hour_angle = self.local_sidereal_time - ra + hours * SIDEREAL_RATE;
+            let (azimuth, altitude) = hour_angle_to_horizontal(hour_angle, dec, self.latitude);
+            altitude + self.refraction(altitude) - horizon.min_elevation(azimuth)
+        };
+        let crossing = |mut below: f64, mut above: f64| -> DateTime<Utc> {
+            for _ in 0..20 {
+                let middle = (below + above) / 2.0;
+                if height(middle) < 0.0 {
+                    below = middle;
+                } else {
+                    above = middle;
+                }
+            }
+            self.time + Duration::milliseconds((above * 3_600_000.0) as i64)
+        };
+
+        let n_samples = 360;
+        let step = 360.0 / SIDEREAL_RATE / n_samples as f64;
+        let mut rise = None;
+        let mut set = None;
+        let mut previous = height(0.0);
+        let (mut ever_above, mut ever_below) = (previous >= 0.0, previous < 0.0);
+        for sample in 1..=n_samples {
+            let hours = sample as f64 * step;
+            let current = height(hours);
+            if previous < 0.0 && current >= 0.0 && rise.is_none() {
+                rise = Some(crossing(hours - step, hours));
+            }
+            if previous >= 0.0 && current < 0.0 && set.is_none() {
+                set = Some(crossing(hours, hours - step));
+            }
+            ever_above |= current >= 0.0;
+            ever_below |= current < 0.0;
+            previous = current;
+        }
+
+        match (rise, set) {
+            (Some(rise), Some(set)) => RiseSet::Rises { rise, transit, set },
+            _ if !ever_below => RiseSet::Circumpolar { transit },
+            _ if !ever_above => RiseSet::NeverRises,
+            // Sign changes both ways within a turn, but a crossing right at
+            // its start may only be seen once.
+            (rise, set) => RiseSet::Rises {
+                rise: rise.unwrap_or(self.time),
+                transit,
+                set: set.unwrap_or(self.time),
+            },
+        }
+    }
+}
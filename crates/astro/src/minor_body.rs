@@ -0,0 +1,283 @@
+//! Comets and asteroids from the one-line orbital elements published by the
+//! Minor Planet Center, on elliptic, parabolic or hyperbolic orbits.
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::astrometry::ApparentFrame;
+use crate::ephemeris;
+use crate::ephemeris::BodyPosition;
+use crate::time;
+
+/// Gaussian gravitational constant, in radians per day.
+const GAUSSIAN_CONSTANT: f64 = 0.017_202_098_95;
+
+/// Eccentricities closer to 1 than this are treated as parabolic.
+const PARABOLIC_TOLERANCE: f64 = 1e-6;
+
+/// Heliocentric orbit referred to the ecliptic and equinox of J2000.0.
+/// Perihelion distance and time describe every kind of conic, unlike the
+/// semi-major axis and mean anomaly.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Orbit {
+    /// Perihelion distance, in astronomical units.
+    pub perihelion_distance: f64,
+    pub eccentricity: f64,
+    /// Inclination, in degrees.
+    pub inclination: f64,
+    /// Longitude of the ascending node, in degrees.
+    pub node: f64,
+    /// Argument of perihelion, in degrees.
+    pub perihelion_argument: f64,
+    /// Julian date of the perihelion passage, in TT.
+    pub perihelion_time: f64,
+}
+
+/// A comet or an asteroid.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MinorBody {
+    pub name: String,
+    pub orbit: Orbit,
+    /// Absolute magnitude (H for asteroids, H₀ for comets).
+    pub magnitude: Option<f64>,
+}
+
+/// Characters `first..=last`, counted from 1 as in the MPC format
+/// descriptions, trimmed.
+fn columns(line: &str, first: usize, last: usize) -> &str {
+    line.get(first - 1..last.min(line.len())).unwrap_or("").trim()
+}
+
+fn number(line: &str, first: usize, last: usize, what: &str) -> Result<f64, String> {
+    let text = columns(line, first, last);
+    text.parse().map_err(|_| format!("invalid {} '{}'", what, text))
+}
+
+/// Value of a character of a packed date: `1`-`9`, then `A` for 10 onwards.
+fn packed_digit(character: char) -> Result<u32, String> {
+    match character {
+        '0'..='9' => Ok(character as u32 - '0' as u32),
+        'A'..='Z' => Ok(character as u32 - 'A' as u32 + 10),
+        _ => Err(format!("invalid packed date character '{}'", character)),
+    }
+}
+
+/// Julian date of a packed MPC epoch such as `K24AH` (2024 October 17).
+fn packed_epoch(text: &str) -> Result<f64, String> {
+    let characters: Vec<char> = text.chars().collect();
+    if characters.len() != 5 {
+        return Err(format!("invalid packed epoch '{}'", text));
+    }
+    let century = match characters[0] {
+        'I' => 1800,
+        'J' => 1900,
+        'K' => 2000,
+        _ => return Err(format!("invalid packed epoch '{}'", text)),
+    };
+    let year: i32 = text[1..3].parse().map_err(|_| format!("invalid packed epoch '{}'", text))?;
+    let month = packed_digit(characters[3])?;
+    let day = packed_digit(characters[4])?;
+    Ok(time::calendar_julian_date(century + year, month, day as f64))
+}
+
+impl MinorBody {
+    /// Parse a line of the MPC comet format (`CometEls.txt`).
+    pub fn parse_comet(line: &str) -> Result<MinorBody, String> {
+        let year = number(line, 15, 18, "perihelion year")? as i32;
+        let month = number(line, 20, 21, "perihelion month")? as u32;
+        let day = number(line, 23, 29, "perihelion day")?;
+        let name = columns(line, 103, 158);
+        if name.is_empty() {
+            return Err(String::from("missing comet name"));
+        }
+        Ok(MinorBody {
+            name: String::from(name),
+            orbit: Orbit {
+                perihelion_distance: number(line, 31, 39, "perihelion distance")?,
+                eccentricity: number(line, 42, 49, "eccentricity")?,
+                perihelion_argument: number(line, 52, 59, "argument of perihelion")?,
+                node: number(line, 62, 69, "ascending node")?,
+                inclination: number(line, 72, 79, "inclination")?,
+                perihelion_time: time::calendar_julian_date(year, month, day),
+            },
+            magnitude: number(line, 92, 95, "magnitude").ok(),
+        })
+    }
+
+    /// Parse a line of the MPC asteroid format (`MPCORB.DAT`).
+    pub fn parse_asteroid(line: &str) -> Result<MinorBody, String> {
+        let epoch = packed_epoch(columns(line, 21, 25))?;
+        let mean_anomaly = number(line, 27, 35, "mean anomaly")?;
+        let eccentricity = number(line, 71, 79, "eccentricity")?;
+        let mean_motion = number(line, 81, 91, "mean daily motion")?;
+        let semi_major_axis = number(line, 93, 103, "semi-major axis")?;
+        if mean_motion <= 0.0 {
+            return Err(String::from("mean daily motion must be positive"));
+        }
+
+        let readable = columns(line, 167, 194);
+        let name = if readable.is_empty() {
+            columns(line, 1, 7)
+        } else {
+            readable
+        };
+        // The mean anomaly is taken on the near side of the epoch so that the
+        // perihelion time stays close to it.
+        let mean_anomaly = (mean_anomaly + 180.0).rem_euclid(360.0) - 180.0;
+        Ok(MinorBody {
+            name: String::from(name),
+            orbit: Orbit {
+                perihelion_distance: semi_major_axis * (1.0 - eccentricity),
+                eccentricity,
+                perihelion_argument: number(line, 38, 46, "argument of perihelion")?,
+                node: number(line, 49, 57, "ascending node")?,
+                inclination: number(line, 60, 68, "inclination")?,
+                perihelion_time: epoch - mean_anomaly / mean_motion,
+            },
+            magnitude: number(line, 9, 13, "magnitude").ok(),
+        })
+    }
+
+    /// Read every comet and asteroid of an MPC file, in either format. Header
+    /// lines and lines that are not orbital elements are skipped.
+    pub fn parse_mpc(content: &str) -> Result<Vec<MinorBody>, String> {
+        let bodies: Vec<MinorBody> = content
+            .lines()
+            .filter_map(|line| {
+                MinorBody::parse_asteroid(line)
+                    .or_else(|_| MinorBody::parse_comet(line))
+                    .ok()
+            })
+            .collect();
+        if bodies.is_empty() && !content.trim().is_empty() {
+            return Err(String::from("no comet or asteroid orbital elements found"));
+        }
+        Ok(bodies)
+    }
+
+    /// Apparent geocentric position at the instant of `frame`.
+    pub fn apparent_position(&self, frame: &ApparentFrame) -> BodyPosition {
+        ephemeris::apparent_from_heliocentric(frame, |julian_date| {
+            self.orbit.heliocentric_position(julian_date)
+        })
+    }
+}
+
+impl Orbit {
+    /// True anomaly, in degrees, and distance from the Sun, in astronomical
+    /// units, at a Julian date in TT.
+    pub fn true_anomaly_and_distance(&self, julian_date: f64) -> (f64, f64) {
+        let q = self.perihelion_distance;
+        let e = self.eccentricity;
+        let days = julian_date - self.perihelion_time;
+
+        if (e - 1.0).abs() < PARABOLIC_TOLERANCE {
+            // Barker's equation, solved in closed form (Meeus chapter 34).
+            let w = 3.0 * GAUSSIAN_CONSTANT / (2.0 * q * q * q).sqrt() * days;
+            let y = (w / 2.0 + (w * w / 4.0 + 1.0).sqrt()).cbrt();
+            let s = y - 1.0 / y;
+            (2.0 * s.atan().to_degrees(), q * (1.0 + s * s))
+        } else if e < 1.0 {
+            let a = q / (1.0 - e);
+            let mean_anomaly = GAUSSIAN_CONSTANT / a.powf(1.5) * days;
+            let mean_anomaly = (mean_anomaly + std::f64::consts::PI)
+                .rem_euclid(2.0 * std::f64::consts::PI) - std::f64::consts::PI;
+            let anomaly = ephemeris::eccentric_anomaly(mean_anomaly, e);
+            let x = a * (anomaly.cos() - e);
+            let y = a * (1.0 - e * e).sqrt() * anomaly.sin();
+            (y.atan2(x).to_degrees(), (x * x + y * y).sqrt())
+        } else {
+            // Hyperbolic Kepler equation e sinh H - H = M, by Newton's method.
+            let a = q / (e - 1.0);
+            let mean_anomaly = GAUSSIAN_CONSTANT / a.powf(1.5) * days;
+            let mut anomaly = (2.0 * mean_anomaly / e).asinh();
+            for _ in 0..100 {
+                let delta = (e * anomaly.sinh() - anomaly - mean_anomaly)
+                    / (e * anomaly.cosh() - 1.0);
+                anomaly -= delta;
+                if delta.abs() < 1e-12 {
+                    break;
+                }
+            }
+            let x = a * (e - anomaly.cosh());
+            let y = a * (e * e - 1.0).sqrt() * anomaly.sinh();
+            (y.atan2(x).to_degrees(), (x * x + y * y).sqrt())
+        }
+    }
+
+    /// Heliocentric rectangular coordinates, in astronomical units, referred
+    /// to the ecliptic and equinox of J2000.0.
+    pub fn heliocentric_position(&self, julian_date: f64) -> [f64; 3] {
+        let (true_anomaly, distance) = self.true_anomaly_and_distance(julian_date);
+        let (sin_anomaly, cos_anomaly) = true_anomaly.to_radians().sin_cos();
+        ephemeris::orbit_to_ecliptic(
+            distance * cos_anomaly,
+            distance * sin_anomaly,
+            self.perihelion_argument,
+            self.node,
+            self.inclination,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    fn orbit(perihelion_distance: f64, eccentricity: f64) -> Orbit {
+        Orbit {
+            perihelion_distance,
+            eccentricity,
+            inclination: 0.0,
+            node: 0.0,
+            perihelion_argument: 0.0,
+            perihelion_time: time::calendar_julian_date(1998, 4, 14.4358),
+        }
+    }
+
+    #[test]
+    fn parabolic_orbit_matches_meeus_34a() {
+        let orbit = orbit(1.487_469, 1.0);
+        let (anomaly, distance) = orbit.true_anomaly_and_distance(time::calendar_julian_date(1998, 8, 5.0));
+        assert_close(anomaly, 66.788_62, 1e-4);
+        assert_close(distance, 2.133_911, 1e-5);
+    }
+
+    #[test]
+    fn conics_agree_close_to_parabolic() {
+        let date = time::calendar_julian_date(1998, 8, 5.0);
+        let (parabolic_anomaly, parabolic_distance) = orbit(1.487_469, 1.0).true_anomaly_and_distance(date);
+        for eccentricity in [0.9999, 1.0001] {
+            let (anomaly, distance) = orbit(1.487_469, eccentricity).true_anomaly_and_distance(date);
+            assert_close(anomaly, parabolic_anomaly, 0.01);
+            assert_close(distance, parabolic_distance, 1e-3);
+        }
+        for eccentricity in [0.5, 1.0, 3.0] {
+            let (anomaly, distance) = orbit(1.487_469, eccentricity)
+                .true_anomaly_and_distance(orbit(1.0, 1.0).perihelion_time);
+            assert_close(anomaly, 0.0, 1e-9);
+            assert_close(distance, 1.487_469, 1e-9);
+        }
+    }
+
+    #[test]
+    fn parses_mpc_formats() {
+        let ceres = "00001    3.34  0.15 K24AH 145.84905   73.28579   80.25414   10.58768  0.0794013  0.21424651   2.7656133  0 E2024-V47  7330 125 1801-2024 0.65 M-v 30k MPCLINUX   4000      (1) Ceres              20241101";
+        let ceres = MinorBody::parse_asteroid(ceres).unwrap();
+        assert_eq!(ceres.name, "(1) Ceres");
+        assert_close(ceres.orbit.perihelion_distance, 2.765_613_3 * (1.0 - 0.079_401_3), 1e-9);
+        assert_close(ceres.orbit.perihelion_time,
+            time::calendar_julian_date(2024, 10, 17.0) - 145.849_05 / 0.214_246_51, 1e-6);
+
+        let halley = "0001P         1986 02  9.4589  0.574602  0.967929  112.2584   59.8476  162.1869  19860307   5.5  6.0  1P/Halley                                                 98,  11";
+        let halley = MinorBody::parse_comet(halley).unwrap();
+        assert_eq!(halley.name, "1P/Halley");
+        assert_close(halley.orbit.perihelion_distance, 0.574_602, 1e-9);
+        assert_close(halley.orbit.inclination, 162.1869, 1e-9);
+
+        assert_eq!(MinorBody::parse_mpc("MPCORB header\n----------\n").unwrap_err(),
+            "no comet or asteroid orbital elements found");
+    }
+}
@@ -0,0 +1,274 @@
+//! Observability of targets over a night: positions sampled in time and the
+//! windows in which they stay above the horizon or meet other conditions.
+use chrono::{
+    DateTime,
+    Duration,
+    Utc,
+};
+
+use crate::LocalSky;
+use crate::cable_wrap::CableWrap;
+use crate::ephemeris::Body;
+use crate::target::Target;
+
+/// Altitudes of the centre of the Sun, in degrees, at the end of civil,
+/// nautical and astronomical twilight.
+pub const TWILIGHTS: [f64; 3] = [-6.0, -12.0, -18.0];
+
+/// Position of a target at one instant.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub time: DateTime<Utc>,
+    pub azimuth: f64,
+    pub altitude: f64,
+    /// Elevation of the horizon profile and elevation limit at the azimuth,
+    /// in degrees.
+    pub min_elevation: f64,
+}
+
+impl Sample {
+    /// Height above the horizon profile and elevation limit, in degrees.
+    pub fn height(&self) -> f64 {
+        self.altitude - self.min_elevation
+    }
+}
+
+/// Interval of time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Window {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Window {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// The site of `sky`, with its atmosphere and horizon, every `step` from
+/// `start` to `end`. Sampling many targets from the same skies saves
+/// computing the frame of each instant again.
+pub fn skies(sky: &LocalSky, start: DateTime<Utc>, end: DateTime<Utc>, step: Duration) -> Vec<LocalSky> {
+    let mut skies = Vec::new();
+    let mut time = start;
+    while time <= end {
+        skies.push(sky.at(time));
+        time += step;
+    }
+    skies
+}
+
+/// Positions of a target in each of the skies.
+pub fn sample_skies(skies: &[LocalSky], target: &Target) -> Vec<Sample> {
+    skies
+        .iter()
+        .map(|sky| {
+            let (azimuth, altitude) = sky.target_horizontal(target);
+            let min_elevation = sky.horizon
+                .as_ref()
+                .map_or(0.0, |horizon| horizon.min_elevation(azimuth));
+            Sample {
+                time: sky.time,
+                azimuth,
+                altitude,
+                min_elevation,
+            }
+        })
+        .collect()
+}
+
+/// Positions of a target seen from the site of `sky`, with its atmosphere
+/// and horizon, every `step` from `start` to `end`.
+pub fn sample(sky: &LocalSky, target: &Target, start: DateTime<Utc>, end: DateTime<Utc>, step: Duration) -> Vec<Sample> {
+    sample_skies(&skies(sky, start, end, step), target)
+}
+
+/// Positions of the Sun, whose altitude sets the twilight.
+pub fn sample_sun(sky: &LocalSky, start: DateTime<Utc>, end: DateTime<Utc>, step: Duration) -> Vec<Sample> {
+    sample(sky, &Target::Body { body: Body::Sun }, start, end, step)
+}
+
+/// Angular distance, in degrees, between the directions of two samples.
+pub fn separation(a: &Sample, b: &Sample) -> f64 {
+    let (sin_a, cos_a) = a.altitude.to_radians().sin_cos();
+    let (sin_b, cos_b) = b.altitude.to_radians().sin_cos();
+    let cos_separation = sin_a * sin_b + cos_a * cos_b * (a.azimuth - b.azimuth).to_radians().cos();
+    cos_separation.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// Limits within which a target can be observed, besides the horizon
+/// profile and elevation limit of the samples.
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    /// Closest distance to the Sun, in degrees.
+    pub sun_separation: f64,
+    /// Closest distance to the Moon, in degrees.
+    pub moon_separation: f64,
+    /// Stops of the mount, which turns freely without one.
+    pub cable_wrap: Option<CableWrap>,
+}
+
+/// Windows in which a target is above the horizon profile and elevation
+/// limit, far enough from the Sun and the Moon and inside the cable wrap.
+/// The samples of the three must be taken at the same times.
+pub fn observable_windows(target: &[Sample], sun: &[Sample], moon: &[Sample], constraints: &Constraints) -> Vec<Window> {
+    let margins: Vec<_> = target
+        .iter()
+        .zip(sun)
+        .zip(moon)
+        .map(|((sample, sun), moon)| {
+            let margin = sample.height()
+                .min(separation(sample, sun) - constraints.sun_separation)
+                .min(separation(sample, moon) - constraints.moon_separation)
+                .min(constraints.cable_wrap.as_ref().map_or(f64::INFINITY, |wrap| wrap.margin(sample.azimuth)));
+            (sample.time, margin)
+        })
+        .collect();
+    windows(&margins)
+}
+
+/// Intervals in which a margin sampled in time is positive. Their ends are
+/// interpolated linearly between the samples, except at the first and last
+/// sample, where a window still open is cut.
+pub fn windows(margins: &[(DateTime<Utc>, f64)]) -> Vec<Window> {
+    let crossing = |(start, start_margin): (DateTime<Utc>, f64), (end, end_margin): (DateTime<Utc>, f64)| {
+        let fraction = start_margin / (start_margin - end_margin);
+        start + Duration::milliseconds(((end - start).num_milliseconds() as f64 * fraction) as i64)
+    };
+
+    let mut windows = Vec::new();
+    let mut open = margins.first().filter(|(_, margin)| *margin >= 0.0).map(|(time, _)| *time);
+    for pair in margins.windows(2) {
+        match open {
+            None if pair[1].1 >= 0.0 => open = Some(crossing(pair[0], pair[1])),
+            Some(start) if pair[1].1 < 0.0 => {
+                windows.push(Window {
+                    start,
+                    end: crossing(pair[0], pair[1]),
+                });
+                open = None;
+            },
+            _ => {},
+        }
+    }
+    if let (Some(start), Some((end, _))) = (open, margins.last()) {
+        windows.push(Window {
+            start,
+            end: *end,
+        });
+    }
+    windows
+}
+
+/// Windows in which a target is above the horizon profile and elevation
+/// limit.
+pub fn up_windows(samples: &[Sample]) -> Vec<Window> {
+    let margins: Vec<_> = samples.iter().map(|sample| (sample.time, sample.height())).collect();
+    windows(&margins)
+}
+
+/// Times of the highest altitudes between the first and the last sample,
+/// refined by a parabola through the neighbouring samples. For a target
+/// moving with the sky they are its upper transits.
+pub fn culminations(samples: &[Sample]) -> Vec<DateTime<Utc>> {
+    samples
+        .windows(3)
+        .filter(|triple| triple[1].altitude > triple[0].altitude && triple[1].altitude >= triple[2].altitude)
+        .map(|triple| {
+            let [before, peak, after] = [triple[0].altitude, triple[1].altitude, triple[2].altitude];
+            let curvature = before - 2.0 * peak + after;
+            let offset = if curvature < 0.0 { (before - after) / (2.0 * curvature) } else { 0.0 };
+            let step = (triple[2].time - triple[1].time).num_milliseconds() as f64;
+            triple[1].time + Duration::milliseconds((offset * step) as i64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::RiseSet;
+    use crate::astrometry::CatalogPosition;
+
+    #[test]
+    fn interpolates_window_ends() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let at = |minutes: i64| start + Duration::minutes(minutes);
+        let margins = [(at(0), 1.0), (at(10), -1.0), (at(20), -1.0), (at(30), 1.0), (at(40), 2.0)];
+        assert_eq!(windows(&margins), vec![
+            Window { start: at(0), end: at(5) },
+            Window { start: at(25), end: at(40) },
+        ]);
+        assert!(windows(&margins[1..3]).is_empty());
+    }
+
+    #[test]
+    fn samples_cross_where_the_star_rises() {
+        // Orion is below the horizon, so the next rise comes before the
+        // next set.
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let sky = LocalSky::new(42.6, -8.7, time);
+        let position = CatalogPosition::j2000(83.8, -5.4);
+        let RiseSet::Rises { rise, transit, set } = sky.rise_transit_set(&position) else {
+            panic!("Orion rises at this latitude");
+        };
+
+        let target = Target::Catalog {
+            name: "Orion".to_string(),
+            position,
+        };
+        let samples = sample(&sky, &target, time, time + Duration::days(1), Duration::minutes(5));
+        let up = up_windows(&samples);
+        let window = up.iter().find(|window| window.start > time).expect("a rise within the day");
+        assert!((window.start - rise).num_seconds().abs() < 30, "rise {} against {}", window.start, rise);
+        assert!((window.end - set).num_seconds().abs() < 30, "set {} against {}", window.end, set);
+
+        let culmination = culminations(&samples)
+            .into_iter()
+            .find(|culmination| *culmination > window.start && *culmination < window.end)
+            .expect("a culmination while up");
+        assert!((culmination - transit).num_seconds().abs() < 60, "transit {} against {}", culmination, transit);
+    }
+
+    #[test]
+    fn constraints_cut_windows() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let sky = LocalSky::new(42.6, -8.7, time);
+        let (start, end, step) = (time, time + Duration::days(1), Duration::minutes(5));
+        let target = Target::Catalog {
+            name: "Orion".to_string(),
+            position: CatalogPosition::j2000(83.8, -5.4),
+        };
+        let samples = sample(&sky, &target, start, end, step);
+        let sun = sample_sun(&sky, start, end, step);
+        let moon = sample(&sky, &Target::Body { body: Body::Moon }, start, end, step);
+
+        let free = observable_windows(&samples, &sun, &moon, &Constraints::default());
+        assert_eq!(free, up_windows(&samples));
+
+        // Orion is about 100° from the Sun at the start of March.
+        let far_from_sun = Constraints {
+            sun_separation: 150.0,
+            ..Constraints::default()
+        };
+        assert!(observable_windows(&samples, &sun, &moon, &far_from_sun).is_empty());
+
+        // Orion sets in the west, where a wrap limited to the east stops it.
+        let east = Constraints {
+            cable_wrap: Some(CableWrap {
+                min: 0.0,
+                max: 180.0,
+            }),
+            ..Constraints::default()
+        };
+        let windows = observable_windows(&samples, &sun, &moon, &east);
+        let (Some(window), Some(up)) = (windows.first(), up_windows(&samples).into_iter().find(|window| window.start > time)) else {
+            panic!("Orion rises in the east");
+        };
+        assert!((window.start - up.start).num_minutes().abs() < 1);
+        assert!(window.end < up.end - Duration::hours(3));
+    }
+}
@@ -0,0 +1,156 @@
+//! Atmospheric refraction.
+//!
+//! Both models share the altitude dependence of Saemundsson's formula, which
+//! holds down to the horizon. The optical model scales it with pressure and
+//! temperature, the radio model with the radio refractivity of moist air,
+//! which at centimetre wavelengths is dominated by water vapour near the
+//! ground.
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+/// Refractivity (n - 1)·10⁶ of the standard atmosphere assumed by
+/// Saemundsson's formula: 1010 hPa and 10 °C.
+const STANDARD_REFRACTIVITY: f64 = 77.6 * 1010.0 / 283.15;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RefractionModel {
+    None,
+    Optical,
+    #[default]
+    Radio,
+}
+
+/// Site weather used to compute refraction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Atmosphere {
+    pub refraction: RefractionModel,
+    /// Air temperature, in degrees Celsius.
+    pub temperature: f64,
+    /// Air pressure, in hectopascals.
+    pub pressure: f64,
+    /// Relative humidity, from 0 to 1.
+    pub humidity: f64,
+    /// File periodically written by a local weather sensor with
+    /// `temperature`, `pressure` and `humidity` entries in TOML, overriding
+    /// the values above.
+    pub sensor_file: Option<String>,
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Atmosphere {
+            refraction: RefractionModel::default(),
+            temperature: 15.0,
+            pressure: 1013.25,
+            humidity: 0.5,
+            sensor_file: None,
+        }
+    }
+}
+
+/// Refraction at the given geometric altitude, in degrees, under the
+/// standard atmosphere of Saemundsson's formula.
+fn saemundsson(altitude: f64) -> f64 {
+    // The formula diverges well below the horizon, where nothing is
+    // observed anyway.
+    let altitude = altitude.max(-1.0);
+    1.02 / (altitude + 10.3 / (altitude + 5.11)).to_radians().tan() / 60.0
+}
+
+/// Saturation pressure of water vapour over water, in hectopascals, at the
+/// given temperature in degrees Celsius.
+fn saturation_vapour_pressure(temperature: f64) -> f64 {
+    6.112 * (17.67 * temperature / (temperature + 243.5)).exp()
+}
+
+impl Atmosphere {
+    /// Radio refractivity (n - 1)·10⁶ from the Smith-Weintraub equation.
+    pub fn radio_refractivity(&self) -> f64 {
+        let temperature = self.temperature + 273.15;
+        let vapour_pressure = self.humidity.clamp(0.0, 1.0)
+            * saturation_vapour_pressure(self.temperature);
+        77.6 * self.pressure / temperature + 3.73e5 * vapour_pressure / (temperature * temperature)
+    }
+
+    /// Amount, in degrees, by which refraction raises an object seen at the
+    /// given geometric altitude.
+    pub fn refraction(&self, altitude: f64) -> f64 {
+        match self.refraction {
+            RefractionModel::None => 0.0,
+            RefractionModel::Optical => saemundsson(altitude)
+                * (self.pressure / 1010.0)
+                * (283.0 / (273.0 + self.temperature)),
+            RefractionModel::Radio => saemundsson(altitude)
+                * self.radio_refractivity() / STANDARD_REFRACTIVITY,
+        }
+    }
+
+    /// Update the weather from `sensor_file`, if there is one. Entries missing
+    /// from the file keep their previous value.
+    pub fn read_sensor_file(&mut self) -> Result<(), String> {
+        let Some(path) = &self.sensor_file else {
+            return Ok(());
+        };
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path, e))?;
+        let values: toml::Table = toml::from_str(&content)
+            .map_err(|e| format!("invalid weather file {}: {}", path, e))?;
+
+        let read = |key: &str| -> Option<f64> {
+            match values.get(key) {
+                Some(toml::Value::Float(value)) => Some(*value),
+                Some(toml::Value::Integer(value)) => Some(*value as f64),
+                _ => None,
+            }
+        };
+        if let Some(temperature) = read("temperature") {
+            self.temperature = temperature;
+        }
+        if let Some(pressure) = read("pressure") {
+            self.pressure = pressure;
+        }
+        if let Some(humidity) = read("humidity") {
+            self.humidity = humidity;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optical_refraction_at_horizon_and_zenith() {
+        let atmosphere = Atmosphere {
+            refraction: RefractionModel::Optical,
+            temperature: 10.0,
+            pressure: 1010.0,
+            ..Atmosphere::default()
+        };
+        // An object whose geometric altitude is zero appears about 29'
+        // above the horizon, where refraction is less than the 34' of
+        // the apparent horizon.
+        assert!((atmosphere.refraction(0.0) * 60.0 - 29.0).abs() < 0.5);
+        assert!(atmosphere.refraction(90.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn humidity_increases_radio_refraction() {
+        let dry = Atmosphere {
+            humidity: 0.0,
+            ..Atmosphere::default()
+        };
+        let humid = Atmosphere {
+            humidity: 1.0,
+            ..Atmosphere::default()
+        };
+        // Dry air refracts radio waves about as much as light.
+        assert!((dry.radio_refractivity() - 273.0).abs() < 1.0);
+        assert!(humid.refraction(10.0) > 1.2 * dry.refraction(10.0));
+    }
+}
@@ -0,0 +1,331 @@
+//! Earth satellites from two-line element sets, propagated with SGP4.
+use chrono::{
+    DateTime,
+    Duration,
+    Utc,
+};
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::time;
+
+/// WGS 84 equatorial radius, in kilometres.
+const WGS84_RADIUS: f64 = 6378.137;
+
+/// WGS 84 flattening.
+const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Time step used to look for passes. Low satellites stay up for a few
+/// minutes, so it must be well under that.
+const PASS_SEARCH_STEP_SECS: i64 = 30;
+
+/// A satellite and its two-line element set.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "ElementSet")]
+pub struct Satellite {
+    pub name: String,
+    pub line1: String,
+    pub line2: String,
+    /// SGP4 initialisation, done once from the element set and boxed as it
+    /// is much larger than the rest of a target.
+    #[serde(skip)]
+    propagator: Box<Propagator>,
+}
+
+/// Serialized form of a satellite, from which the propagator is rebuilt.
+#[derive(Deserialize)]
+struct ElementSet {
+    name: String,
+    line1: String,
+    line2: String,
+}
+
+impl TryFrom<ElementSet> for Satellite {
+    type Error = String;
+
+    fn try_from(set: ElementSet) -> Result<Self, String> {
+        Satellite::new(set.name, set.line1, set.line2)
+    }
+}
+
+impl PartialEq for Satellite {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.line1 == other.line1 && self.line2 == other.line2
+    }
+}
+
+/// Visibility of a satellite above a minimum elevation.
+#[derive(Clone, Copy, Debug)]
+pub struct Pass {
+    /// Acquisition of signal, when the satellite rises above the minimum
+    /// elevation.
+    pub rise: DateTime<Utc>,
+    pub culmination: DateTime<Utc>,
+    /// Highest elevation of the pass, in degrees.
+    pub max_elevation: f64,
+    /// Loss of signal, when the satellite goes below the minimum elevation.
+    pub set: DateTime<Utc>,
+}
+
+/// Elements and SGP4 initialisation of a satellite, to propagate it many
+/// times.
+#[derive(Clone, Debug)]
+pub struct Propagator {
+    elements: sgp4::Elements,
+    constants: sgp4::Constants,
+}
+
+impl Satellite {
+    /// Satellite of a two-line element set, initialising SGP4 for it.
+    pub fn new(name: String, line1: String, line2: String) -> Result<Self, String> {
+        let elements = sgp4::Elements::from_tle(Some(name.clone()), line1.as_bytes(), line2.as_bytes())
+            .map_err(|e| format!("{}: {}", name, e))?;
+        let constants = sgp4::Constants::from_elements(&elements)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        Ok(Satellite {
+            name,
+            line1,
+            line2,
+            propagator: Box::new(Propagator {
+                elements,
+                constants,
+            }),
+        })
+    }
+
+    /// Read every element set of a TLE file, with or without a name line
+    /// before each pair of element lines. Satellites without a name are
+    /// named after their catalog number.
+    pub fn parse_tle(content: &str) -> Result<Vec<Satellite>, String> {
+        let lines: Vec<&str> = content
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+
+        let mut satellites = Vec::new();
+        let mut index = 0;
+        while index < lines.len() {
+            let (name, first) = if lines[index].starts_with("1 ") {
+                (None, index)
+            } else {
+                let name = lines[index].strip_prefix("0 ").unwrap_or(lines[index]).trim();
+                (Some(String::from(name)), index + 1)
+            };
+            let (Some(line1), Some(line2)) = (lines.get(first), lines.get(first + 1)) else {
+                return Err(format!("incomplete element set at line {}", index + 1));
+            };
+            let elements = sgp4::Elements::from_tle(name.clone(), line1.as_bytes(), line2.as_bytes())
+                .map_err(|e| format!("invalid element set at line {}: {}", first + 1, e))?;
+            let name = name.unwrap_or_else(|| format!("NORAD {}", elements.norad_id));
+            satellites.push(
+                Satellite::new(name, String::from(*line1), String::from(*line2))
+                    .map_err(|e| format!("invalid element set at line {}: {}", first + 1, e))?,
+            );
+            index = first + 2;
+        }
+        Ok(satellites)
+    }
+
+    pub fn propagator(&self) -> &Propagator {
+        &self.propagator
+    }
+
+    /// Azimuth, geometric elevation, in degrees, and range, in kilometres, of
+    /// the satellite from a site at sea level.
+    pub fn topocentric(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        time: DateTime<Utc>,
+    ) -> Result<(f64, f64, f64), String> {
+        self.propagator.topocentric(latitude, longitude, time)
+    }
+
+    /// Passes of the satellite above `min_elevation` starting between `from`
+    /// and `until`. A pass already in progress at `from` starts at `from`, and
+    /// one lasting more than a day past `until`, as for a geostationary
+    /// satellite, ends then.
+    pub fn passes(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        min_elevation: f64,
+    ) -> Result<Vec<Pass>, String> {
+        let propagator = &self.propagator;
+        let elevation = |time: DateTime<Utc>| -> Result<f64, String> {
+            Ok(propagator.topocentric(latitude, longitude, time)?.1 - min_elevation)
+        };
+        let step = Duration::seconds(PASS_SEARCH_STEP_SECS);
+
+        let mut passes = Vec::new();
+        let mut time = from;
+        let mut previous = elevation(time)?;
+        let mut current: Option<Pass> = if previous >= 0.0 {
+            Some(Pass {
+                rise: from,
+                culmination: from,
+                max_elevation: previous + min_elevation,
+                set: from,
+            })
+        } else {
+            None
+        };
+
+        let end = until + Duration::days(1);
+        while time < until || (current.is_some() && time < end) {
+            let next_time = time + step;
+            let next = elevation(next_time)?;
+            if let Some(pass) = &mut current {
+                if next + min_elevation > pass.max_elevation {
+                    pass.max_elevation = next + min_elevation;
+                    pass.culmination = next_time;
+                }
+            }
+            if previous < 0.0 && next >= 0.0 {
+                let rise = bisect(time, next_time, &elevation)?;
+                current = Some(Pass {
+                    rise,
+                    culmination: next_time,
+                    max_elevation: next + min_elevation,
+                    set: next_time,
+                });
+            } else if previous >= 0.0 && next < 0.0 {
+                if let Some(mut pass) = current.take() {
+                    pass.set = bisect(time, next_time, &elevation)?;
+                    passes.push(pass);
+                }
+            }
+            time = next_time;
+            previous = next;
+        }
+        if let Some(mut pass) = current {
+            pass.set = time;
+            passes.push(pass);
+        }
+        Ok(passes)
+    }
+}
+
+impl Propagator {
+    /// Position in the TEME frame, in kilometres.
+    pub fn position(&self, time: DateTime<Utc>) -> Result<[f64; 3], String> {
+        let minutes = self.elements
+            .datetime_to_minutes_since_epoch(&time.naive_utc())
+            .map_err(|e| e.to_string())?;
+        let prediction = self.constants.propagate(minutes).map_err(|e| e.to_string())?;
+        Ok(prediction.position)
+    }
+
+    /// Azimuth, geometric elevation, in degrees, and range, in kilometres, of
+    /// the satellite from a site at sea level.
+    pub fn topocentric(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        time: DateTime<Utc>,
+    ) -> Result<(f64, f64, f64), String> {
+        let position = self.position(time)?;
+
+        // The TEME frame turns with the Greenwich mean sidereal time.
+        let sidereal_time = time::greenwich_mean_sidereal_time(time::julian_date(time)) + longitude;
+        let (sin_theta, cos_theta) = sidereal_time.to_radians().sin_cos();
+        let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+        let eccentricity_squared = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+        let normal = WGS84_RADIUS / (1.0 - eccentricity_squared * sin_lat * sin_lat).sqrt();
+        let site = [
+            normal * cos_lat * cos_theta,
+            normal * cos_lat * sin_theta,
+            normal * (1.0 - eccentricity_squared) * sin_lat,
+        ];
+
+        let [x, y, z] = [position[0] - site[0], position[1] - site[1], position[2] - site[2]];
+        let south = sin_lat * cos_theta * x + sin_lat * sin_theta * y - cos_lat * z;
+        let east = -sin_theta * x + cos_theta * y;
+        let up = cos_lat * cos_theta * x + cos_lat * sin_theta * y + sin_lat * z;
+        let range = (x * x + y * y + z * z).sqrt();
+
+        Ok((
+            east.atan2(-south).to_degrees().rem_euclid(360.0),
+            (up / range).asin().to_degrees(),
+            range,
+        ))
+    }
+}
+
+/// Find, to the second, when `function` changes sign between `from` and
+/// `until`.
+fn bisect(
+    mut from: DateTime<Utc>,
+    mut until: DateTime<Utc>,
+    function: &impl Fn(DateTime<Utc>) -> Result<f64, String>,
+) -> Result<DateTime<Utc>, String> {
+    let rising = function(from)? < 0.0;
+    while until - from > Duration::seconds(1) {
+        let middle = from + (until - from) / 2;
+        if (function(middle)? < 0.0) == rising {
+            from = middle;
+        } else {
+            until = middle;
+        }
+    }
+    Ok(until)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISS: &str = "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+";
+
+    #[test]
+    fn parses_named_and_unnamed_element_sets() {
+        let satellites = Satellite::parse_tle(ISS).unwrap();
+        assert_eq!(satellites.len(), 1);
+        assert_eq!(satellites[0].name, "ISS (ZARYA)");
+
+        let unnamed = ISS.lines().skip(1).collect::<Vec<_>>().join("\n");
+        assert_eq!(Satellite::parse_tle(&unnamed).unwrap()[0].name, "NORAD 25544");
+
+        assert!(Satellite::parse_tle("ISS\n1 25544U").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_serde() {
+        let iss = Satellite::parse_tle(ISS).unwrap().remove(0);
+        let text = toml::to_string(&iss).unwrap();
+        assert!(!text.contains("propagator"));
+        let parsed: Satellite = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, iss);
+        assert_eq!(parsed.propagator().elements.norad_id, 25544);
+
+        let invalid = text.replace("25544U", "2554XU");
+        assert!(toml::from_str::<Satellite>(&invalid).is_err());
+    }
+
+    #[test]
+    fn passes_rise_culminate_and_set() {
+        let iss = &Satellite::parse_tle(ISS).unwrap()[0];
+        let from = iss.propagator().elements.datetime.and_utc();
+        let passes = iss.passes(40.4, -3.7, from, from + Duration::days(1), 10.0).unwrap();
+        // A low orbit inclined over the site passes a few times a day.
+        assert!(!passes.is_empty() && passes.len() < 10);
+        for pass in passes {
+            assert!(pass.rise < pass.culmination && pass.culmination < pass.set);
+            assert!(pass.max_elevation >= 10.0 && pass.max_elevation <= 90.0);
+            assert!(pass.set - pass.rise < Duration::minutes(15));
+            let (_, elevation, range) = iss.topocentric(40.4, -3.7, pass.rise).unwrap();
+            // Rise times are found to the second, over which a low
+            // satellite climbs about a tenth of a degree.
+            assert!((elevation - 10.0).abs() < 0.2);
+            assert!((400.0..2000.0).contains(&range));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Objects the telescope can point at.
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::astrometry::CatalogPosition;
+use crate::ephemeris::Body;
+use crate::ephemeris_table::EphemerisTable;
+use crate::minor_body::MinorBody;
+use crate::satellite::Satellite;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Target {
+    /// Fixed object with catalog coordinates, moving with the sky.
+    Catalog {
+        name: String,
+        position: CatalogPosition,
+    },
+    /// Solar system body, moving against the stars.
+    Body {
+        body: Body,
+    },
+    /// Earth satellite, crossing the sky in minutes.
+    Satellite {
+        satellite: Satellite,
+    },
+    /// Comet or asteroid on its own heliocentric orbit.
+    MinorBody {
+        body: MinorBody,
+    },
+    /// Object with tabulated positions, such as a spacecraft.
+    Table {
+        table: EphemerisTable,
+    },
+}
+
+impl Target {
+    pub fn name(&self) -> String {
+        match self {
+            Target::Catalog { name, .. } => name.clone(),
+            Target::Body { body } => String::from(body.name()),
+            Target::Satellite { satellite } => satellite.name.clone(),
+            Target::MinorBody { body } => body.name.clone(),
+            Target::Table { table } => table.name.clone(),
+        }
+    }
+
+    /// Fail if the position of the target is not known at `time`, which
+    /// happens outside the span of an ephemeris table.
+    pub fn check_time(&self, time: DateTime<Utc>) -> Result<(), String> {
+        match self {
+            Target::Table { table } => table.check_time(time),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if the position of the target is not known over the whole
+    /// interval from `start` to `end`.
+    pub fn check_interval(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<(), String> {
+        match self {
+            Target::Table { table } => table.check_interval(start, end),
+            _ => Ok(()),
+        }
+    }
+}
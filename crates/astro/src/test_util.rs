@@ -0,0 +1,12 @@
+//! Helpers shared by the tests of the crate.
+
+/// Fail unless `value` is within `tolerance` of `expected`.
+pub fn assert_close(value: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (value - expected).abs() < tolerance,
+        "{} differs from {} by more than {}",
+        value,
+        expected,
+        tolerance,
+    );
+}
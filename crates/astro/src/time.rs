@@ -0,0 +1,146 @@
+//! Time scales and sidereal time.
+use chrono::{
+    DateTime,
+    Datelike,
+    Utc,
+};
+
+/// Julian date of the J2000.0 epoch.
+pub const J2000: f64 = 2_451_545.0;
+
+/// Days in a Julian century.
+pub const JULIAN_CENTURY: f64 = 36_525.0;
+
+/// Julian date of a UTC instant.
+pub fn julian_date(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5
+}
+
+/// UTC instant of a Julian date, to the millisecond.
+pub fn from_julian_date(julian_date: f64) -> DateTime<Utc> {
+    let milliseconds = ((julian_date - 2_440_587.5) * 86_400_000.0).round() as i64;
+    DateTime::from_timestamp_millis(milliseconds).unwrap_or_default()
+}
+
+/// Difference between terrestrial time and universal time, in seconds, from
+/// the Espenak and Meeus polynomials.
+pub fn delta_t(time: DateTime<Utc>) -> f64 {
+    delta_t_year(time.year() as f64 + (time.ordinal0() as f64 + 0.5) / 365.25)
+}
+
+/// Espenak and Meeus polynomials for ΔT, in seconds, at a decimal year.
+fn delta_t_year(y: f64) -> f64 {
+    let long_term = |y: f64| {
+        let u = (y - 1820.0) / 100.0;
+        -20.0 + 32.0 * u * u
+    };
+    let polynomial = |t: f64, coefficients: &[f64]| {
+        coefficients.iter().rev().fold(0.0, |sum, coefficient| sum * t + coefficient)
+    };
+    if y < -500.0 {
+        long_term(y)
+    } else if y < 500.0 {
+        polynomial(y / 100.0, &[
+            10583.6, -1014.41, 33.78311, -5.952053, -0.1798452, 0.022174192, 0.0090316521,
+        ])
+    } else if y < 1600.0 {
+        polynomial((y - 1000.0) / 100.0, &[
+            1574.2, -556.01, 71.23472, 0.319781, -0.8503463, -0.005050998, 0.0083572073,
+        ])
+    } else if y < 1700.0 {
+        polynomial(y - 1600.0, &[120.0, -0.9808, -0.01532, 1.0 / 7129.0])
+    } else if y < 1800.0 {
+        polynomial(y - 1700.0, &[8.83, 0.1603, -0.0059285, 0.00013336, -1.0 / 1_174_000.0])
+    } else if y < 1860.0 {
+        polynomial(y - 1800.0, &[
+            13.72, -0.332447, 0.0068612, 0.0041116, -0.00037436, 0.0000121272, -0.0000001699,
+            0.000000000875,
+        ])
+    } else if y < 1900.0 {
+        polynomial(y - 1860.0, &[
+            7.62, 0.5737, -0.251754, 0.01680668, -0.0004473624, 1.0 / 233_174.0,
+        ])
+    } else if y < 1920.0 {
+        polynomial(y - 1900.0, &[-2.79, 1.494119, -0.0598939, 0.0061966, -0.000197])
+    } else if y < 1941.0 {
+        polynomial(y - 1920.0, &[21.20, 0.84493, -0.076100, 0.0020936])
+    } else if y < 1961.0 {
+        polynomial(y - 1950.0, &[29.07, 0.407, -1.0 / 233.0, 1.0 / 2547.0])
+    } else if y < 1986.0 {
+        polynomial(y - 1975.0, &[45.45, 1.067, -1.0 / 260.0, -1.0 / 718.0])
+    } else if y < 2005.0 {
+        polynomial(y - 2000.0, &[
+            63.86, 0.3345, -0.060374, 0.0017275, 0.000651814, 0.00002373599,
+        ])
+    } else if y < 2050.0 {
+        polynomial(y - 2000.0, &[62.92, 0.32217, 0.005589])
+    } else if y < 2150.0 {
+        long_term(y) - 0.5628 * (2150.0 - y)
+    } else {
+        long_term(y)
+    }
+}
+
+/// Julian date in terrestrial time (TT) of a UTC instant.
+pub fn julian_date_tt(time: DateTime<Utc>) -> f64 {
+    julian_date(time) + delta_t(time) / 86_400.0
+}
+
+/// Julian centuries since J2000.0.
+pub fn julian_centuries(julian_date: f64) -> f64 {
+    (julian_date - J2000) / JULIAN_CENTURY
+}
+
+/// Greenwich mean sidereal time, in degrees, at the given Julian date in
+/// universal time (IAU 1982 expression).
+pub fn greenwich_mean_sidereal_time(julian_date: f64) -> f64 {
+    let t = julian_centuries(julian_date);
+    (280.460_618_37
+        + 360.985_647_366_29 * (julian_date - J2000)
+        + 0.000_387_933 * t * t
+        - t * t * t / 38_710_000.0)
+        .rem_euclid(360.0)
+}
+
+/// Julian date of a Gregorian calendar date, the day possibly having a
+/// fractional part (Meeus chapter 7).
+pub fn calendar_julian_date(year: i32, month: u32, day: f64) -> f64 {
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 12)
+    } else {
+        (year, month)
+    };
+    let century = (year as f64 / 100.0).floor();
+    let gregorian = 2.0 - century + (century / 4.0).floor();
+    (365.25 * (year as f64 + 4716.0)).floor() + (30.6001 * (month as f64 + 1.0)).floor()
+        + day + gregorian - 1524.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_t_follows_espenak_and_meeus() {
+        let cases = [
+            (-1000.0, 25_427.7),
+            (0.0, 10_583.6),
+            (1000.0, 1574.2),
+            (1700.0, 8.83),
+            (1900.0, -2.79),
+            (1950.0, 29.07),
+            (1990.0, 56.86),
+            (2000.0, 63.86),
+            (2020.0, 71.62),
+            (2150.0, 328.5),
+        ];
+        for (year, expected) in cases {
+            let delta_t = delta_t_year(year);
+            assert!((delta_t - expected).abs() < 0.1, "{}: {} instead of {}", year, delta_t, expected);
+        }
+        // The polynomials join within a couple of seconds at their bounds.
+        for year in [500.0, 1600.0, 1800.0, 1860.0, 1920.0, 1941.0, 1961.0, 1986.0, 2005.0, 2050.0] {
+            assert!((delta_t_year(year - 1e-6) - delta_t_year(year)).abs() < 2.5, "{}", year);
+        }
+    }
+}
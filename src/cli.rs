@@ -0,0 +1,580 @@
+use clap::{
+    Args,
+    Subcommand,
+};
+
+use astro::angle;
+use astro::ephemeris::Body;
+use astro::galactic;
+use astro::target::Target;
+use crate::config;
+use crate::protocol;
+use crate::protocol::BlockState;
+use crate::protocol::ObservationBlock;
+use crate::protocol::ObservationMode;
+use crate::protocol::Request;
+use crate::protocol::Response;
+use crate::protocol::ScanPattern;
+use crate::protocol::StartConstraints;
+use crate::protocol::TelescopeMode;
+
+/// Headless commands that control a running daemon.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Show the telescope position and mode.
+    Status,
+    /// Show the horizon profile and cable wrap of the telescope.
+    Site,
+    /// Point the telescope to equatorial (--ra/--dec), galactic
+    /// (--glon/--glat) or horizontal (--az/--el) coordinates.
+    Goto(GotoArgs),
+    /// Track a star from the client configuration, the Sun, the Moon, a
+    /// planet, a source of the imported catalogs, a satellite from the
+    /// configured TLE files, a comet or asteroid from the configured MPC
+    /// files or an object of the configured ephemeris tables.
+    Track {
+        name: String,
+    },
+    /// Predict the passes of a satellite from the configured TLE files.
+    Passes {
+        name: String,
+        /// Length of the prediction, in hours.
+        #[arg(long, default_value_t = 24.0)]
+        hours: f64,
+        /// Elevation above which the satellite is acquired, in degrees.
+        #[arg(long, default_value_t = 0.0)]
+        min_elevation: f64,
+    },
+    /// Stop any movement.
+    Stop,
+    /// Move the telescope to its park position.
+    Park,
+    /// Raster scan a rectangle of horizontal coordinates.
+    Scan(ScanArgs),
+    /// Read the daemon log.
+    Log {
+        #[command(subcommand)]
+        command: LogCommand,
+    },
+    /// Show or edit the observation queue of the daemon.
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommand,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct GotoArgs {
+    /// J2000.0 right ascension, in degrees or as `05h35m17s`.
+    #[arg(long, requires = "dec", conflicts_with_all = ["az", "el", "glon", "glat"],
+          value_parser = angle::parse_right_ascension)]
+    ra: Option<f64>,
+    /// J2000.0 declination, in degrees or as `-05°23'28"`.
+    #[arg(long, requires = "ra", allow_hyphen_values = true,
+          value_parser = angle::parse_declination)]
+    dec: Option<f64>,
+    /// Galactic longitude, in degrees or as `120d09m`.
+    #[arg(long, requires = "glat", conflicts_with_all = ["az", "el"], allow_hyphen_values = true,
+          value_parser = angle::parse_galactic_longitude)]
+    glon: Option<f64>,
+    /// Galactic latitude, in degrees or as `-2d07m`.
+    #[arg(long, requires = "glon", allow_hyphen_values = true,
+          value_parser = angle::parse_galactic_latitude)]
+    glat: Option<f64>,
+    #[arg(long, requires = "el", conflicts_with_all = ["ra", "dec", "glon", "glat"], allow_negative_numbers = true)]
+    az: Option<f64>,
+    #[arg(long, requires = "az", allow_negative_numbers = true)]
+    el: Option<f64>,
+}
+
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    #[arg(long, allow_negative_numbers = true)]
+    az_from: f64,
+    #[arg(long, allow_negative_numbers = true)]
+    az_to: f64,
+    #[arg(long)]
+    el_from: f64,
+    #[arg(long)]
+    el_to: f64,
+    /// Distance between scan points, in degrees.
+    #[arg(long, default_value_t = 1.0)]
+    step: f64,
+    /// Seconds spent on each scan point.
+    #[arg(long, default_value_t = 5.0)]
+    dwell: f64,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LogCommand {
+    /// Print the last lines of the daemon log.
+    Tail {
+        /// Number of lines to print.
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
+        /// Keep printing new lines as they are logged.
+        #[arg(short, long, default_value_t = false)]
+        follow: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum QueueCommand {
+    /// List the blocks of the queue.
+    List,
+    /// Queue an observation of a target, named as for `track`.
+    Add(QueueAddArgs),
+    /// Remove a block that is not running.
+    Remove {
+        id: u64,
+    },
+    /// Remove the blocks that are done or failed.
+    Clear,
+    /// Let the scheduler start blocks by itself.
+    Enable,
+    /// Hold the scheduler, letting the running block finish.
+    Disable,
+}
+
+#[derive(Args, Debug)]
+pub struct QueueAddArgs {
+    name: String,
+    /// Length of the observation, in minutes.
+    #[arg(long)]
+    duration: f64,
+    /// Track the target, wait for it to drift through the beam or raster
+    /// scan a square around it.
+    #[arg(long, default_value = "track", value_parser = ["track", "drift", "scan"])]
+    mode: String,
+    /// Side of the scanned square, in degrees.
+    #[arg(long, default_value_t = 5.0)]
+    size: f64,
+    /// Distance between scan points, in degrees.
+    #[arg(long, default_value_t = 1.0)]
+    step: f64,
+    /// Seconds spent on each scan point.
+    #[arg(long, default_value_t = 5.0)]
+    dwell: f64,
+    /// Blocks of higher priority start first.
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    priority: i32,
+    /// Earliest start, in RFC 3339 or local `YYYY-MM-DD HH:MM`.
+    #[arg(long)]
+    not_before: Option<String>,
+    /// Latest start, after which the block fails.
+    #[arg(long)]
+    not_after: Option<String>,
+    /// Time by which the block is over, shortening it when it starts late.
+    #[arg(long)]
+    end_by: Option<String>,
+    /// Lowest elevation of the target during the block, in degrees.
+    #[arg(long, allow_negative_numbers = true)]
+    min_elevation: Option<f64>,
+    /// Closest distance of the target to the Sun during the block, in
+    /// degrees.
+    #[arg(long)]
+    sun_separation: Option<f64>,
+    /// Closest distance of the target to the Moon during the block, in
+    /// degrees.
+    #[arg(long)]
+    moon_separation: Option<f64>,
+}
+
+/// Run a client command against the daemon at `remote`, printing the
+/// response as text or JSON. Returns the process exit code.
+pub fn run(command: Command, remote: &str, json: bool) -> i32 {
+    let request = match command {
+        Command::Status => Request::Status,
+        Command::Site => Request::Site,
+        Command::Goto(GotoArgs { ra: Some(ra), dec: Some(dec), .. }) => Request::GotoEquatorial { ra, dec },
+        Command::Goto(GotoArgs { glon: Some(longitude), glat: Some(latitude), .. }) => {
+            let (ra, dec) = galactic::galactic_to_equatorial(longitude, latitude);
+            Request::GotoEquatorial { ra, dec }
+        },
+        Command::Goto(GotoArgs { az: Some(az), el: Some(el), .. }) => Request::GotoHorizontal { az, el },
+        Command::Goto(_) => {
+            eprintln!("goto needs either --ra and --dec, --glon and --glat or --az and --el");
+            return 2;
+        },
+        Command::Track { name } => {
+            let client_config = config::ClientConfig::default();
+            let Some(target) = find_target(&client_config, &name) else {
+                eprintln!("Unknown star, planet, catalog source, satellite, comet, asteroid or ephemeris table {}", name);
+                return 1;
+            };
+            Request::Track { target }
+        },
+        Command::Passes { name, hours, min_elevation } => {
+            match find_by_name(config::ClientConfig::default().satellites(), &name, |satellite| &satellite.name) {
+                Some(satellite) => Request::Passes {
+                    satellite,
+                    hours,
+                    min_elevation,
+                },
+                None => {
+                    eprintln!("Unknown satellite {}", name);
+                    return 1;
+                },
+            }
+        },
+        Command::Stop => Request::Stop,
+        Command::Park => Request::Park,
+        Command::Scan(args) => Request::Scan(ScanPattern {
+            az_from: args.az_from,
+            az_to: args.az_to,
+            el_from: args.el_from,
+            el_to: args.el_to,
+            step: args.step,
+            dwell: args.dwell,
+        }),
+        Command::Log { command: LogCommand::Tail { lines, follow } } => {
+            return tail_log(remote, lines, follow, json);
+        },
+        Command::Queue { command: QueueCommand::List } => Request::QueueList,
+        Command::Queue { command: QueueCommand::Add(args) } => match queue_block(args) {
+            Ok(block) => Request::QueueAdd {
+                blocks: vec![block],
+            },
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            },
+        },
+        Command::Queue { command: QueueCommand::Remove { id } } => Request::QueueRemove { id },
+        Command::Queue { command: QueueCommand::Clear } => Request::QueueClear,
+        Command::Queue { command: QueueCommand::Enable } => Request::SetScheduler { enabled: true },
+        Command::Queue { command: QueueCommand::Disable } => Request::SetScheduler { enabled: false },
+    };
+
+    match protocol::send(remote, &request) {
+        Ok(response) => print_response(&response, json),
+        Err(e) => {
+            eprintln!("Could not reach daemon at {}: {}", remote, e);
+            1
+        },
+    }
+}
+
+fn queue_block(args: QueueAddArgs) -> Result<ObservationBlock, String> {
+    let target = find_target(&config::ClientConfig::default(), &args.name)
+        .ok_or(format!("Unknown star, planet, catalog source, satellite, comet, asteroid or ephemeris table {}", args.name))?;
+    // Times are sent in RFC 3339, whatever form they were given in.
+    let time = |text: Option<String>| text
+        .map(|text| protocol::parse_time(&text).map(protocol::format_time))
+        .transpose();
+    Ok(ObservationBlock {
+        id: 0,
+        target,
+        mode: match args.mode.as_str() {
+            "drift" => ObservationMode::Drift,
+            "scan" => ObservationMode::Scan {
+                size: args.size,
+                step: args.step,
+                dwell: args.dwell,
+            },
+            _ => ObservationMode::Track,
+        },
+        duration: args.duration * 60.0,
+        constraints: StartConstraints {
+            not_before: time(args.not_before)?,
+            not_after: time(args.not_after)?,
+            end_by: time(args.end_by)?,
+            min_elevation: args.min_elevation,
+            sun_separation: args.sun_separation,
+            moon_separation: args.moon_separation,
+        },
+        priority: args.priority,
+        state: BlockState::Queued,
+    })
+}
+
+/// Item named `name`, ignoring case. Without an exact match, the first item
+/// whose name contains `name` is returned, so that `iss` finds the satellite
+/// `ISS (ZARYA)`.
+fn find_by_name<T>(items: Vec<T>, name: &str, item_name: impl Fn(&T) -> &str) -> Option<T> {
+    let name = name.to_lowercase();
+    let exact = items.iter().position(|item| item_name(item).to_lowercase() == name);
+    let index = exact.or_else(|| {
+        items.iter().position(|item| item_name(item).to_lowercase().contains(&name))
+    })?;
+    items.into_iter().nth(index)
+}
+
+/// Resolve a target name, ignoring case, looking in turn at the configured
+/// stars, the solar system bodies, the imported catalogs, the satellites, the
+/// comets and asteroids and the ephemeris tables.
+pub fn find_target(client_config: &config::ClientConfig, name: &str) -> Option<Target> {
+    find_target_with(client_config, name, |name| find_catalog_source(client_config, name))
+}
+
+/// Resolve a target name like [`find_target`], with the sources of the
+/// imported catalogs found by `find_source`, such as among catalogs that are
+/// already loaded.
+pub fn find_target_with(
+    client_config: &config::ClientConfig,
+    name: &str,
+    find_source: impl FnOnce(&str) -> Option<Target>,
+) -> Option<Target> {
+    let star = client_config.stars.iter().find(|star| star.name.eq_ignore_ascii_case(name)).map(|star| Target::Catalog {
+        name: star.name.clone(),
+        position: star.position(),
+    });
+    star.or_else(|| Body::parse(name).map(|body| Target::Body { body }))
+        .or_else(|| find_source(name))
+        .or_else(|| find_by_name(client_config.satellites(), name, |satellite| &satellite.name)
+            .map(|satellite| Target::Satellite { satellite }))
+        .or_else(|| find_by_name(client_config.minor_bodies(), name, |body| &body.name)
+            .map(|body| Target::MinorBody { body }))
+        .or_else(|| find_by_name(client_config.ephemeris_tables(), name, |table| &table.name)
+            .map(|table| Target::Table { table }))
+}
+
+/// Source of the imported catalogs with exactly this name, ignoring case.
+/// Catalogs are too large for partial names to be useful.
+fn find_catalog_source(client_config: &config::ClientConfig, name: &str) -> Option<Target> {
+    for layer in &client_config.catalogs {
+        let catalog = match layer.load() {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                eprintln!("Could not load catalog {}: {}", layer.name, e);
+                continue;
+            },
+        };
+        if let Some(source) = catalog.sources.into_iter().find(|source| source.name.eq_ignore_ascii_case(name)) {
+            return Some(Target::Catalog {
+                name: source.name,
+                position: source.position,
+            });
+        }
+    }
+    None
+}
+
+fn tail_log(remote: &str, lines: usize, follow: bool, json: bool) -> i32 {
+    let mut since = None;
+    loop {
+        match protocol::send(remote, &Request::LogTail { since, lines }) {
+            Ok(Response::Log { next, lines }) => {
+                if json {
+                    for line in lines {
+                        println!("{}", serde_json::to_string(&line).unwrap());
+                    }
+                } else {
+                    for line in lines {
+                        println!("{}", line);
+                    }
+                }
+                since = Some(next);
+            },
+            Ok(response) => return print_response(&response, json),
+            Err(e) => {
+                eprintln!("Could not reach daemon at {}: {}", remote, e);
+                return 1;
+            },
+        }
+        if !follow {
+            return 0;
+        }
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+fn print_response(response: &Response, json: bool) -> i32 {
+    if json {
+        println!("{}", serde_json::to_string_pretty(response).unwrap());
+        return match response {
+            Response::Error { .. } => 1,
+            _ => 0,
+        };
+    }
+
+    match response {
+        Response::Ok => println!("OK"),
+        Response::Status(status) => {
+            let mode = match &status.mode {
+                TelescopeMode::Idle => String::from("idle"),
+                TelescopeMode::Slewing => String::from("slewing"),
+                TelescopeMode::Tracking { name } => format!("tracking {}", name),
+                TelescopeMode::Scanning { point, points } => format!("scanning point {}/{}", point, points),
+                TelescopeMode::Parked => String::from("parked"),
+            };
+            println!("Time:      {}", status.time);
+            println!("Mode:      {}", mode);
+            println!("Azimuth:   {:8.3}° (target {:8.3}°, {} steps)",
+                status.azimuth, status.target_azimuth, status.azimuth_steps);
+            println!("Elevation: {:8.3}° (target {:8.3}°, {} steps)",
+                status.elevation, status.target_elevation, status.elevation_steps);
+            if let TelescopeMode::Tracking { .. } = status.mode {
+                println!("Rate:      {:+.5}°/s az, {:+.5}°/s el",
+                    status.azimuth_rate, status.elevation_rate);
+            }
+        },
+        Response::Site { horizon, cable_wrap } => {
+            match horizon.limit {
+                Some(limit) => println!("Elevation limit: {:.1}°", limit),
+                None => println!("Elevation limit: none"),
+            }
+            if horizon.points.is_empty() {
+                println!("Horizon:         flat");
+            }
+            for [azimuth, elevation] in &horizon.points {
+                println!("Horizon:         {:6.1}° az {:5.1}° el", azimuth, elevation);
+            }
+            match cable_wrap {
+                Some(wrap) => println!("Cable wrap:      {:.1}° to {:.1}°", wrap.min, wrap.max),
+                None => println!("Cable wrap:      none"),
+            }
+        },
+        Response::Passes { passes } => {
+            if passes.is_empty() {
+                println!("No passes");
+            }
+            for pass in passes {
+                println!("Rise {}  culmination {} at {:5.1}°  set {}",
+                    pass.rise, pass.culmination, pass.max_elevation, pass.set);
+            }
+        },
+        Response::Log { lines, .. } => {
+            for line in lines {
+                println!("{}", line);
+            }
+        },
+        Response::Queue { enabled, blocks } => {
+            println!("Scheduler {}", if *enabled { "enabled" } else { "held" });
+            if blocks.is_empty() {
+                println!("No blocks in the queue");
+            }
+            for block in blocks {
+                let mode = match &block.mode {
+                    ObservationMode::Track => String::from("track"),
+                    ObservationMode::Drift => String::from("drift"),
+                    ObservationMode::Scan { size, .. } => format!("scan {}°", size),
+                };
+                let state = match &block.state {
+                    BlockState::Queued => String::from("queued"),
+                    BlockState::Running { started } => format!("running since {}", started),
+                    BlockState::Done { finished } => format!("done at {}", finished),
+                    BlockState::Failed { reason } => format!("failed: {}", reason),
+                };
+                println!("{:4}  {:<20} {:<10} {:6.1} min  priority {:3}  {}",
+                    block.id, block.target.name(), mode, block.duration / 60.0, block.priority, state);
+                let constraints = &block.constraints;
+                if constraints.not_before.is_some() || constraints.not_after.is_some() {
+                    println!("      start between {} and {}",
+                        constraints.not_before.as_deref().unwrap_or("now"),
+                        constraints.not_after.as_deref().unwrap_or("any time"));
+                }
+                if let Some(end_by) = &constraints.end_by {
+                    println!("      over by {}", end_by);
+                }
+                if let Some(min_elevation) = constraints.min_elevation {
+                    println!("      above {:.1}°", min_elevation);
+                }
+                if constraints.sun_separation.is_some() || constraints.moon_separation.is_some() {
+                    println!("      at least {:.1}° from the Sun and {:.1}° from the Moon",
+                        constraints.sun_separation.unwrap_or(0.0), constraints.moon_separation.unwrap_or(0.0));
+                }
+            }
+        },
+        Response::Error { message } => {
+            eprintln!("Error: {}", message);
+            return 1;
+        },
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use astro::astrometry::CatalogPosition;
+    use astro::catalog::SourceInfo;
+    use astro::catalog::SourceType;
+
+    const ISS: &str = "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+";
+
+    fn star(name: &str, ra: f64, dec: f64) -> config::Star {
+        let position = CatalogPosition::j2000(ra, dec);
+        config::Star {
+            name: name.to_string(),
+            ra,
+            dec,
+            epoch: position.epoch,
+            pm_ra: 0.0,
+            pm_dec: 0.0,
+            parallax: 0.0,
+            info: SourceInfo {
+                flux: None,
+                frequency: None,
+                spectral_index: None,
+                magnitude: None,
+                kind: SourceType::Unknown,
+            },
+        }
+    }
+
+    /// A client configuration with two stars, one of them named like a
+    /// planet, and the satellites of a TLE file written for the test.
+    fn client_config(name: &str) -> config::ClientConfig {
+        let tle_file = std::env::temp_dir().join(format!("cli-{}-{}.tle", std::process::id(), name));
+        std::fs::write(&tle_file, ISS).unwrap();
+        config::ClientConfig {
+            remote_url: String::from("http://localhost:7777"),
+            stars: vec![star("Sirius", 101.3, -16.7), star("Jupiter", 10.0, 20.0)],
+            tle_files: vec![tle_file.to_string_lossy().into_owned()],
+            mpc_files: vec![],
+            ephemeris_files: vec![],
+            catalogs: vec![],
+            constellation_boundaries: None,
+        }
+    }
+
+    fn source(name: &str) -> Option<Target> {
+        Some(Target::Catalog {
+            name: name.to_string(),
+            position: CatalogPosition::j2000(83.6, 22.0),
+        })
+    }
+
+    #[test]
+    fn stars_come_first() {
+        let client_config = client_config("stars");
+        let sirius = find_target_with(&client_config, "SIRIUS", source).unwrap();
+        assert_eq!(sirius, Target::Catalog {
+            name: String::from("Sirius"),
+            position: client_config.stars[0].position(),
+        });
+        // A star of the configuration hides the planet of the same name.
+        let jupiter = find_target_with(&client_config, "jupiter", |_| None).unwrap();
+        assert_eq!(jupiter.name(), "Jupiter");
+        assert!(matches!(jupiter, Target::Catalog { .. }));
+    }
+
+    #[test]
+    fn bodies_come_before_catalog_sources() {
+        let client_config = client_config("bodies");
+        assert_eq!(
+            find_target_with(&client_config, "moon", source),
+            Some(Target::Body {
+                body: Body::Moon,
+            }),
+        );
+        assert_eq!(find_target_with(&client_config, "Crab", source), source("Crab"));
+    }
+
+    #[test]
+    fn satellites_come_after_catalog_sources() {
+        let client_config = client_config("satellites");
+        let satellite = find_target_with(&client_config, "ISS (ZARYA)", |_| None).unwrap();
+        assert!(matches!(satellite, Target::Satellite { .. }));
+        assert_eq!(satellite.name(), "ISS (ZARYA)");
+        assert_eq!(find_target_with(&client_config, "ISS (ZARYA)", source), source("ISS (ZARYA)"));
+        // Satellites are also found by part of their name.
+        assert_eq!(find_target_with(&client_config, "iss", |_| None), Some(satellite));
+        assert_eq!(find_target_with(&client_config, "Vega", |_| None), None);
+    }
+}
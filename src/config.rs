@@ -1,21 +1,49 @@
+#[cfg(feature = "gui")]
 use bevy::prelude::Resource;
 use serde::{
     Serialize,
     Deserialize,
 };
-use toml;
+
+use astro::angle;
+use astro::angle::Epoch;
+use astro::astrometry::CatalogPosition;
+use astro::catalog::Catalog;
+use astro::catalog::CatalogFormat;
+use astro::catalog::SourceInfo;
+use astro::catalog::SourceType;
+#[cfg(feature = "gui")]
+use astro::constellation;
+#[cfg(feature = "gui")]
+use astro::constellation::Boundary;
+use astro::ephemeris_table::EphemerisTable;
+use astro::minor_body::MinorBody;
+use astro::satellite::Satellite;
+#[cfg(feature = "daemon")]
+use astro::cable_wrap::CableWrap;
+#[cfg(feature = "daemon")]
+use astro::horizon::HorizonProfile;
+#[cfg(feature = "daemon")]
+use astro::refraction::Atmosphere;
 
 
 const DEFAULT_CLIENT_CONFIG: &str = r#"
     remote_url = "http://localhost:7777"
 
     [stars."Orion Nebula"]
-    ra = 101.56875000000
-    dec = -16.7514722222
+    ra = "05h35m17.3s"
+    dec = "-05°23'28\""
+    epoch = "J2000"
+    type = "hii_region"
+    flux = 340.0
+    frequency = 1400.0
 
     [stars.Sirius]
-    ra = 83.826791666666
-    dec = -5.39255555555
+    ra = "06h45m08.9s"
+    dec = "-16°42'58\""
+    epoch = "J2000"
+    type = "star"
+    magnitude = -1.46
 "#;
 
 const DEFAULT_SERVER_CONFIG: &str = r#"
@@ -24,9 +52,15 @@ const DEFAULT_SERVER_CONFIG: &str = r#"
     longitude = -3.703790
     azimuth_steps_per_revolution = 20000
     altitude_steps_per_revolution = 20000
+
+    [atmosphere]
+    refraction = "radio"
+    temperature = 15.0
+    pressure = 1013.25
+    humidity = 0.5
 "#;
 
-fn load_config_file(client: bool) -> String {
+fn config_file_path(client: bool) -> (String, String) {
     let dir = std::env::var("XDG_CONFIG_HOME").unwrap_or(
         std::env::var("HOME").unwrap_or(String::from("~")) + "/.config"
     ) + "/radio-telescope-controller/";
@@ -36,6 +70,11 @@ fn load_config_file(client: bool) -> String {
         "server.toml"
     };
     let path = dir.clone() + file_name;
+    (dir, path)
+}
+
+fn load_config_file(client: bool) -> String {
+    let (dir, path) = config_file_path(client);
 
     std::fs::create_dir_all(dir).unwrap();
     let write_default_config_and_return_content = | | -> String {
@@ -50,21 +89,141 @@ fn load_config_file(client: bool) -> String {
     };
 
     std::fs::read_to_string(&path)
-        .unwrap_or(write_default_config_and_return_content())
+        .unwrap_or_else(|_| write_default_config_and_return_content())
         .parse()
         .unwrap()
 }
 
+#[derive(Clone, PartialEq)]
 pub struct Star {
     pub name: String,
-    pub ra: f32,
-    pub dec: f32,
+    /// Right ascension in degrees, referred to `epoch`.
+    pub ra: f64,
+    /// Declination in degrees, referred to `epoch`.
+    pub dec: f64,
+    pub epoch: Epoch,
+    /// Proper motion in right ascension times the cosine of the declination,
+    /// in milliarcseconds per year.
+    pub pm_ra: f64,
+    /// Proper motion in declination, in milliarcseconds per year.
+    pub pm_dec: f64,
+    /// Annual parallax, in milliarcseconds.
+    pub parallax: f64,
+    pub info: SourceInfo,
+}
+
+impl Star {
+    pub fn position(&self) -> CatalogPosition {
+        CatalogPosition {
+            ra: self.ra,
+            dec: self.dec,
+            epoch: self.epoch,
+            pm_ra: self.pm_ra,
+            pm_dec: self.pm_dec,
+            parallax: self.parallax,
+        }
+    }
+}
+
+/// A catalog file shown as a layer of its own on the sky.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct CatalogLayer {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub format: CatalogFormat,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
-#[derive(Resource)]
+impl CatalogLayer {
+    pub fn load(&self) -> Result<Catalog, String> {
+        let content = std::fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        Catalog::parse(self.format, &content)
+    }
+}
+
+/// Read an angle setting that may be written either as a number of degrees
+/// or as a sexagesimal string.
+fn parse_angle_setting(
+    value: &toml::Value,
+    parse: fn(&str) -> Result<f64, String>,
+) -> Result<f64, String> {
+    match value {
+        toml::Value::Float(degrees) => parse(&degrees.to_string()),
+        toml::Value::Integer(degrees) => parse(&degrees.to_string()),
+        toml::Value::String(text) => parse(text),
+        _ => Err(format!("expected a number or a string, found {}", value)),
+    }
+}
+
+/// Star of the `stars` table of the client configuration.
+fn parse_star(star_name: &str, star_data: &toml::Value) -> Star {
+    let star_ra = star_data.get("ra").unwrap_or_else(
+        || panic!("Star {} is missing a right-ascension (ra) setting.", star_name)
+    );
+    let star_dec = star_data.get("dec").unwrap_or_else(
+        || panic!("Star {} is missing a declination (dec) setting.", star_name)
+    );
+    let star_epoch = star_data.get("epoch").or(star_data.get("equinox"));
+    let optional_float = |key: &str| -> f64 {
+        match star_data.get(key) {
+            Some(toml::Value::Float(value)) => *value,
+            Some(toml::Value::Integer(value)) => *value as f64,
+            Some(_) => panic!("Star {}: {} must be a number.", star_name, key),
+            None => 0.0,
+        }
+    };
+    let optional_number = |key: &str| star_data.get(key).map(|_| optional_float(key));
+    Star {
+        name: star_name.to_string(),
+        ra: parse_angle_setting(star_ra, angle::parse_right_ascension)
+            .unwrap_or_else(|e| panic!("Star {}: {}.", star_name, e)),
+        dec: parse_angle_setting(star_dec, angle::parse_declination)
+            .unwrap_or_else(|e| panic!("Star {}: {}.", star_name, e)),
+        epoch: match star_epoch {
+            Some(toml::Value::String(text)) => Epoch::parse(text),
+            Some(toml::Value::Float(year)) => Ok(Epoch::Julian(*year)),
+            Some(toml::Value::Integer(year)) => Ok(Epoch::Julian(*year as f64)),
+            Some(value) => Err(format!("invalid epoch {}", value)),
+            None => Ok(Epoch::default()),
+        }.unwrap_or_else(|e| panic!("Star {}: {}.", star_name, e)),
+        pm_ra: optional_float("pm_ra"),
+        pm_dec: optional_float("pm_dec"),
+        parallax: optional_float("parallax"),
+        info: SourceInfo {
+            flux: optional_number("flux"),
+            frequency: optional_number("frequency"),
+            spectral_index: optional_number("spectral_index"),
+            magnitude: optional_number("magnitude"),
+            kind: match star_data.get("type") {
+                Some(toml::Value::String(text)) => SourceType::parse(text),
+                Some(_) => panic!("Star {}: type must be a string.", star_name),
+                None => SourceType::Unknown,
+            },
+        },
+    }
+}
+
+#[cfg_attr(feature = "gui", derive(Resource))]
 pub struct ClientConfig {
     pub remote_url: String,
     pub stars: Vec<Star>,
+    /// Files of two-line element sets to load satellites from.
+    pub tle_files: Vec<String>,
+    /// Files of MPC orbital elements to load comets and asteroids from.
+    pub mpc_files: Vec<String>,
+    /// Files of tabulated ephemerides, from JPL Horizons or plain CSV.
+    pub ephemeris_files: Vec<String>,
+    /// Imported catalogs, kept apart from `stars`.
+    pub catalogs: Vec<CatalogLayer>,
+    /// File of IAU constellation boundaries, `bound_20.dat` of the CDS
+    /// catalogue VI/49, drawn instead of the bundled ones.
+    pub constellation_boundaries: Option<String>,
 }
 
 impl Default for ClientConfig {
@@ -73,37 +232,469 @@ impl Default for ClientConfig {
         let mut client_config = ClientConfig {
             remote_url: String::from(config.get("remote_url").unwrap().as_str().unwrap()),
             stars: vec![],
+            tle_files: vec![],
+            mpc_files: vec![],
+            ephemeris_files: vec![],
+            catalogs: vec![],
+            constellation_boundaries: None,
         };
-        
+
+        if let Some(tle_files) = config.get("tle_files") {
+            for file in tle_files.as_array().expect("tle_files must be a list of paths.") {
+                client_config.tle_files.push(String::from(
+                    file.as_str().expect("tle_files must be a list of paths.")
+                ));
+            }
+        }
+
+        if let Some(mpc_files) = config.get("mpc_files") {
+            for file in mpc_files.as_array().expect("mpc_files must be a list of paths.") {
+                client_config.mpc_files.push(String::from(
+                    file.as_str().expect("mpc_files must be a list of paths.")
+                ));
+            }
+        }
+
+        if let Some(ephemeris_files) = config.get("ephemeris_files") {
+            for file in ephemeris_files.as_array().expect("ephemeris_files must be a list of paths.") {
+                client_config.ephemeris_files.push(String::from(
+                    file.as_str().expect("ephemeris_files must be a list of paths.")
+                ));
+            }
+        }
+
+        if let Some(path) = config.get("constellation_boundaries") {
+            client_config.constellation_boundaries = Some(String::from(
+                path.as_str().expect("constellation_boundaries must be a path.")
+            ));
+        }
+
+        if let Some(catalogs) = config.get("catalogs") {
+            client_config.catalogs = catalogs.clone()
+                .try_into()
+                .unwrap_or_else(|e| panic!("Invalid catalogs setting: {}.", e));
+        }
+
         if let Some(star_table) = config.get("stars") {
             for (star_name, star_data) in star_table.as_table().unwrap() {
-                let star_ra = star_data.get("ra").expect(
-                    &format!("Star {} is missing a right-ascension (ra) setting.", star_name)
-                );
-                let star_dec = star_data.get("dec").expect(
-                    &format!("Star {} is missing a declination (dec) setting.", star_name)
-                );
-                client_config.stars.push(
-                    Star {
-                        name: star_name.clone(),
-                        ra: star_ra.as_float().unwrap() as f32,
-                        dec: star_dec.as_float().unwrap() as f32,
-                    }
-                );
+                client_config.stars.push(parse_star(star_name, star_data));
             }
         }
         client_config
     }
 }
 
+impl ClientConfig {
+    /// Satellites of all the TLE files. Files that cannot be read are
+    /// reported and skipped.
+    pub fn satellites(&self) -> Vec<Satellite> {
+        let mut satellites = Vec::new();
+        for path in &self.tle_files {
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| Satellite::parse_tle(&content));
+            match parsed {
+                Ok(mut file_satellites) => satellites.append(&mut file_satellites),
+                Err(e) => println!("Could not load TLE file {}: {}", path, e),
+            }
+        }
+        satellites
+    }
+
+    /// Comets and asteroids of all the MPC files. Files that cannot be read
+    /// are reported and skipped.
+    pub fn minor_bodies(&self) -> Vec<MinorBody> {
+        let mut bodies = Vec::new();
+        for path in &self.mpc_files {
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| MinorBody::parse_mpc(&content));
+            match parsed {
+                Ok(mut file_bodies) => bodies.append(&mut file_bodies),
+                Err(e) => println!("Could not load MPC file {}: {}", path, e),
+            }
+        }
+        bodies
+    }
+
+    /// Constellation boundaries of the configured file, or the bundled ones
+    /// when it is not set. A file that cannot be read is reported and no
+    /// boundaries are drawn.
+    #[cfg(feature = "gui")]
+    pub fn constellation_boundaries(&self) -> Vec<Boundary> {
+        let Some(path) = &self.constellation_boundaries else {
+            return constellation::bundled_boundaries();
+        };
+        match std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| constellation::parse_boundaries(&content)) {
+            Ok(boundaries) => boundaries,
+            Err(e) => {
+                println!("Could not load constellation boundaries {}: {}", path, e);
+                Vec::new()
+            },
+        }
+    }
+
+    /// Tables of all the ephemeris files, named after the target of Horizons
+    /// tables or else the file. Files that cannot be read are reported and
+    /// skipped.
+    pub fn ephemeris_tables(&self) -> Vec<EphemerisTable> {
+        let mut tables = Vec::new();
+        for path in &self.ephemeris_files {
+            let name = std::path::Path::new(path)
+                .file_stem()
+                .map_or(path.clone(), |stem| stem.to_string_lossy().into_owned());
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| EphemerisTable::parse(&name, &content));
+            match parsed {
+                Ok(table) => tables.push(table),
+                Err(e) => println!("Could not load ephemeris file {}: {}", path, e),
+            }
+        }
+        tables
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ClientConfig {
+    /// Write the configuration back to `client.toml`. The file is edited in
+    /// place: settings that did not change keep their formatting and
+    /// comments, and unknown settings are kept.
+    pub fn save(&self) -> std::io::Result<()> {
+        let (_, path) = config_file_path(true);
+        let mut document: toml_edit::DocumentMut = std::fs::read_to_string(&path)
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        self.update_document(&mut document);
+        std::fs::write(path, document.to_string())
+    }
+
+    /// Write the settings into a parsed configuration file, changing only
+    /// what differs.
+    fn update_document(&self, document: &mut toml_edit::DocumentMut) {
+        set_item(document, "remote_url", Some(toml::Value::String(self.remote_url.clone())));
+        let paths = |paths: &Vec<String>| (!paths.is_empty()).then(|| toml::Value::Array(
+            paths.iter().map(|path| toml::Value::String(path.clone())).collect()
+        ));
+        set_item(document, "tle_files", paths(&self.tle_files));
+        set_item(document, "mpc_files", paths(&self.mpc_files));
+        set_item(document, "ephemeris_files", paths(&self.ephemeris_files));
+        set_item(document, "constellation_boundaries",
+            self.constellation_boundaries.clone().map(toml::Value::String));
+        set_item(document, "catalogs",
+            (!self.catalogs.is_empty()).then(|| toml::Value::try_from(&self.catalogs).unwrap()));
+
+        if !document.contains_table("stars") {
+            let mut stars = toml_edit::Table::new();
+            stars.set_implicit(true);
+            document.insert("stars", toml_edit::Item::Table(stars));
+        }
+        let stars = document["stars"].as_table_like_mut().unwrap();
+        let removed: Vec<String> = stars
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .filter(|name| !self.stars.iter().any(|star| star.name == *name))
+            .collect();
+        for name in removed {
+            stars.remove(&name);
+        }
+        for star in &self.stars {
+            // A star read back as it is keeps the way it was written, such
+            // as sexagesimal angles.
+            let unchanged = stars
+                .get(&star.name)
+                .and_then(item_value)
+                .is_some_and(|value| parse_star(&star.name, &value) == *star);
+            if !unchanged {
+                let mut star_item = item(&star.name, star_value(star));
+                if let Some(table) = star_item.as_table_mut() {
+                    table.decor_mut().set_prefix("\n");
+                }
+                stars.insert(&star.name, star_item);
+            }
+        }
+    }
+}
+
+/// Settings of a star, with angles in degrees.
+#[cfg(feature = "gui")]
+fn star_value(star: &Star) -> toml::Value {
+    let mut star_data = toml::Table::new();
+    star_data.insert(String::from("ra"), toml::Value::Float(star.ra));
+    star_data.insert(String::from("dec"), toml::Value::Float(star.dec));
+    star_data.insert(String::from("epoch"), toml::Value::String(star.epoch.to_string()));
+    for (key, value) in [("pm_ra", star.pm_ra), ("pm_dec", star.pm_dec), ("parallax", star.parallax)] {
+        if value != 0.0 {
+            star_data.insert(String::from(key), toml::Value::Float(value));
+        }
+    }
+    let info = &star.info;
+    for (key, value) in [
+        ("flux", info.flux),
+        ("frequency", info.frequency),
+        ("spectral_index", info.spectral_index),
+        ("magnitude", info.magnitude),
+    ] {
+        if let Some(value) = value {
+            star_data.insert(String::from(key), toml::Value::Float(value));
+        }
+    }
+    if info.kind != SourceType::Unknown {
+        star_data.insert(String::from("type"), toml::Value::try_from(info.kind).unwrap());
+    }
+    toml::Value::Table(star_data)
+}
+
+/// Replace a top level setting of a document, or remove it when `value` is
+/// `None`, leaving it untouched when it already has this value. A replaced
+/// setting keeps its comments.
+#[cfg(feature = "gui")]
+fn set_item(document: &mut toml_edit::DocumentMut, key: &str, value: Option<toml::Value>) {
+    if document.get(key).and_then(item_value) == value {
+        return;
+    }
+    match value {
+        Some(value) => {
+            let mut new_item = item(key, value);
+            match document.get_mut(key) {
+                Some(existing) => {
+                    if let (Some(old), Some(new)) = (existing.as_value(), new_item.as_value_mut()) {
+                        *new.decor_mut() = old.decor().clone();
+                    }
+                    *existing = new_item;
+                },
+                None => {
+                    document.insert(key, new_item);
+                },
+            }
+        },
+        None => {
+            document.remove(key);
+        },
+    }
+}
+
+/// A value as an item of a document, written as `toml` writes it.
+#[cfg(feature = "gui")]
+fn item(key: &str, value: toml::Value) -> toml_edit::Item {
+    let mut table = toml::Table::new();
+    table.insert(key.to_string(), value);
+    let mut document: toml_edit::DocumentMut = toml::to_string(&table).unwrap().parse().unwrap();
+    document.remove(key).unwrap()
+}
+
+/// The value of an item of a document, as read by `toml`.
+#[cfg(feature = "gui")]
+fn item_value(item: &toml_edit::Item) -> Option<toml::Value> {
+    let mut document = toml_edit::DocumentMut::new();
+    document.insert("value", item.clone());
+    toml::from_str::<toml::Table>(&document.to_string()).ok()?.remove("value")
+}
+
+#[cfg(feature = "daemon")]
 #[derive(Deserialize)]
 pub struct ServerConfig {
     pub ip: String,
+    pub latitude: f64,
+    pub longitude: f64,
     pub azimuth_steps_per_revolution: u32,
     pub altitude_steps_per_revolution: u32,
+    /// Maximum speed of each axis, in degrees per second.
+    #[serde(default = "default_slew_rate")]
+    pub slew_rate: f64,
+    #[serde(default)]
+    pub park_azimuth: f64,
+    #[serde(default = "default_park_elevation")]
+    pub park_elevation: f64,
+    /// Site weather and refraction model applied to tracked positions.
+    #[serde(default)]
+    pub atmosphere: Atmosphere,
+    /// Lowest elevation the telescope may point at in each direction.
+    #[serde(default)]
+    pub horizon: HorizonProfile,
+    /// Stops of the azimuth axis, which turns freely when there are none.
+    #[serde(default)]
+    pub cable_wrap: Option<CableWrap>,
+    /// File keeping the observation queue, by default in the state
+    /// directory.
+    #[serde(default)]
+    pub queue_file: Option<String>,
+}
+
+#[cfg(feature = "daemon")]
+fn default_slew_rate() -> f64 {
+    2.0
 }
 
+#[cfg(feature = "daemon")]
+fn default_park_elevation() -> f64 {
+    90.0
+}
+
+#[cfg(feature = "daemon")]
 pub fn parse_server_config() -> ServerConfig {
     let config: ServerConfig = toml::from_str(load_config_file(false).as_str()).unwrap();
+    if let Some(Err(e)) = config.cable_wrap.as_ref().map(CableWrap::check) {
+        panic!("Invalid server configuration: {}", e);
+    }
     config
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn star(content: &str) -> Star {
+        let star_data: toml::Value = toml::from_str(content).unwrap();
+        parse_star("Test", &star_data)
+    }
+
+    #[test]
+    fn parses_stars() {
+        let orion = star(r#"
+            ra = "05h35m17.3s"
+            dec = "-05°23'28\""
+            epoch = "J2000"
+            type = "hii_region"
+            flux = 340
+            frequency = 1400.0
+        "#);
+        assert!((orion.ra - 83.822083).abs() < 1e-6);
+        assert!((orion.dec + 5.391111).abs() < 1e-6);
+        assert!(orion.epoch == Epoch::Julian(2000.0));
+        assert_eq!(orion.info.kind, SourceType::HiiRegion);
+        assert_eq!(orion.info.flux, Some(340.0));
+        assert_eq!(orion.info.frequency, Some(1400.0));
+        assert_eq!(orion.info.magnitude, None);
+        assert_eq!(orion.pm_ra, 0.0);
+
+        let barnard = star(r#"
+            ra = 269.452
+            dec = 4
+            equinox = "B1950"
+            pm_ra = -802.8
+            pm_dec = 10362
+            parallax = 546.98
+        "#);
+        assert_eq!(barnard.ra, 269.452);
+        assert_eq!(barnard.dec, 4.0);
+        assert!(barnard.epoch == Epoch::Besselian(1950.0));
+        assert_eq!(barnard.pm_dec, 10362.0);
+        assert_eq!(barnard.parallax, 546.98);
+        assert_eq!(barnard.info.kind, SourceType::Unknown);
+
+        assert!(star("ra = 10.0\ndec = 20.0\nepoch = 2016.0").epoch == Epoch::Julian(2016.0));
+        assert!(star("ra = 10.0\ndec = 20.0").epoch == Epoch::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Star Test is missing a declination (dec) setting.")]
+    fn stars_need_a_declination() {
+        star("ra = 10.0");
+    }
+
+    #[test]
+    #[should_panic(expected = "Star Test:")]
+    fn stars_need_valid_angles() {
+        star("ra = \"25h00m\"\ndec = 20.0");
+    }
+
+    #[test]
+    #[should_panic(expected = "Star Test:")]
+    fn stars_need_a_valid_epoch() {
+        star("ra = 10.0\ndec = 20.0\nepoch = \"X2000\"");
+    }
+
+    #[test]
+    #[should_panic(expected = "Star Test: pm_ra must be a number.")]
+    fn stars_need_numeric_proper_motions() {
+        star("ra = 10.0\ndec = 20.0\npm_ra = \"fast\"");
+    }
+
+    #[cfg(feature = "gui")]
+    const COMMENTED_CONFIG: &str = r#"# Client of the telescope on the roof.
+remote_url = "http://localhost:7777"   # on the roof network
+tle_files = [ "stations.tle",   "weather.tle" ]   # refreshed every week
+
+[stars."Orion Nebula"]
+# Brightest HII region of the winter sky.
+ra = "05h35m17.3s"
+dec = "-05°23'28\""
+epoch = "J2000"
+type = "hii_region"
+
+[stars.Sirius]
+ra = "06h45m08.9s"
+dec = "-16°42'58\""
+magnitude = -1.46
+"#;
+
+    /// The client configuration of a file, as far as saving is concerned.
+    #[cfg(feature = "gui")]
+    fn client_config(content: &str) -> ClientConfig {
+        let config: toml::Table = toml::from_str(content).unwrap();
+        ClientConfig {
+            remote_url: config["remote_url"].as_str().unwrap().to_string(),
+            stars: config["stars"]
+                .as_table()
+                .unwrap()
+                .iter()
+                .map(|(star_name, star_data)| parse_star(star_name, star_data))
+                .collect(),
+            tle_files: config["tle_files"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|path| path.as_str().unwrap().to_string())
+                .collect(),
+            mpc_files: vec![],
+            ephemeris_files: vec![],
+            catalogs: vec![],
+            constellation_boundaries: None,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn saving_keeps_an_unchanged_file() {
+        let mut document: toml_edit::DocumentMut = COMMENTED_CONFIG.parse().unwrap();
+        client_config(COMMENTED_CONFIG).update_document(&mut document);
+        assert_eq!(document.to_string(), COMMENTED_CONFIG);
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn saving_edits_the_file_in_place() {
+        let mut edited = client_config(COMMENTED_CONFIG);
+        edited.remote_url = String::from("http://telescope:7777");
+        edited.stars.retain(|star| star.name != "Sirius");
+        let mut vega = star("ra = 279.2347\ndec = 38.7837\nmagnitude = 0.03");
+        vega.name = String::from("Vega");
+        edited.stars.push(vega);
+        edited.mpc_files.push(String::from("comets.txt"));
+
+        let mut document: toml_edit::DocumentMut = COMMENTED_CONFIG.parse().unwrap();
+        edited.update_document(&mut document);
+        let content = document.to_string();
+
+        for kept in [
+            "# Client of the telescope on the roof.\n",
+            "tle_files = [ \"stations.tle\",   \"weather.tle\" ]   # refreshed every week\n",
+            "[stars.\"Orion Nebula\"]\n# Brightest HII region of the winter sky.\nra = \"05h35m17.3s\"\n",
+        ] {
+            assert!(content.contains(kept), "lost {:?} in\n{}", kept, content);
+        }
+        assert!(content.contains("remote_url = \"http://telescope:7777\"   # on the roof network\n"), "{}", content);
+        assert!(content.contains("mpc_files = [\"comets.txt\"]\n"), "{}", content);
+        assert!(!content.contains("Sirius"), "{}", content);
+        assert!(content.contains("[stars.Vega]\n"), "{}", content);
+
+        let saved = client_config(&content);
+        assert!(saved.stars.len() == 2);
+        assert!(saved.stars.iter().zip(&edited.stars).all(|(saved, star)| saved == star));
+        assert_eq!(saved.remote_url, edited.remote_url);
+    }
+}
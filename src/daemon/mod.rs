@@ -0,0 +1,148 @@
+use std::io::BufReader;
+use std::net::{
+    TcpListener,
+    TcpStream,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+mod queue;
+mod telescope;
+
+use crate::config::ServerConfig;
+use crate::protocol;
+use crate::protocol::Request;
+use crate::protocol::Response;
+use crate::protocol::SatellitePass;
+use crate::daemon::telescope::Telescope;
+
+/// Time between two updates of the telescope position.
+const CONTROL_PERIOD: Duration = Duration::from_millis(100);
+
+/// Run the telescope daemon. Clients are served on `config.ip` while a
+/// separate thread keeps the telescope moving towards its target.
+pub fn run(config: ServerConfig) {
+    let address = protocol::socket_address(&config.ip).to_string();
+    let telescope = Arc::new(Mutex::new(Telescope::new(config)));
+
+    let control_telescope = telescope.clone();
+    std::thread::spawn(move || {
+        let mut last_update = Instant::now();
+        loop {
+            std::thread::sleep(CONTROL_PERIOD);
+            let elapsed = last_update.elapsed();
+            last_update = Instant::now();
+            control_telescope.lock().unwrap().update(elapsed.as_secs_f64());
+        }
+    });
+
+    let listener = TcpListener::bind(&address)
+        .unwrap_or_else(|e| panic!("Could not listen on {}: {}", address, e));
+    telescope.lock().unwrap().log(format!("Listening on {}", address));
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let telescope = telescope.clone();
+                std::thread::spawn(move || handle_client(stream, telescope));
+            },
+            Err(e) => println!("Failed to accept client: {}", e),
+        }
+    }
+}
+
+fn handle_client(stream: TcpStream, telescope: Arc<Mutex<Telescope>>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let response = match protocol::read_message::<Request>(&mut reader) {
+            Ok(Some(request)) => handle_request(request, &mut telescope.lock().unwrap()),
+            Ok(None) => return,
+            Err(e) => Response::Error {
+                message: format!("Invalid request: {}", e),
+            },
+        };
+        if protocol::write_message(&mut writer, &response).is_err() {
+            return;
+        }
+    }
+}
+
+fn handle_request(request: Request, telescope: &mut Telescope) -> Response {
+    let result = match request {
+        Request::Status => return Response::Status(telescope.status()),
+        Request::Site => {
+            let (horizon, cable_wrap) = telescope.site();
+            return Response::Site { horizon, cable_wrap };
+        },
+        Request::Passes { satellite, hours, min_elevation } => {
+            return match telescope.passes(&satellite, hours, min_elevation) {
+                Ok(passes) => Response::Passes {
+                    passes: passes.iter().map(|pass| SatellitePass {
+                        rise: pass.rise.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                        culmination: pass.culmination.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                        max_elevation: pass.max_elevation,
+                        set: pass.set.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    }).collect(),
+                },
+                Err(message) => Response::Error { message },
+            };
+        },
+        Request::LogTail { since, lines } => {
+            let (next, lines) = telescope.log_tail(since, lines);
+            return Response::Log { next, lines };
+        },
+        Request::GotoEquatorial { ra, dec } => telescope.goto_equatorial(ra, dec),
+        Request::GotoHorizontal { az, el } => telescope.goto_horizontal(az, el),
+        Request::Track { target } => telescope.track(target),
+        Request::Stop => telescope.stop(),
+        Request::Park => telescope.park(),
+        Request::Scan(pattern) => telescope.scan(pattern),
+        Request::QueueList => return queue_response(Ok(()), telescope),
+        Request::QueueAdd { blocks } => {
+            let result = telescope.queue_add(blocks);
+            return queue_response(result, telescope);
+        },
+        Request::QueueUpdate { block } => {
+            let result = telescope.queue_update(block);
+            return queue_response(result, telescope);
+        },
+        Request::QueueRemove { id } => {
+            let result = telescope.queue_remove(id);
+            return queue_response(result, telescope);
+        },
+        Request::QueueClear => {
+            let result = telescope.queue_clear();
+            return queue_response(result, telescope);
+        },
+        Request::SetScheduler { enabled } => {
+            let result = telescope.set_scheduler(enabled);
+            return queue_response(result, telescope);
+        },
+    };
+    match result {
+        Ok(()) => Response::Ok,
+        Err(message) => Response::Error { message },
+    }
+}
+
+/// Answer a request on the queue with the queue as it is after it.
+fn queue_response(result: Result<(), String>, telescope: &Telescope) -> Response {
+    match result {
+        Ok(()) => {
+            let (enabled, blocks) = telescope.queue();
+            Response::Queue { enabled, blocks }
+        },
+        Err(message) => Response::Error { message },
+    }
+}
@@ -0,0 +1,397 @@
+//! Observation queue of the daemon, kept in a file so that it survives
+//! restarts.
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::protocol;
+use crate::protocol::BlockState;
+use crate::protocol::ObservationBlock;
+use crate::protocol::ObservationMode;
+
+/// Default queue file, in the state directory.
+pub fn default_path() -> String {
+    std::env::var("XDG_STATE_HOME").unwrap_or(
+        std::env::var("HOME").unwrap_or(String::from("~")) + "/.local/state"
+    ) + "/radio-telescope-controller/queue.json"
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Queue {
+    /// Whether the scheduler starts blocks by itself.
+    pub enabled: bool,
+    /// Id of the last block added.
+    last_id: u64,
+    pub blocks: Vec<ObservationBlock>,
+    /// File the queue is saved to, or `None` when it is kept in memory
+    /// only.
+    #[serde(skip)]
+    path: Option<String>,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Queue {
+            enabled: true,
+            last_id: 0,
+            blocks: Vec::new(),
+            path: None,
+        }
+    }
+}
+
+impl Queue {
+    /// Empty queue to be saved in `path`.
+    pub fn empty(path: &str) -> Self {
+        Queue {
+            path: Some(path.to_string()),
+            ..Queue::default()
+        }
+    }
+
+    /// Queue saved in `path`, empty when there is no such file yet. Blocks
+    /// that were running when the daemon stopped are queued again.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut queue = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("invalid queue file {}: {}", path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Queue::default(),
+            Err(e) => return Err(format!("could not read {}: {}", path, e)),
+        };
+        queue.path = Some(path.to_string());
+        for block in &mut queue.blocks {
+            if let BlockState::Running { .. } = block.state {
+                block.state = BlockState::Queued;
+            }
+        }
+        Ok(queue)
+    }
+
+    /// Write the queue to its file, through a temporary file so that a crash
+    /// never leaves half of it.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Err(String::from("the queue has no file to be saved to"));
+        };
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+        }
+        let temporary = format!("{}.tmp", path);
+        std::fs::write(&temporary, serde_json::to_string_pretty(self).unwrap())
+            .and_then(|_| std::fs::rename(&temporary, path))
+            .map_err(|e| format!("could not write {}: {}", path, e))
+    }
+
+    /// Add blocks at the end of the queue. None is added if any is invalid.
+    pub fn add(&mut self, blocks: Vec<ObservationBlock>) -> Result<(), String> {
+        for block in &blocks {
+            validate(block)?;
+        }
+        for mut block in blocks {
+            self.last_id += 1;
+            block.id = self.last_id;
+            block.state = BlockState::Queued;
+            self.blocks.push(block);
+        }
+        Ok(())
+    }
+
+    /// Replace a block that is not running, which queues it again.
+    pub fn update(&mut self, block: ObservationBlock) -> Result<(), String> {
+        validate(&block)?;
+        let queued = self.blocks
+            .iter_mut()
+            .find(|queued| queued.id == block.id)
+            .ok_or(format!("No block {} in the queue", block.id))?;
+        if let BlockState::Running { .. } = queued.state {
+            return Err(format!("Block {} is running", block.id));
+        }
+        *queued = ObservationBlock {
+            state: BlockState::Queued,
+            ..block
+        };
+        Ok(())
+    }
+
+    /// Remove a block, unless it is running.
+    pub fn remove(&mut self, id: u64) -> Result<(), String> {
+        let index = self.blocks
+            .iter()
+            .position(|block| block.id == id)
+            .ok_or(format!("No block {} in the queue", id))?;
+        if let BlockState::Running { .. } = self.blocks[index].state {
+            return Err(format!("Block {} is running, stop the telescope first", id));
+        }
+        self.blocks.remove(index);
+        Ok(())
+    }
+
+    /// Remove the blocks that are done or failed.
+    pub fn clear_finished(&mut self) {
+        self.blocks.retain(|block| matches!(block.state, BlockState::Queued | BlockState::Running { .. }));
+    }
+
+    /// Index of the running block, if any.
+    pub fn running(&self) -> Option<usize> {
+        self.blocks.iter().position(|block| matches!(block.state, BlockState::Running { .. }))
+    }
+
+    /// Fail the queued blocks that can no longer start at `now`. Returns a
+    /// line to log for each.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<String> {
+        let mut expired = Vec::new();
+        for block in &mut self.blocks {
+            if block.state != BlockState::Queued {
+                continue;
+            }
+            if let Some(deadline) = block.deadline().ok().flatten().filter(|deadline| *deadline < now) {
+                block.state = BlockState::Failed {
+                    reason: format!("not started by {}", protocol::format_time(deadline)),
+                };
+                expired.push(format!("Queue: block {} ({}) was not started by {}",
+                    block.id, block.target.name(), protocol::format_time(deadline)));
+            }
+        }
+        expired
+    }
+
+    /// Index of the queued block to start next among those that
+    /// `can_start`: the one of highest priority, then of earliest deadline,
+    /// then the first added.
+    pub fn next(&self, mut can_start: impl FnMut(&ObservationBlock) -> bool) -> Option<usize> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.state == BlockState::Queued && can_start(block))
+            .min_by_key(|(_, block)| (
+                -block.priority,
+                block.deadline().ok().flatten().unwrap_or(DateTime::<Utc>::MAX_UTC),
+                block.id,
+            ))
+            .map(|(index, _)| index)
+    }
+}
+
+fn validate(block: &ObservationBlock) -> Result<(), String> {
+    if block.duration <= 0.0 {
+        return Err(format!("The duration of {} must be positive", block.target.name()));
+    }
+    if let ObservationMode::Scan { size, step, dwell } = block.mode {
+        if size <= 0.0 || step <= 0.0 {
+            return Err(String::from("Scan size and step must be positive"));
+        }
+        if dwell < 0.0 {
+            return Err(String::from("Scan dwell must not be negative"));
+        }
+    }
+    if let (Some(not_before), Some(not_after)) = (block.not_before()?, block.not_after()?) {
+        if not_after < not_before {
+            return Err(format!("{} must start before {} but not before {}",
+                block.target.name(), not_after, not_before));
+        }
+    }
+    if let (Some(not_before), Some(end_by)) = (block.not_before()?, block.end_by()?) {
+        if end_by <= not_before {
+            return Err(format!("{} must be over by {} but not start before {}",
+                block.target.name(), end_by, not_before));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use astro::astrometry::CatalogPosition;
+    use astro::target::Target;
+    use crate::protocol::StartConstraints;
+
+    fn block(name: &str, priority: i32, not_after: Option<&str>) -> ObservationBlock {
+        ObservationBlock {
+            id: 0,
+            target: Target::Catalog {
+                name: name.to_string(),
+                position: CatalogPosition::j2000(83.8, -5.4),
+            },
+            mode: ObservationMode::Track,
+            duration: 600.0,
+            constraints: StartConstraints {
+                not_after: not_after.map(str::to_string),
+                ..StartConstraints::default()
+            },
+            priority,
+            state: BlockState::Queued,
+        }
+    }
+
+    fn time(text: &str) -> DateTime<Utc> {
+        protocol::parse_time(text).unwrap()
+    }
+
+    /// Path in the temporary directory, unique to this run and test.
+    fn temporary_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("queue-{}-{}.json", std::process::id(), name));
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn edits_blocks() {
+        let mut queue = Queue::default();
+        let mut done = block("M 42", 0, None);
+        done.state = BlockState::Done {
+            finished: String::from("2024-03-01T20:00:00Z"),
+        };
+        queue.add(vec![done, block("M 31", 0, None)]).unwrap();
+        assert_eq!(queue.blocks.iter().map(|block| block.id).collect::<Vec<_>>(), [1, 2]);
+        assert!(queue.blocks.iter().all(|block| block.state == BlockState::Queued));
+
+        // No block is added when one of them is invalid.
+        let mut invalid = block("M 1", 0, None);
+        invalid.duration = 0.0;
+        assert!(queue.add(vec![block("M 33", 0, None), invalid]).is_err());
+        assert_eq!(queue.blocks.len(), 2);
+
+        let mut updated = queue.blocks[1].clone();
+        updated.priority = 5;
+        queue.update(updated.clone()).unwrap();
+        assert_eq!(queue.blocks[1].priority, 5);
+        updated.id = 7;
+        assert!(queue.update(updated).is_err());
+
+        // A running block can neither be changed nor removed.
+        queue.blocks[0].state = BlockState::Running {
+            started: String::from("2024-03-01T20:00:00Z"),
+        };
+        assert!(queue.update(queue.blocks[0].clone()).is_err());
+        assert!(queue.remove(1).is_err());
+        queue.remove(2).unwrap();
+        assert!(queue.remove(2).is_err());
+        assert_eq!(queue.running(), Some(0));
+    }
+
+    #[test]
+    fn validates_blocks() {
+        assert!(validate(&block("M 42", 0, None)).is_ok());
+
+        let mut scan = block("M 42", 0, None);
+        scan.mode = ObservationMode::Scan {
+            size: 5.0,
+            step: 0.0,
+            dwell: 1.0,
+        };
+        assert!(validate(&scan).is_err());
+        scan.mode = ObservationMode::Scan {
+            size: 5.0,
+            step: 1.0,
+            dwell: -1.0,
+        };
+        assert_eq!(validate(&scan), Err(String::from("Scan dwell must not be negative")));
+
+        let mut late = block("M 42", 0, Some("2024-03-01T20:00:00Z"));
+        late.constraints.not_before = Some(String::from("2024-03-01T21:00:00Z"));
+        assert!(validate(&late).is_err());
+
+        let mut over = block("M 42", 0, None);
+        over.constraints.not_before = Some(String::from("2024-03-01T21:00:00Z"));
+        over.constraints.end_by = Some(String::from("2024-03-01T21:00:00Z"));
+        assert!(validate(&over).is_err());
+        over.constraints.end_by = Some(String::from("2024-03-01T21:05:00Z"));
+        assert!(validate(&over).is_ok());
+        // Starting late shortens the block to be over by its end.
+        assert_eq!(over.duration_from(time("2024-03-01T21:00:00Z")), Ok(300.0));
+        assert_eq!(over.duration_from(time("2024-03-01T20:00:00Z")), Ok(600.0));
+
+        let mut unparsable = block("M 42", 0, Some("tomorrow"));
+        unparsable.constraints.not_before = Some(String::from("today"));
+        assert!(validate(&unparsable).is_err());
+    }
+
+    #[test]
+    fn picks_by_priority_then_deadline() {
+        let mut queue = Queue::default();
+        queue.add(vec![
+            block("M 42", 0, None),
+            block("M 31", 0, Some("2024-03-01T23:00:00Z")),
+            block("M 33", 0, Some("2024-03-01T22:00:00Z")),
+            block("M 1", 1, None),
+        ]).unwrap();
+        assert_eq!(queue.next(|_| true), Some(3));
+        assert_eq!(queue.next(|block| block.priority == 0), Some(2));
+        assert_eq!(queue.next(|block| block.constraints.not_after.is_none()), Some(3));
+        assert_eq!(queue.next(|block| block.id == 1), Some(0));
+        assert_eq!(queue.next(|_| false), None);
+
+        // Only queued blocks are started.
+        queue.blocks[3].state = BlockState::Failed {
+            reason: String::from("too low"),
+        };
+        assert_eq!(queue.next(|_| true), Some(2));
+
+        let mut ending = block("NGC 7000", 0, None);
+        ending.constraints.end_by = Some(String::from("2024-03-01T21:30:00Z"));
+        queue.add(vec![ending]).unwrap();
+        assert_eq!(queue.next(|_| true), Some(4));
+    }
+
+    #[test]
+    fn expires_blocks_past_their_deadline() {
+        let mut queue = Queue::default();
+        queue.add(vec![
+            block("M 42", 0, None),
+            block("M 31", 0, Some("2024-03-01T22:00:00Z")),
+            block("M 33", 0, Some("2024-03-01T23:00:00Z")),
+        ]).unwrap();
+        let mut ending = block("M 1", 0, None);
+        ending.constraints.end_by = Some(String::from("2024-03-01T21:00:00Z"));
+        queue.add(vec![ending]).unwrap();
+
+        let expired = queue.expire(time("2024-03-01T22:30:00Z"));
+        assert_eq!(expired.len(), 2);
+        assert_eq!(queue.blocks[1].state, BlockState::Failed {
+            reason: String::from("not started by 2024-03-01T22:00:00Z"),
+        });
+        assert_eq!(queue.blocks[2].state, BlockState::Queued);
+        assert!(matches!(queue.blocks[3].state, BlockState::Failed { .. }));
+        // Failed blocks are not failed again.
+        assert!(queue.expire(time("2024-03-01T22:30:00Z")).is_empty());
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let path = temporary_path("round-trip");
+        let mut queue = Queue::empty(&path);
+        queue.enabled = false;
+        queue.add(vec![block("M 42", 2, Some("2024-03-01T22:00:00Z")), block("M 31", 0, None)]).unwrap();
+        queue.blocks[1].state = BlockState::Running {
+            started: String::from("2024-03-01T20:00:00Z"),
+        };
+        queue.save().unwrap();
+
+        let loaded = Queue::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!loaded.enabled);
+        assert_eq!(loaded.blocks[0], queue.blocks[0]);
+        // The block running when the daemon stopped is queued again.
+        assert_eq!(loaded.blocks[1].state, BlockState::Queued);
+        // Ids keep counting from the last one given.
+        let mut loaded = loaded;
+        loaded.add(vec![block("M 33", 0, None)]).unwrap();
+        assert_eq!(loaded.blocks[2].id, 3);
+
+        assert!(Queue::load(&temporary_path("missing")).unwrap().blocks.is_empty());
+        assert!(Queue::default().save().is_err());
+
+        let path = temporary_path("invalid");
+        std::fs::write(&path, "{ not json").unwrap();
+        let result = Queue::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
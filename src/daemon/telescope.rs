@@ -0,0 +1,824 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+use astro::LocalSky;
+use astro::cable_wrap::CableWrap;
+use astro::ephemeris::Body;
+use astro::observability;
+use astro::horizon::HorizonProfile;
+use astro::astrometry::CatalogPosition;
+use astro::satellite::Pass;
+use astro::satellite::Satellite;
+use astro::target::Target;
+use crate::config::ServerConfig;
+use crate::daemon::queue;
+use crate::daemon::queue::Queue;
+use crate::protocol;
+use crate::protocol::BlockState;
+use crate::protocol::ObservationBlock;
+use crate::protocol::ObservationMode;
+use crate::protocol::ScanPattern;
+use crate::protocol::TelescopeMode;
+use crate::protocol::TelescopeStatus;
+
+/// Number of log lines kept in memory for `log tail`.
+const LOG_CAPACITY: usize = 1000;
+
+/// Angular distance under which the telescope is considered on target.
+const ON_TARGET_TOLERANCE: f64 = 0.01;
+
+/// Seconds between two reads of the weather sensor file.
+const WEATHER_PERIOD: f64 = 60.0;
+
+/// Seconds between two runs of the scheduler.
+const QUEUE_PERIOD: f64 = 1.0;
+
+/// Minutes between two positions of a target checked over a block before
+/// it starts.
+const CHECK_STEP_MINUTES: i64 = 2;
+
+/// Seconds before a block that can not start is checked again, since
+/// checking it means computing its target, the Sun and the Moon over its
+/// whole length.
+const RECHECK_PERIOD: i64 = 60;
+
+/// What the telescope has been told to point at.
+enum Pointing {
+    Idle,
+    Horizontal {
+        az: f64,
+        el: f64,
+    },
+    Target(Target),
+    Scan {
+        points: Vec<(f64, f64)>,
+        point: usize,
+        dwell: f64,
+        dwelled: f64,
+    },
+    Parked,
+}
+
+pub struct Telescope {
+    config: ServerConfig,
+    pointing: Pointing,
+    /// Number of commands that changed `pointing`, to tell when one
+    /// interrupts the running block.
+    commands: u64,
+    queue: Queue,
+    /// Value of `commands` when the running block started.
+    block_commands: u64,
+    /// Position of the azimuth axis. With a cable wrap, it counts every
+    /// turn, so that it tells how wound the cables are.
+    azimuth: f64,
+    elevation: f64,
+    target_azimuth: f64,
+    target_elevation: f64,
+    log: VecDeque<String>,
+    /// Index of the first line in `log`, counting every line ever logged.
+    log_start: u64,
+    /// Seconds until the weather sensor file is read again.
+    weather_countdown: f64,
+    /// Seconds until the scheduler runs again.
+    queue_countdown: f64,
+    /// Time after which each block found unable to start is checked again,
+    /// by id.
+    rechecks: HashMap<u64, chrono::DateTime<chrono::Utc>>,
+}
+
+impl Telescope {
+    pub fn new(config: ServerConfig) -> Self {
+        let park_azimuth = config.cable_wrap
+            .as_ref()
+            .and_then(|wrap| wrap.position(config.park_azimuth, config.park_azimuth))
+            .unwrap_or(config.park_azimuth);
+        let mut telescope = Telescope {
+            azimuth: park_azimuth,
+            elevation: config.park_elevation,
+            target_azimuth: park_azimuth,
+            target_elevation: config.park_elevation,
+            config,
+            pointing: Pointing::Parked,
+            commands: 0,
+            queue: Queue::default(),
+            block_commands: 0,
+            log: VecDeque::new(),
+            log_start: 0,
+            weather_countdown: 0.0,
+            queue_countdown: 0.0,
+            rechecks: HashMap::new(),
+        };
+        telescope.log(String::from("Telescope started, parked"));
+        if let Err(e) = telescope.config.horizon.read_file() {
+            telescope.log(format!("Horizon profile: {}", e));
+        }
+        let path = telescope.config.queue_file.clone().unwrap_or_else(queue::default_path);
+        telescope.queue = match Queue::load(&path) {
+            Ok(queue) => {
+                let queued = queue.blocks.iter().filter(|block| block.state == BlockState::Queued).count();
+                telescope.log(format!("Queue: {} blocks queued in {}", queued, path));
+                queue
+            },
+            // Keep the unreadable file for whoever wants to mend it, rather
+            // than overwriting it with the next change of the queue.
+            Err(e) => {
+                let bad = format!("{}.bad", path);
+                match std::fs::rename(&path, &bad) {
+                    Ok(()) => {
+                        telescope.log(format!("Queue: {}, moved it to {} and starting with an empty queue", e, bad));
+                        Queue::empty(&path)
+                    },
+                    Err(rename_error) => {
+                        telescope.log(format!("Queue: {}, and could not move it to {}: {}, starting with an empty queue that is not saved",
+                            e, bad, rename_error));
+                        Queue::default()
+                    },
+                }
+            },
+        };
+        telescope
+    }
+
+    pub fn log(&mut self, message: String) {
+        let line = format!("{} {}", chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"), message);
+        println!("{}", line);
+        self.log.push_back(line);
+        if self.log.len() > LOG_CAPACITY {
+            self.log.pop_front();
+            self.log_start += 1;
+        }
+    }
+
+    /// Return the log lines after `since`, or the last `lines` lines if
+    /// `since` is not given, together with the index of the next line.
+    pub fn log_tail(&self, since: Option<u64>, lines: usize) -> (u64, Vec<String>) {
+        let next = self.log_start + self.log.len() as u64;
+        let first = match since {
+            Some(since) => since.clamp(self.log_start, next),
+            None => next.saturating_sub(lines as u64).max(self.log_start),
+        };
+        let lines = self.log
+            .iter()
+            .skip((first - self.log_start) as usize)
+            .cloned()
+            .collect();
+        (next, lines)
+    }
+
+    pub fn status(&self) -> TelescopeStatus {
+        let mode = match &self.pointing {
+            Pointing::Idle => TelescopeMode::Idle,
+            Pointing::Parked if self.on_target() => TelescopeMode::Parked,
+            Pointing::Target(target) if self.on_target() => TelescopeMode::Tracking {
+                name: target.name(),
+            },
+            Pointing::Scan { points, point, .. } => TelescopeMode::Scanning {
+                point: *point + 1,
+                points: points.len(),
+            },
+            _ if self.on_target() => TelescopeMode::Idle,
+            _ => TelescopeMode::Slewing,
+        };
+
+        // Motion of the target over the next second, which is the sidereal
+        // rate for catalog objects and differs from it for moving ones.
+        let (azimuth_rate, elevation_rate) = match &self.pointing {
+            Pointing::Target(target) => {
+                let now = chrono::Utc::now();
+                let (az, el) = self.horizontal_at(target, now);
+                let (next_az, next_el) = self.horizontal_at(target, now + chrono::Duration::seconds(1));
+                ((next_az - az + 180.0).rem_euclid(360.0) - 180.0, next_el - el)
+            },
+            _ => (0.0, 0.0),
+        };
+
+        TelescopeStatus {
+            time: chrono::Utc::now().to_rfc3339(),
+            mode,
+            azimuth: self.azimuth.rem_euclid(360.0),
+            elevation: self.elevation,
+            target_azimuth: self.target_azimuth.rem_euclid(360.0),
+            target_elevation: self.target_elevation,
+            azimuth_rate,
+            elevation_rate,
+            azimuth_steps: (self.azimuth / 360.0
+                * self.config.azimuth_steps_per_revolution as f64).round() as i64,
+            elevation_steps: (self.elevation / 360.0
+                * self.config.altitude_steps_per_revolution as f64).round() as i64,
+        }
+    }
+
+    /// Horizon profile and cable wrap limiting where the telescope points.
+    pub fn site(&self) -> (HorizonProfile, Option<CableWrap>) {
+        (self.config.horizon.clone(), self.config.cable_wrap.clone())
+    }
+
+    pub fn goto_horizontal(&mut self, az: f64, el: f64) -> Result<(), String> {
+        check_pointing(&self.config, az, el)?;
+        self.log(format!("Goto az {:.4} el {:.4}", az, el));
+        self.commands += 1;
+        self.pointing = Pointing::Horizontal {
+            az: az.rem_euclid(360.0),
+            el,
+        };
+        Ok(())
+    }
+
+    pub fn goto_equatorial(&mut self, ra: f64, dec: f64) -> Result<(), String> {
+        self.track(Target::Catalog {
+            name: format!("RA {:.4} Dec {:.4}", ra, dec),
+            position: CatalogPosition::j2000(ra, dec),
+        })
+    }
+
+    pub fn track(&mut self, target: Target) -> Result<(), String> {
+        target.check_time(chrono::Utc::now())?;
+        let (az, el) = self.horizontal(&target);
+        check_pointing(&self.config, az, el).map_err(|e| format!("{} is not visible: {}", target.name(), e))?;
+        match &target {
+            Target::Catalog { name, position } => self.log(format!("Tracking {} (ra {:.4} dec {:.4} {})",
+                name, position.ra, position.dec, position.epoch)),
+            Target::Body { body } => self.log(format!("Tracking {}", body)),
+            Target::Satellite { satellite } => self.log(format!("Tracking satellite {}", satellite.name)),
+            Target::MinorBody { body } => self.log(format!("Tracking {} (q {:.4} AU, e {:.4})",
+                body.name, body.orbit.perihelion_distance, body.orbit.eccentricity)),
+            Target::Table { table } => self.log(format!("Tracking {} from its ephemeris table until {}",
+                table.name, table.span().1.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))),
+        }
+        self.commands += 1;
+        self.pointing = Pointing::Target(target);
+        Ok(())
+    }
+
+    /// Passes of a satellite over the site in the coming `hours`.
+    pub fn passes(
+        &self,
+        satellite: &Satellite,
+        hours: f64,
+        min_elevation: f64,
+    ) -> Result<Vec<Pass>, String> {
+        let now = chrono::Utc::now();
+        satellite.passes(
+            self.config.latitude,
+            self.config.longitude,
+            now,
+            now + chrono::Duration::seconds((hours * 3600.0) as i64),
+            min_elevation,
+        )
+    }
+
+    pub fn stop(&mut self) -> Result<(), String> {
+        self.log(String::from("Stopped"));
+        self.commands += 1;
+        self.pointing = Pointing::Idle;
+        Ok(())
+    }
+
+    pub fn park(&mut self) -> Result<(), String> {
+        self.log(String::from("Parking"));
+        self.commands += 1;
+        self.pointing = Pointing::Parked;
+        Ok(())
+    }
+
+    pub fn scan(&mut self, pattern: ScanPattern) -> Result<(), String> {
+        if pattern.step <= 0.0 {
+            return Err(String::from("Scan step must be positive"));
+        }
+
+        let az_points = ((pattern.az_to - pattern.az_from).abs() / pattern.step).floor() as usize + 1;
+        let el_points = ((pattern.el_to - pattern.el_from).abs() / pattern.step).floor() as usize + 1;
+        let az_step = pattern.step * (pattern.az_to - pattern.az_from).signum();
+        let el_step = pattern.step * (pattern.el_to - pattern.el_from).signum();
+
+        // Go back and forth in azimuth so consecutive points are close.
+        let mut points = Vec::new();
+        for row in 0..el_points {
+            let el = pattern.el_from + row as f64 * el_step;
+            for column in 0..az_points {
+                let column = if row % 2 == 0 { column } else { az_points - 1 - column };
+                let az = pattern.az_from + column as f64 * az_step;
+                points.push((az.rem_euclid(360.0), el));
+            }
+        }
+        for (az, el) in &points {
+            check_pointing(&self.config, *az, *el)?;
+        }
+        // With a cable wrap, keep the positions of the mount, going from
+        // each point to the next the short way.
+        if let Some(wrap) = &self.config.cable_wrap {
+            let mut position = self.azimuth;
+            for (index, (az, _)) in points.iter_mut().enumerate() {
+                position = match index {
+                    0 => wrap.position(*az, position),
+                    _ => wrap.follow(position, *az),
+                }.ok_or(format!("The scan would have to unwind the cable wrap at azimuth {:.2}", az))?;
+                *az = position;
+            }
+        }
+
+        self.log(format!("Scanning {} points", points.len()));
+        self.commands += 1;
+        self.pointing = Pointing::Scan {
+            points,
+            point: 0,
+            dwell: pattern.dwell,
+            dwelled: 0.0,
+        };
+        Ok(())
+    }
+
+    /// Whether the scheduler is enabled, and the blocks of the queue.
+    pub fn queue(&self) -> (bool, Vec<ObservationBlock>) {
+        (self.queue.enabled, self.queue.blocks.clone())
+    }
+
+    pub fn queue_add(&mut self, blocks: Vec<ObservationBlock>) -> Result<(), String> {
+        let count = blocks.len();
+        self.queue.add(blocks)?;
+        self.log(format!("Queue: added {} blocks", count));
+        self.queue.save()
+    }
+
+    pub fn queue_update(&mut self, block: ObservationBlock) -> Result<(), String> {
+        let id = block.id;
+        self.queue.update(block)?;
+        self.rechecks.remove(&id);
+        self.log(format!("Queue: updated block {}", id));
+        self.queue.save()
+    }
+
+    pub fn queue_remove(&mut self, id: u64) -> Result<(), String> {
+        self.queue.remove(id)?;
+        self.rechecks.remove(&id);
+        self.log(format!("Queue: removed block {}", id));
+        self.queue.save()
+    }
+
+    pub fn queue_clear(&mut self) -> Result<(), String> {
+        self.queue.clear_finished();
+        self.log(String::from("Queue: removed the finished blocks"));
+        self.queue.save()
+    }
+
+    pub fn set_scheduler(&mut self, enabled: bool) -> Result<(), String> {
+        self.queue.enabled = enabled;
+        self.log(format!("Queue: scheduler {}", if enabled { "enabled" } else { "held" }));
+        self.queue.save()
+    }
+
+    /// Finish the running block when its time is over or it was
+    /// interrupted, fail the blocks that can no longer start, and start the
+    /// next block that can. Blocks only start while the telescope is idle
+    /// or parked, so that manual use is never taken over.
+    fn run_queue(&mut self) {
+        let now = chrono::Utc::now();
+        let mut changed = false;
+
+        if let Some(index) = self.queue.running() {
+            let block = &self.queue.blocks[index];
+            let started = match &block.state {
+                BlockState::Running { started } => protocol::parse_time(started).unwrap_or(now),
+                _ => now,
+            };
+            let end = started + seconds(block.duration_from(started).unwrap_or(block.duration));
+            let state = if self.commands != self.block_commands {
+                Some(BlockState::Failed {
+                    reason: String::from("interrupted by a command"),
+                })
+            } else if now >= end {
+                self.pointing = Pointing::Idle;
+                Some(BlockState::Done {
+                    finished: protocol::format_time(now),
+                })
+            } else if let Pointing::Idle = self.pointing {
+                // The telescope stopped by itself: a scan is over, anything
+                // else lost its target.
+                match block.mode {
+                    ObservationMode::Scan { .. } => Some(BlockState::Done {
+                        finished: protocol::format_time(now),
+                    }),
+                    _ => Some(BlockState::Failed {
+                        reason: String::from("the target went out of reach"),
+                    }),
+                }
+            } else {
+                None
+            };
+            if let Some(state) = state {
+                let message = match &state {
+                    BlockState::Failed { reason } => format!("failed: {}", reason),
+                    _ => String::from("done"),
+                };
+                let block = &mut self.queue.blocks[index];
+                block.state = state;
+                let line = format!("Queue: block {} ({}) {}", block.id, block.target.name(), message);
+                self.log(line);
+                changed = true;
+            }
+        }
+
+        let expired = self.queue.expire(now);
+        changed |= !expired.is_empty();
+        for line in expired {
+            self.log(line);
+        }
+
+        let idle = matches!(self.pointing, Pointing::Idle | Pointing::Parked);
+        if self.queue.enabled && idle && self.queue.running().is_none() {
+            // Blocks that could not start are only checked again after a
+            // while, or once they are no longer too early.
+            let mut rechecks = std::mem::take(&mut self.rechecks);
+            rechecks.retain(|_, recheck| *recheck > now);
+            let next = self.queue.next(|block| {
+                if rechecks.contains_key(&block.id) {
+                    return false;
+                }
+                let result = self.can_start(block, now);
+                if result.is_err() {
+                    let recheck = match block.not_before().ok().flatten() {
+                        Some(not_before) if not_before > now => not_before,
+                        _ => now + chrono::Duration::seconds(RECHECK_PERIOD),
+                    };
+                    rechecks.insert(block.id, recheck);
+                }
+                result.is_ok()
+            });
+            self.rechecks = rechecks;
+            if let Some(index) = next {
+                self.start_block(index, now);
+                changed = true;
+            }
+        }
+
+        if changed {
+            if let Err(e) = self.queue.save() {
+                self.log(format!("Queue: {}", e));
+            }
+        }
+    }
+
+    /// Check that a block may start at `time` and that its target stays
+    /// within reach, and meets the constraints of the block, until its end.
+    fn can_start(&self, block: &ObservationBlock, time: chrono::DateTime<chrono::Utc>) -> Result<(), String> {
+        if block.not_before()?.is_some_and(|not_before| time < not_before) {
+            return Err(String::from("too early"));
+        }
+        let duration = block.duration_from(time)?;
+        if duration <= 0.0 {
+            return Err(String::from("too late to be over in time"));
+        }
+        let end = time + seconds(duration);
+        block.target.check_interval(time, end)?;
+
+        let sky = LocalSky::new(self.config.latitude, self.config.longitude, time)
+            .with_atmosphere(&self.config.atmosphere);
+        let mut skies = observability::skies(&sky, time, end, chrono::Duration::minutes(CHECK_STEP_MINUTES));
+        if skies.last().is_some_and(|last| last.time < end) {
+            skies.push(sky.at(end));
+        }
+        let samples = observability::sample_skies(&skies, &block.target);
+        // A tracked target is followed the short way, which must not reach a
+        // stop of the cable wrap.
+        let mut position = None;
+        for sample in &samples {
+            let (az, el) = (sample.azimuth, sample.altitude);
+            check_pointing(&self.config, az, el)?;
+            if let Some(min_elevation) = block.constraints.min_elevation {
+                if el < min_elevation {
+                    return Err(format!("elevation {:.2} is under {:.2} at {}",
+                        el, min_elevation, protocol::format_time(sample.time)));
+                }
+            }
+            if let (Some(wrap), ObservationMode::Track) = (&self.config.cable_wrap, &block.mode) {
+                position = match position {
+                    None => wrap.position(az, self.azimuth),
+                    Some(position) => wrap.follow(position, az),
+                };
+                if position.is_none() {
+                    return Err(format!("azimuth {:.2} would be past a stop of the cable wrap at {}",
+                        az, protocol::format_time(sample.time)));
+                }
+            }
+        }
+        let separations = [
+            (Body::Sun, block.constraints.sun_separation),
+            (Body::Moon, block.constraints.moon_separation),
+        ];
+        for (body, min_separation) in separations {
+            let Some(min_separation) = min_separation else {
+                continue;
+            };
+            let body_samples = observability::sample_skies(&skies, &Target::Body { body });
+            for (sample, body_sample) in samples.iter().zip(&body_samples) {
+                let separation = observability::separation(sample, body_sample);
+                if separation < min_separation {
+                    return Err(format!("{:.2}° from the {} at {}, under {:.2}°",
+                        separation, body, protocol::format_time(sample.time), min_separation));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn start_block(&mut self, index: usize, now: chrono::DateTime<chrono::Utc>) {
+        let block = self.queue.blocks[index].clone();
+        let name = block.target.name();
+        let duration = block.duration_from(now).unwrap_or(block.duration);
+        self.log(format!("Queue: starting block {} ({}) for {:.0} s", block.id, name, duration));
+        let result = match block.mode {
+            ObservationMode::Track => self.track(block.target.clone()),
+            ObservationMode::Drift => {
+                let (az, el) = self.horizontal_at(&block.target, now + seconds(duration / 2.0));
+                self.goto_horizontal(az, el)
+            },
+            ObservationMode::Scan { size, step, dwell } => {
+                // Keep the square in degrees on the sky, which takes more
+                // azimuth at high elevation.
+                let (az, el) = self.horizontal(&block.target);
+                let half_width = size / 2.0 / el.to_radians().cos().max(0.1);
+                self.scan(ScanPattern {
+                    az_from: az - half_width,
+                    az_to: az + half_width,
+                    el_from: el - size / 2.0,
+                    el_to: el + size / 2.0,
+                    step,
+                    dwell,
+                })
+            },
+        };
+        self.block_commands = self.commands;
+        self.queue.blocks[index].state = match result {
+            Ok(()) => BlockState::Running {
+                started: protocol::format_time(now),
+            },
+            Err(reason) => {
+                self.log(format!("Queue: block {} ({}) failed: {}", block.id, name, reason));
+                BlockState::Failed { reason }
+            },
+        };
+    }
+
+    /// Advance the telescope position by `dt` seconds.
+    pub fn update(&mut self, dt: f64) {
+        self.weather_countdown -= dt;
+        if self.weather_countdown <= 0.0 {
+            self.weather_countdown = WEATHER_PERIOD;
+            if let Err(e) = self.config.atmosphere.read_sensor_file() {
+                self.log(format!("Weather sensor: {}", e));
+            }
+        }
+        self.queue_countdown -= dt;
+        if self.queue_countdown <= 0.0 {
+            self.queue_countdown = QUEUE_PERIOD;
+            self.run_queue();
+        }
+
+        let on_target = self.on_target();
+        let target = match &mut self.pointing {
+            Pointing::Idle => None,
+            Pointing::Horizontal { az, el } => Some((*az, *el)),
+            Pointing::Parked => Some((self.config.park_azimuth, self.config.park_elevation)),
+            // Aim where the target will be at the end of this step, so that
+            // fast movers such as satellites are not trailed by a step.
+            Pointing::Target(target) => {
+                let target = target.clone();
+                let time = chrono::Utc::now() + chrono::Duration::milliseconds((dt * 1000.0) as i64);
+                Some(self.horizontal_at(&target, time))
+            },
+            Pointing::Scan { points, point, dwell, dwelled } => {
+                if on_target {
+                    *dwelled += dt;
+                }
+                if *dwelled >= *dwell {
+                    *dwelled = 0.0;
+                    *point += 1;
+                }
+                points.get(*point).copied()
+            },
+        };
+
+        if let Pointing::Scan { points, point, .. } = &self.pointing {
+            if *point >= points.len() {
+                self.log(String::from("Scan finished"));
+                self.pointing = Pointing::Idle;
+            }
+        }
+        if let Pointing::Target(tracked) = &self.pointing {
+            let time = chrono::Utc::now() + chrono::Duration::milliseconds((dt * 1000.0) as i64);
+            if let Err(e) = tracked.check_time(time) {
+                self.log(format!("{}, stopping", e));
+                self.pointing = Pointing::Idle;
+                return;
+            }
+            if let Some((az, el)) = target {
+                if let Err(e) = check_pointing(&self.config, az, el) {
+                    let name = tracked.name();
+                    self.log(format!("{} is out of reach, stopping: {}", name, e));
+                    self.pointing = Pointing::Idle;
+                    return;
+                }
+            }
+        }
+
+        let Some((target_azimuth, target_elevation)) = target else {
+            self.target_azimuth = self.azimuth;
+            self.target_elevation = self.elevation;
+            return;
+        };
+
+        // Position of the azimuth axis to reach. Without a cable wrap the
+        // short way is taken. With one, the long way is taken when the
+        // short one crosses a stop, except while tracking, where the
+        // target is followed up to the stop.
+        let target_position = match (&self.config.cable_wrap, &self.pointing) {
+            (None, _) => Some(self.azimuth + (target_azimuth - self.azimuth + 180.0).rem_euclid(360.0) - 180.0),
+            // Scan points are already positions of the mount.
+            (Some(_), Pointing::Scan { .. }) => Some(target_azimuth),
+            (Some(wrap), Pointing::Target(_)) if on_target => wrap.follow(self.azimuth, target_azimuth),
+            (Some(wrap), _) => wrap.position(target_azimuth, self.azimuth),
+        };
+        let Some(target_position) = target_position else {
+            self.log(format!("Azimuth {:.2} can not be reached from {:.2} within the cable wrap, stopping",
+                target_azimuth, self.azimuth));
+            self.pointing = Pointing::Idle;
+            return;
+        };
+        self.target_azimuth = target_position;
+        self.target_elevation = target_elevation;
+
+        let max_step = self.config.slew_rate * dt;
+        let azimuth_error = target_position - self.azimuth;
+        let elevation_error = target_elevation - self.elevation;
+        self.azimuth += azimuth_error.clamp(-max_step, max_step);
+        if self.config.cable_wrap.is_none() {
+            self.azimuth = self.azimuth.rem_euclid(360.0);
+            self.target_azimuth = self.target_azimuth.rem_euclid(360.0);
+        }
+        self.elevation += elevation_error.clamp(-max_step, max_step);
+    }
+
+    fn on_target(&self) -> bool {
+        let azimuth_error = match self.config.cable_wrap {
+            Some(_) => self.target_azimuth - self.azimuth,
+            None => (self.target_azimuth - self.azimuth + 180.0).rem_euclid(360.0) - 180.0,
+        };
+        azimuth_error.abs() < ON_TARGET_TOLERANCE
+            && (self.target_elevation - self.elevation).abs() < ON_TARGET_TOLERANCE
+    }
+
+    /// Current azimuth and refracted elevation of a target.
+    fn horizontal(&self, target: &Target) -> (f64, f64) {
+        self.horizontal_at(target, chrono::Utc::now())
+    }
+
+    fn horizontal_at(&self, target: &Target, time: chrono::DateTime<chrono::Utc>) -> (f64, f64) {
+        LocalSky::new(self.config.latitude, self.config.longitude, time)
+            .with_atmosphere(&self.config.atmosphere)
+            .target_horizontal(target)
+    }
+}
+
+fn seconds(seconds: f64) -> chrono::Duration {
+    chrono::Duration::milliseconds((seconds * 1000.0) as i64)
+}
+
+/// Check that a direction is above the horizon profile, not past the
+/// zenith and inside the cable wrap.
+fn check_pointing(config: &ServerConfig, az: f64, el: f64) -> Result<(), String> {
+    let min_elevation = config.horizon.min_elevation(az);
+    if !(min_elevation..=90.0).contains(&el) {
+        return Err(format!("elevation {:.2} is outside of {:.2}..90 at azimuth {:.2}", el, min_elevation, az));
+    }
+    if let Some(wrap) = config.cable_wrap.as_ref().filter(|wrap| !wrap.contains(az)) {
+        return Err(format!("azimuth {:.2} is outside of the cable wrap {:.2}..{:.2}",
+            az, wrap.min, wrap.max));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use astro::refraction::Atmosphere;
+
+    /// A telescope parked at `park_azimuth`, 45° high, whose azimuth is
+    /// limited by `cable_wrap`.
+    fn wrapped_telescope(name: &str, cable_wrap: CableWrap, park_azimuth: f64) -> Telescope {
+        let queue_file = std::env::temp_dir().join(format!("telescope-{}-{}.json", std::process::id(), name));
+        Telescope::new(ServerConfig {
+            ip: String::new(),
+            latitude: 42.6,
+            longitude: -8.7,
+            azimuth_steps_per_revolution: 3600,
+            altitude_steps_per_revolution: 3600,
+            slew_rate: 2.0,
+            park_azimuth,
+            park_elevation: 45.0,
+            atmosphere: Atmosphere::default(),
+            horizon: HorizonProfile::default(),
+            cable_wrap: Some(cable_wrap),
+            queue_file: Some(queue_file.to_string_lossy().into_owned()),
+        })
+    }
+
+    #[test]
+    fn slews_the_long_way_around_a_stop() {
+        // Stops in the north: going from 30° to 330° the short way would
+        // cross them.
+        let wrap = CableWrap {
+            min: 20.0,
+            max: 340.0,
+        };
+        let mut telescope = wrapped_telescope("slew", wrap, 30.0);
+        telescope.goto_horizontal(330.0, 45.0).unwrap();
+        telescope.update(1.0);
+        assert!((telescope.azimuth - 32.0).abs() < 1e-9);
+        for _ in 0..200 {
+            telescope.update(1.0);
+            assert!((20.0..=340.0).contains(&telescope.azimuth));
+        }
+        assert!(telescope.on_target());
+        assert!((telescope.azimuth - 330.0).abs() < 1e-9);
+
+        // With more than a turn of cable, the nearest position is taken,
+        // unwinding when the short way goes past a stop.
+        let wrap = CableWrap {
+            min: -270.0,
+            max: 270.0,
+        };
+        let mut telescope = wrapped_telescope("unwind", wrap, 260.0);
+        telescope.goto_horizontal(280.0, 45.0).unwrap();
+        telescope.update(1.0);
+        assert!((telescope.azimuth - 258.0).abs() < 1e-9);
+        assert!((telescope.target_azimuth + 80.0).abs() < 1e-9);
+        assert!((telescope.status().target_azimuth - 280.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stops_tracking_at_a_stop() {
+        let mut telescope = wrapped_telescope("track", CableWrap { min: 0.0, max: 360.0 }, 0.0);
+        // Some star high in the sky now, wherever the clock is.
+        let now = chrono::Utc::now();
+        let (target, azimuth, elevation) = (0..12)
+            .map(|hour| Target::Catalog {
+                name: format!("RA {}h", hour * 2),
+                position: CatalogPosition::j2000(hour as f64 * 30.0, 30.0),
+            })
+            .map(|target| {
+                let (azimuth, elevation) = telescope.horizontal_at(&target, now);
+                (target, azimuth, elevation)
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .unwrap();
+        let (later, _) = telescope.horizontal_at(&target, now + chrono::Duration::seconds(60));
+        let turning = (later - azimuth + 180.0).rem_euclid(360.0) - 180.0;
+
+        // Put a stop just ahead of the star, with more than a turn of cable
+        // so that its direction stays reachable by unwinding.
+        telescope.config.cable_wrap = Some(if turning > 0.0 {
+            CableWrap { min: azimuth - 400.0, max: azimuth + 1e-6 }
+        } else {
+            CableWrap { min: azimuth - 1e-6, max: azimuth + 400.0 }
+        });
+        telescope.azimuth = azimuth;
+        telescope.elevation = elevation;
+        telescope.track(target).unwrap();
+        telescope.target_azimuth = azimuth;
+        telescope.target_elevation = elevation;
+
+        telescope.update(60.0);
+        assert!(matches!(telescope.pointing, Pointing::Idle));
+        assert!(telescope.log.back().is_some_and(|line| line.contains("can not be reached")));
+        assert_eq!(telescope.azimuth, azimuth);
+    }
+
+    #[test]
+    fn refuses_scans_across_a_stop() {
+        let wrap = CableWrap {
+            min: -270.0,
+            max: 270.0,
+        };
+        let mut telescope = wrapped_telescope("scan", wrap, 260.0);
+        let scan = |az_from, az_to| ScanPattern {
+            az_from,
+            az_to,
+            el_from: 30.0,
+            el_to: 35.0,
+            step: 5.0,
+            dwell: 1.0,
+        };
+        // Every direction is within reach, but going from 270° to 275°
+        // would wind the cables past their stop.
+        let result = telescope.scan(scan(260.0, 280.0));
+        assert!(result.is_err_and(|e| e.contains("unwind")), "scan accepted");
+        assert!(matches!(telescope.pointing, Pointing::Parked));
+
+        telescope.scan(scan(250.0, 270.0)).unwrap();
+        let Pointing::Scan { points, .. } = &telescope.pointing else {
+            panic!("not scanning");
+        };
+        assert!(points.iter().all(|(az, _)| (250.0..=270.0).contains(az)));
+    }
+}
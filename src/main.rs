@@ -1,10 +1,13 @@
 use clap::Parser;
 
+mod cli;
 mod config;
+#[cfg(feature = "daemon")]
+mod daemon;
+mod protocol;
+#[cfg(feature = "gui")]
 mod ui;
 
-use crate::ui::UiPlugin;
-
 /// Radio telescope controller program.
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -12,19 +15,57 @@ struct Args {
     /// Run in daemon-mode (no UI, for microcontrollers).
     #[arg(short, long, default_value_t = false)]
     daemon: bool,
+
+    /// Print daemon responses as JSON.
+    #[arg(long, global = true, default_value_t = false)]
+    json: bool,
+
+    /// Daemon URL, overriding `remote_url` from the client configuration.
+    #[arg(long, global = true)]
+    remote: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<cli::Command>,
 }
 
 fn main() {
     let args = Args::parse();
 
     if args.daemon {
-        println!("Running in daemon (server) mode...");
-        let config = config::parse_server_config();
+        run_daemon();
+    } else if let Some(command) = args.command {
+        let remote = args.remote.unwrap_or_else(|| config::ClientConfig::default().remote_url);
+        std::process::exit(cli::run(command, &remote, args.json));
     } else {
-        println!("Running in client mode...");
-        bevy::prelude::App::new()
-            .init_resource::<config::ClientConfig>()
-            .add_plugins(UiPlugin)
-            .run();
+        run_gui();
     }
 }
+
+#[cfg(feature = "daemon")]
+fn run_daemon() {
+    println!("Running in daemon (server) mode...");
+    let config = config::parse_server_config();
+    daemon::run(config);
+}
+
+#[cfg(not(feature = "daemon"))]
+fn run_daemon() {
+    eprintln!("This build does not include the daemon, rebuild with `--features daemon`");
+    std::process::exit(2);
+}
+
+#[cfg(feature = "gui")]
+fn run_gui() {
+    println!("Running in client mode...");
+    bevy::prelude::App::new()
+        .init_resource::<config::ClientConfig>()
+        .add_plugins(ui::UiPlugin)
+        .run();
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui() {
+    eprintln!("This build does not include the graphical client, rebuild with `--features gui` \
+        or use a subcommand (see `--help`)");
+    std::process::exit(2);
+}
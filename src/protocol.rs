@@ -0,0 +1,515 @@
+//! Messages exchanged between the daemon and its clients.
+//!
+//! Each request and each response is a single line of JSON sent over a TCP
+//! connection. A connection may carry any number of request/response pairs.
+use std::io::{
+    BufRead,
+    BufReader,
+    Write,
+};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use chrono::{
+    DateTime,
+    NaiveDateTime,
+    TimeZone,
+    Utc,
+};
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use astro::cable_wrap::CableWrap;
+use astro::horizon::HorizonProfile;
+use astro::satellite::Satellite;
+use astro::target::Target;
+
+/// A command sent by a client to the daemon.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    /// Ask for the limits of the telescope at its site, answered with a
+    /// [`Response::Site`].
+    Site,
+    /// Point to J2000.0 equatorial coordinates, in degrees.
+    GotoEquatorial {
+        ra: f64,
+        dec: f64,
+    },
+    GotoHorizontal {
+        az: f64,
+        el: f64,
+    },
+    Track {
+        target: Target,
+    },
+    Stop,
+    Park,
+    Scan(ScanPattern),
+    /// Predict the passes of a satellite over the site.
+    Passes {
+        satellite: Satellite,
+        hours: f64,
+        min_elevation: f64,
+    },
+    LogTail {
+        /// Only return lines after this index, as returned in a previous
+        /// [`Response::Log`]. When missing, the last `lines` are returned.
+        since: Option<u64>,
+        lines: usize,
+    },
+    /// List the observation queue. This and the requests editing the
+    /// queue are answered with a [`Response::Queue`].
+    QueueList,
+    /// Add blocks to the queue. Their ids and states are set by the daemon.
+    QueueAdd {
+        blocks: Vec<ObservationBlock>,
+    },
+    /// Replace a queued block, found by its id.
+    QueueUpdate {
+        block: ObservationBlock,
+    },
+    QueueRemove {
+        id: u64,
+    },
+    /// Remove the blocks that are done or failed.
+    QueueClear,
+    /// Let the scheduler start blocks by itself, or hold it.
+    SetScheduler {
+        enabled: bool,
+    },
+}
+
+/// An observation of the queue.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ObservationBlock {
+    /// Unique in the queue, given by the daemon.
+    #[serde(default)]
+    pub id: u64,
+    pub target: Target,
+    pub mode: ObservationMode,
+    /// Length of the observation, in seconds.
+    pub duration: f64,
+    #[serde(default)]
+    pub constraints: StartConstraints,
+    /// Blocks of higher priority start first.
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub state: BlockState,
+}
+
+#[cfg(feature = "daemon")]
+impl ObservationBlock {
+    pub fn not_before(&self) -> Result<Option<DateTime<Utc>>, String> {
+        self.constraints.not_before.as_deref().map(parse_time).transpose()
+    }
+
+    pub fn not_after(&self) -> Result<Option<DateTime<Utc>>, String> {
+        self.constraints.not_after.as_deref().map(parse_time).transpose()
+    }
+
+    pub fn end_by(&self) -> Result<Option<DateTime<Utc>>, String> {
+        self.constraints.end_by.as_deref().map(parse_time).transpose()
+    }
+
+    /// Latest time at which the block can start, if any.
+    pub fn deadline(&self) -> Result<Option<DateTime<Utc>>, String> {
+        Ok(match (self.not_after()?, self.end_by()?) {
+            (Some(not_after), Some(end_by)) => Some(not_after.min(end_by)),
+            (not_after, end_by) => not_after.or(end_by),
+        })
+    }
+
+    /// Length of the block when it starts at `start`, in seconds, cut short
+    /// to be over by its `end_by` time.
+    pub fn duration_from(&self, start: DateTime<Utc>) -> Result<f64, String> {
+        Ok(match self.end_by()? {
+            Some(end_by) => self.duration.min((end_by - start).num_milliseconds() as f64 / 1000.0),
+            None => self.duration,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ObservationMode {
+    Track,
+    /// Wait where the target will be halfway through the block and let it
+    /// drift through the beam.
+    Drift,
+    /// Raster scan a square centred on the target.
+    Scan {
+        /// Side of the square, in degrees.
+        size: f64,
+        /// Distance between scan points, in degrees.
+        step: f64,
+        /// Time spent on each scan point, in seconds.
+        dwell: f64,
+    },
+}
+
+/// Conditions for a block to start. Times are in RFC 3339.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct StartConstraints {
+    pub not_before: Option<String>,
+    /// Latest start, after which the block fails.
+    pub not_after: Option<String>,
+    /// Time by which the block is over, shortening it when it starts late.
+    pub end_by: Option<String>,
+    /// Lowest elevation of the target over the whole block, in degrees, on
+    /// top of the limits of the telescope.
+    pub min_elevation: Option<f64>,
+    /// Closest distances of the target to the Sun and to the Moon over the
+    /// whole block, in degrees.
+    pub sun_separation: Option<f64>,
+    pub moon_separation: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum BlockState {
+    #[default]
+    Queued,
+    Running {
+        started: String,
+    },
+    Done {
+        finished: String,
+    },
+    Failed {
+        reason: String,
+    },
+}
+
+/// Raster scan over a rectangle of horizontal coordinates.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScanPattern {
+    pub az_from: f64,
+    pub az_to: f64,
+    pub el_from: f64,
+    pub el_to: f64,
+    /// Distance between scan points, in degrees.
+    pub step: f64,
+    /// Time spent on each scan point, in seconds.
+    pub dwell: f64,
+}
+
+/// The answer of the daemon to a [`Request`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Status(TelescopeStatus),
+    /// Where the telescope may point, as configured on the daemon.
+    Site {
+        /// Horizon profile, with the points of its file already read.
+        horizon: HorizonProfile,
+        /// `None` when the mount turns freely in azimuth.
+        cable_wrap: Option<CableWrap>,
+    },
+    Passes {
+        passes: Vec<SatellitePass>,
+    },
+    Log {
+        /// Index to pass as `since` to get the lines following these ones.
+        next: u64,
+        lines: Vec<String>,
+    },
+    Queue {
+        /// Whether the scheduler starts blocks by itself.
+        enabled: bool,
+        blocks: Vec<ObservationBlock>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum TelescopeMode {
+    Idle,
+    Slewing,
+    Tracking {
+        name: String,
+    },
+    Scanning {
+        point: usize,
+        points: usize,
+    },
+    Parked,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelescopeStatus {
+    pub time: String,
+    pub mode: TelescopeMode,
+    pub azimuth: f64,
+    pub elevation: f64,
+    pub target_azimuth: f64,
+    pub target_elevation: f64,
+    /// Motion of the tracked target, in degrees per second.
+    #[serde(default)]
+    pub azimuth_rate: f64,
+    #[serde(default)]
+    pub elevation_rate: f64,
+    pub azimuth_steps: i64,
+    pub elevation_steps: i64,
+}
+
+/// A satellite pass, with times in RFC 3339.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SatellitePass {
+    pub rise: String,
+    pub culmination: String,
+    pub max_elevation: f64,
+    pub set: String,
+}
+
+/// Parse a time in RFC 3339, or in local time as `2024-03-01 21:30` with
+/// optional seconds.
+pub fn parse_time(text: &str) -> Result<DateTime<Utc>, String> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .and_then(|time| chrono::Local.from_local_datetime(&time).earliest())
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or(format!("Invalid time \"{}\", expected RFC 3339 or YYYY-MM-DD HH:MM", text))
+}
+
+pub fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Turn a configured URL such as `http://localhost:7777` into a socket
+/// address usable by [`std::net`].
+pub fn socket_address(url: &str) -> &str {
+    let address = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    address.trim_end_matches('/')
+}
+
+/// Read one message from a stream. Returns `None` when the peer closed the
+/// connection.
+pub fn read_message<T: for<'de> Deserialize<'de>>(
+    reader: &mut impl BufRead,
+) -> std::io::Result<Option<T>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message<T: Serialize>(
+    writer: &mut impl Write,
+    message: &T,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+    writer.flush()
+}
+
+/// Open a connection to the daemon, send a single request and wait for its
+/// response.
+pub fn send(url: &str, request: &Request) -> std::io::Result<Response> {
+    let mut stream = TcpStream::connect(socket_address(url))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    write_message(&mut stream, request)?;
+    read_message(&mut BufReader::new(stream))?.ok_or(std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "daemon closed the connection without answering",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use astro::astrometry::CatalogPosition;
+    use astro::ephemeris::Body;
+
+    fn time(text: &str) -> DateTime<Utc> {
+        parse_time(text).unwrap()
+    }
+
+    /// Serialise a message, read it back and serialise it again, which
+    /// must give the same line.
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(message: &T) -> T {
+        let mut line = Vec::new();
+        write_message(&mut line, message).unwrap();
+        let read: T = read_message(&mut line.as_slice()).unwrap().unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), serde_json::to_string(message).unwrap());
+        read
+    }
+
+    fn block(not_after: Option<&str>, end_by: Option<&str>) -> ObservationBlock {
+        ObservationBlock {
+            id: 7,
+            target: Target::Catalog {
+                name: String::from("Orion Nebula"),
+                position: CatalogPosition::j2000(83.8, -5.4),
+            },
+            mode: ObservationMode::Scan {
+                size: 2.0,
+                step: 0.5,
+                dwell: 4.0,
+            },
+            duration: 3600.0,
+            constraints: StartConstraints {
+                not_after: not_after.map(str::to_string),
+                end_by: end_by.map(str::to_string),
+                ..StartConstraints::default()
+            },
+            priority: 1,
+            state: BlockState::Queued,
+        }
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(time("2024-03-01T21:30:00Z"), Utc.with_ymd_and_hms(2024, 3, 1, 21, 30, 0).unwrap());
+        assert_eq!(time(" 2024-03-01T23:30:00+02:00 "), Utc.with_ymd_and_hms(2024, 3, 1, 21, 30, 0).unwrap());
+
+        let local = chrono::Local.with_ymd_and_hms(2024, 3, 1, 21, 30, 0).unwrap();
+        assert_eq!(time("2024-03-01 21:30"), local.with_timezone(&Utc));
+        assert_eq!(time("2024-03-01 21:30:00"), local.with_timezone(&Utc));
+    }
+
+    #[test]
+    fn rejects_invalid_times() {
+        for text in ["", "tonight", "2024-03-01", "2024-13-01 21:30", "2024-03-01 25:00", "21:30"] {
+            let e = parse_time(text).unwrap_err();
+            assert!(e.contains("Invalid time"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn strips_url_schemes() {
+        assert_eq!(socket_address("http://localhost:7777"), "localhost:7777");
+        assert_eq!(socket_address("tcp://10.0.0.2:7777/"), "10.0.0.2:7777");
+        assert_eq!(socket_address("localhost:7777"), "localhost:7777");
+    }
+
+    #[cfg(feature = "daemon")]
+    #[test]
+    fn deadline_is_the_earliest_limit() {
+        assert_eq!(block(None, None).deadline().unwrap(), None);
+        assert_eq!(
+            block(Some("2024-03-01T22:00:00Z"), None).deadline().unwrap(),
+            Some(time("2024-03-01T22:00:00Z")),
+        );
+        assert_eq!(
+            block(None, Some("2024-03-01T23:00:00Z")).deadline().unwrap(),
+            Some(time("2024-03-01T23:00:00Z")),
+        );
+        assert_eq!(
+            block(Some("2024-03-01T22:00:00Z"), Some("2024-03-01T21:00:00Z")).deadline().unwrap(),
+            Some(time("2024-03-01T21:00:00Z")),
+        );
+        assert!(block(Some("later"), None).deadline().is_err());
+    }
+
+    #[cfg(feature = "daemon")]
+    #[test]
+    fn end_by_shortens_late_blocks() {
+        let start = time("2024-03-01T21:00:00Z");
+        assert_eq!(block(None, None).duration_from(start).unwrap(), 3600.0);
+        assert_eq!(block(None, Some("2024-03-01T23:00:00Z")).duration_from(start).unwrap(), 3600.0);
+        assert_eq!(block(None, Some("2024-03-01T21:20:30Z")).duration_from(start).unwrap(), 1230.0);
+        assert!(block(None, Some("soon")).duration_from(start).is_err());
+    }
+
+    #[test]
+    fn requests_survive_the_wire() {
+        let requests = [
+            Request::Status,
+            Request::GotoEquatorial {
+                ra: 83.8,
+                dec: -5.4,
+            },
+            Request::Track {
+                target: Target::Body {
+                    body: Body::Moon,
+                },
+            },
+            Request::LogTail {
+                since: None,
+                lines: 20,
+            },
+            Request::QueueAdd {
+                blocks: vec![block(Some("2024-03-01T22:00:00Z"), None)],
+            },
+            Request::SetScheduler {
+                enabled: true,
+            },
+        ];
+        for request in &requests {
+            round_trip(request);
+        }
+
+        let line = serde_json::to_string(&requests[1]).unwrap();
+        assert_eq!(line, r#"{"request":"goto_equatorial","ra":83.8,"dec":-5.4}"#);
+        let Request::QueueAdd { blocks } = round_trip(&requests[4]) else {
+            panic!("expected a queue_add request");
+        };
+        assert_eq!(blocks, vec![block(Some("2024-03-01T22:00:00Z"), None)]);
+    }
+
+    #[test]
+    fn responses_survive_the_wire() {
+        let status = Response::Status(TelescopeStatus {
+            time: String::from("2024-03-01T21:30:00Z"),
+            mode: TelescopeMode::Tracking {
+                name: String::from("Moon"),
+            },
+            azimuth: 120.5,
+            elevation: 30.25,
+            target_azimuth: 121.0,
+            target_elevation: 30.0,
+            azimuth_rate: 0.004,
+            elevation_rate: -0.002,
+            azimuth_steps: 6694,
+            elevation_steps: 1681,
+        });
+        let Response::Status(read) = round_trip(&status) else {
+            panic!("expected a status response");
+        };
+        assert_eq!(read.mode, TelescopeMode::Tracking {
+            name: String::from("Moon"),
+        });
+        round_trip(&Response::Error {
+            message: String::from("Target is below the horizon"),
+        });
+        round_trip(&Response::Queue {
+            enabled: false,
+            blocks: vec![block(None, Some("2024-03-01T23:00:00Z"))],
+        });
+
+        // Fields added later default when an older daemon leaves them out.
+        let line = r#"{"response":"status","time":"2024-03-01T21:30:00Z","mode":{"mode":"idle"},"azimuth":0.0,"elevation":90.0,"target_azimuth":0.0,"target_elevation":90.0,"azimuth_steps":0,"elevation_steps":0}"#;
+        let Response::Status(read) = serde_json::from_str(line).unwrap() else {
+            panic!("expected a status response");
+        };
+        assert_eq!(read.mode, TelescopeMode::Idle);
+        assert_eq!(read.azimuth_rate, 0.0);
+    }
+
+    #[test]
+    fn closed_connections_read_as_none() {
+        let message: Option<Response> = read_message(&mut "".as_bytes()).unwrap();
+        assert!(message.is_none());
+        assert!(read_message::<Response>(&mut "{\"response\":\"sideways\"}\n".as_bytes()).is_err());
+    }
+}
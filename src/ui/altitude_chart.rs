@@ -0,0 +1,327 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui,
+    EguiContexts,
+};
+use chrono::{
+    DateTime,
+    Duration,
+    NaiveDate,
+    TimeZone,
+    Timelike,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::observability;
+use astro::observability::Sample;
+use astro::observability::TWILIGHTS;
+use astro::target::Target;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::clock::SimulationClock;
+use crate::ui::sphere::Star;
+use crate::ui::stars::format_time;
+
+/// Time between two samples of the curves, in minutes.
+const SAMPLE_MINUTES: i64 = 2;
+
+/// Lowest and highest altitude of the chart, in degrees. The bottom leaves
+/// room for the Sun down to the end of astronomical twilight.
+const ALTITUDE_RANGE: (f64, f64) = (-20.0, 90.0);
+
+pub struct AltitudeChartPlugin;
+
+impl Plugin for AltitudeChartPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SelectedTarget>()
+            .add_systems(Update, altitude_chart_window);
+    }
+}
+
+/// Target picked on the sky or in the chart, whose altitude is plotted.
+#[derive(Resource, Default)]
+pub struct SelectedTarget {
+    pub name: String,
+    pub target: Option<Target>,
+}
+
+/// Curves of the selected target and of the Sun over one night.
+#[derive(Default)]
+struct AltitudeChart {
+    /// Local date of the evening of the night shown. It follows the clock
+    /// until a night is picked.
+    night: Option<NaiveDate>,
+    /// Target, night and site the curves were sampled for.
+    sampled: Option<(String, NaiveDate, TelescopeConfig)>,
+    target: Vec<Sample>,
+    sun: Vec<Sample>,
+}
+
+/// Local date of the evening of the night going on at `time`: before noon
+/// it is still the night of the day before.
+pub fn night_of(time: DateTime<Utc>) -> NaiveDate {
+    let local = time.with_timezone(&chrono::Local);
+    if local.hour() < 12 {
+        local.date_naive() - Duration::days(1)
+    } else {
+        local.date_naive()
+    }
+}
+
+/// Local noon of the evening of a night, where its chart starts.
+pub fn night_start(night: NaiveDate) -> DateTime<Utc> {
+    let noon = night.and_hms_opt(12, 0, 0).unwrap();
+    chrono::Local.from_local_datetime(&noon)
+        .earliest()
+        .map_or_else(|| noon.and_utc(), |time| time.with_timezone(&Utc))
+}
+
+/// Colour of the sky at an altitude of the Sun: day, civil, nautical and
+/// astronomical twilight, and night.
+pub fn twilight_color(sun_altitude: f64) -> egui::Color32 {
+    let shades = [
+        egui::Color32::from_rgb(62, 84, 116),
+        egui::Color32::from_rgb(46, 58, 92),
+        egui::Color32::from_rgb(34, 40, 70),
+        egui::Color32::from_rgb(25, 28, 50),
+    ];
+    if sun_altitude >= 0.0 {
+        return shades[0];
+    }
+    TWILIGHTS
+        .iter()
+        .zip(&shades[1..])
+        .find(|(end, _)| sun_altitude >= **end)
+        .map_or(egui::Color32::from_rgb(14, 14, 28), |(_, shade)| *shade)
+}
+
+/// Rise, transit and set of the target within the night, as a line of text.
+fn describe_target(samples: &[Sample]) -> String {
+    let windows = observability::up_windows(samples);
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return String::new();
+    };
+    if windows.is_empty() {
+        return String::from("Below the horizon profile and elevation limit all night");
+    }
+    let mut parts: Vec<String> = windows
+        .iter()
+        .map(|window| match (window.start == first.time, window.end == last.time) {
+            (true, true) => String::from("Up all night"),
+            (true, false) => format!("Sets {}", format_time(Some(window.end))),
+            (false, true) => format!("Rises {}", format_time(Some(window.start))),
+            (false, false) => format!("Rises {}, sets {}", format_time(Some(window.start)), format_time(Some(window.end))),
+        })
+        .collect();
+    for culmination in observability::culminations(samples) {
+        let altitude = samples
+            .iter()
+            .min_by_key(|sample| (sample.time - culmination).num_seconds().abs())
+            .map_or(0.0, |sample| sample.altitude);
+        parts.push(format!("transits {} at {:.1}°", format_time(Some(culmination)), altitude));
+    }
+    parts.join(", ")
+}
+
+/// Sunset, the dark part of the night and sunrise, as a line of text.
+fn describe_sun(samples: &[Sample]) -> String {
+    let below = |altitude: f64| {
+        let margins: Vec<_> = samples.iter().map(|sample| (sample.time, altitude - sample.altitude)).collect();
+        observability::windows(&margins)
+    };
+    let night = below(0.0);
+    let dark = below(TWILIGHTS[2]);
+    let mut parts = Vec::new();
+    match night.first() {
+        Some(night) => {
+            parts.push(format!("Sunset {}", format_time(Some(night.start))));
+            match dark.first() {
+                Some(dark) => parts.push(format!("astronomical night {} – {}",
+                    format_time(Some(dark.start)), format_time(Some(dark.end)))),
+                None => parts.push(String::from("no astronomical night")),
+            }
+            parts.push(format!("sunrise {}", format_time(Some(night.end))));
+        },
+        None => parts.push(String::from("The Sun does not set")),
+    }
+    parts.join(", ")
+}
+
+/// Plot of the altitude of the target over the night, on the twilight of
+/// the Sun, with the horizon profile and elevation limit in its direction.
+fn altitude_plot(ui: &mut egui::Ui, chart: &AltitudeChart, now: DateTime<Utc>) {
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width().max(360.0), 260.0),
+        egui::Sense::hover(),
+    );
+    let outer = response.rect;
+    let rect = egui::Rect::from_min_max(outer.min + egui::vec2(34.0, 6.0), outer.max - egui::vec2(6.0, 18.0));
+    let (Some(first), Some(last)) = (chart.sun.first(), chart.sun.last()) else {
+        return;
+    };
+    let span = (last.time - first.time).num_seconds() as f32;
+    let x = |time: DateTime<Utc>| rect.left() + rect.width() * (time - first.time).num_seconds() as f32 / span;
+    let y = |altitude: f64| {
+        let fraction = (altitude.clamp(ALTITUDE_RANGE.0, ALTITUDE_RANGE.1) - ALTITUDE_RANGE.0)
+            / (ALTITUDE_RANGE.1 - ALTITUDE_RANGE.0);
+        rect.bottom() - rect.height() * fraction as f32
+    };
+    let text_color = ui.visuals().text_color();
+    let font = egui::FontId::proportional(11.0);
+
+    for pair in chart.sun.windows(2) {
+        painter.rect_filled(
+            egui::Rect::from_x_y_ranges(x(pair[0].time)..=x(pair[1].time), rect.y_range()),
+            0.0,
+            twilight_color(pair[0].altitude),
+        );
+    }
+    // The terrain and limit in the direction of the target at each moment.
+    for pair in chart.target.windows(2) {
+        painter.rect_filled(
+            egui::Rect::from_x_y_ranges(x(pair[0].time)..=x(pair[1].time), y(pair[0].min_elevation.max(0.0))..=y(0.0)),
+            0.0,
+            egui::Color32::from_rgb(40, 58, 30),
+        );
+    }
+
+    let grid = egui::Stroke::new(0.5, egui::Color32::from_gray(90));
+    for altitude in (0..=90).step_by(15) {
+        let height = y(altitude as f64);
+        let stroke = if altitude == 0 { egui::Stroke::new(1.0, egui::Color32::from_gray(160)) } else { grid };
+        painter.hline(rect.x_range(), height, stroke);
+        painter.text(egui::pos2(rect.left() - 4.0, height), egui::Align2::RIGHT_CENTER,
+            format!("{}°", altitude), font.clone(), text_color);
+    }
+    for hour in (0..=24).step_by(2) {
+        let time = first.time + Duration::hours(hour);
+        let left = x(time);
+        painter.vline(left, rect.y_range(), grid);
+        painter.text(egui::pos2(left, rect.bottom() + 2.0), egui::Align2::CENTER_TOP,
+            format_time(Some(time)), font.clone(), text_color);
+    }
+
+    let sun_stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(240, 200, 60));
+    let up_stroke = egui::Stroke::new(2.0, egui::Color32::from_rgb(110, 210, 255));
+    let down_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(70, 100, 120));
+    for pair in chart.sun.windows(2) {
+        painter.line_segment([
+            egui::pos2(x(pair[0].time), y(pair[0].altitude)),
+            egui::pos2(x(pair[1].time), y(pair[1].altitude)),
+        ], sun_stroke);
+    }
+    for pair in chart.target.windows(2) {
+        let stroke = if pair[0].height() >= 0.0 && pair[1].height() >= 0.0 { up_stroke } else { down_stroke };
+        painter.line_segment([
+            egui::pos2(x(pair[0].time), y(pair[0].altitude)),
+            egui::pos2(x(pair[1].time), y(pair[1].altitude)),
+        ], stroke);
+    }
+
+    if now >= first.time && now <= last.time {
+        painter.vline(x(now), rect.y_range(), egui::Stroke::new(1.0, egui::Color32::from_rgb(220, 60, 60)));
+    }
+
+    if let Some(pointer) = response.hover_pos().filter(|pointer| rect.x_range().contains(pointer.x)) {
+        let index = (((pointer.x - rect.left()) / rect.width()) * (chart.sun.len() - 1) as f32).round() as usize;
+        painter.vline(pointer.x, rect.y_range(), egui::Stroke::new(1.0, egui::Color32::from_gray(200)));
+        let mut readout = format_time(chart.sun.get(index).map(|sample| sample.time));
+        if let Some(sample) = chart.target.get(index) {
+            readout += &format!("  target {:.1}° (limit {:.1}°)", sample.altitude, sample.min_elevation);
+        }
+        if let Some(sample) = chart.sun.get(index) {
+            readout += &format!("  Sun {:.1}°", sample.altitude);
+        }
+        painter.text(rect.left_top() + egui::vec2(4.0, 4.0), egui::Align2::LEFT_TOP,
+            readout, font, egui::Color32::WHITE);
+    }
+}
+
+/// Window with the altitude of the selected target over a night, its rise,
+/// transit and set, and the twilight.
+fn altitude_chart_window(
+    mut contexts: EguiContexts,
+
+    mut view_config: ResMut<ViewConfig>,
+    telescope_config: Res<TelescopeConfig>,
+    clock: Res<SimulationClock>,
+    mut selected: ResMut<SelectedTarget>,
+
+    star_query: Query<&Star>,
+
+    mut chart: Local<AltitudeChart>,
+) {
+    if !view_config.show_altitude_chart {
+        return;
+    }
+
+    let night = chart.night.unwrap_or_else(|| night_of(clock.time()));
+    let sampled_for = (selected.name.clone(), night, telescope_config.clone());
+    if chart.sampled.as_ref() != Some(&sampled_for) {
+        let start = night_start(night);
+        let end = start + Duration::days(1);
+        let step = Duration::minutes(SAMPLE_MINUTES);
+        let sky = LocalSky::new(
+            telescope_config.latitude as f64,
+            telescope_config.longitude as f64,
+            start,
+        ).with_atmosphere(&telescope_config.atmosphere)
+        .with_horizon(&telescope_config.horizon);
+        chart.sun = observability::sample_sun(&sky, start, end, step);
+        chart.target = selected.target
+            .as_ref()
+            .map(|target| observability::sample(&sky, target, start, end, step))
+            .unwrap_or_default();
+        chart.sampled = Some(sampled_for);
+    }
+
+    let mut open = true;
+    egui::Window::new("Altitude")
+        .open(&mut open)
+        .default_width(560.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Target");
+                egui::ComboBox::from_id_salt("altitude_chart_target")
+                    .selected_text(if selected.target.is_some() { selected.name.as_str() } else { "none" })
+                    .show_ui(ui, |ui| {
+                        let mut stars: Vec<&Star> = star_query.iter().collect();
+                        stars.sort_by(|a, b| a.name.cmp(&b.name));
+                        for star in stars {
+                            if ui.selectable_label(selected.name == star.name, &star.name).clicked() {
+                                selected.name = star.name.clone();
+                                selected.target = Some(star.target.clone());
+                            }
+                        }
+                    });
+
+                ui.separator();
+                ui.label("Night of");
+                if ui.button("◀").clicked() {
+                    chart.night = Some(night - Duration::days(1));
+                }
+                ui.label(night.format("%Y-%m-%d").to_string());
+                if ui.button("▶").clicked() {
+                    chart.night = Some(night + Duration::days(1));
+                }
+                if ui.add(egui::widgets::Button::new("Clock")
+                    .selected(chart.night.is_none()))
+                    .clicked() {
+                    chart.night = None;
+                }
+            });
+
+            if selected.target.is_some() {
+                ui.label(format!("{}: {}", selected.name, describe_target(&chart.target)));
+            } else {
+                ui.label("Click a marker on the sky or pick a target above.");
+            }
+            ui.label(describe_sun(&chart.sun));
+            altitude_plot(ui, &chart, clock.time());
+        });
+    if !open {
+        view_config.show_altitude_chart = false;
+    }
+}
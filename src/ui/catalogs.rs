@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::catalog::Catalog;
+use astro::catalog::CatalogFormat;
+use astro::target::Target;
+use crate::config;
+use crate::config::CatalogLayer;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::clock::SimulationClock;
+use crate::ui::labels::AnchorTarget;
+use crate::ui::labels::LabelAnchor;
+use crate::ui::labels::LabelAnchors;
+use crate::ui::labels::PushLabelAnchors;
+use crate::ui::legend::marker_color;
+use crate::ui::legend::marker_scale;
+use crate::ui::sphere::horizontal_to_world;
+use crate::ui::stars::save_client_config;
+
+/// Seconds between two updates of the catalog source positions.
+const UPDATE_PERIOD_SECS: f32 = 5.0;
+
+/// Colours given to the layers in turn.
+const LAYER_COLORS: [(f32, f32, f32); 6] = [
+    (1.0, 1.0, 0.7),
+    (0.4, 0.8, 1.0),
+    (1.0, 0.5, 0.5),
+    (0.6, 1.0, 0.6),
+    (1.0, 0.7, 0.3),
+    (0.8, 0.6, 1.0),
+];
+
+pub struct CatalogsPlugin;
+
+impl Plugin for CatalogsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CatalogLayers>()
+            .add_systems(Update, (
+                load_catalogs.run_if(resource_changed::<config::ClientConfig>),
+                update_catalogs,
+                draw_catalogs.in_set(PushLabelAnchors),
+            ).chain());
+    }
+}
+
+/// A catalog layer of the client configuration and its sources.
+struct LoadedLayer {
+    layer: CatalogLayer,
+    catalog: Catalog,
+    error: Option<String>,
+    /// Azimuth and altitude of each source of `catalog`.
+    positions: Vec<(f64, f64)>,
+}
+
+/// Imported catalogs, each drawn as a layer that can be hidden.
+#[derive(Resource, Default)]
+pub struct CatalogLayers {
+    layers: Vec<LoadedLayer>,
+    /// Whether the positions must be updated without waiting for the next
+    /// period.
+    outdated: bool,
+}
+
+impl CatalogLayers {
+    /// Source of the loaded catalogs with exactly this name, ignoring case,
+    /// as the command line finds it in the catalog files.
+    pub fn find_source(&self, name: &str) -> Option<Target> {
+        self.layers
+            .iter()
+            .flat_map(|loaded| &loaded.catalog.sources)
+            .find(|source| source.name.eq_ignore_ascii_case(name))
+            .map(|source| Target::Catalog {
+                name: source.name.clone(),
+                position: source.position,
+            })
+    }
+}
+
+#[derive(Default)]
+pub struct CatalogsTab {
+    name: String,
+    path: String,
+    format: CatalogFormat,
+}
+
+/// Load the configured catalogs, keeping those already loaded from the same
+/// file.
+fn load_catalogs(
+    client_config: Res<config::ClientConfig>,
+    mut catalogs: ResMut<CatalogLayers>,
+) {
+    let mut previous = std::mem::take(&mut catalogs.layers);
+    for layer in &client_config.catalogs {
+        let loaded = previous
+            .iter()
+            .position(|loaded| loaded.layer.path == layer.path && loaded.layer.format == layer.format)
+            .map(|index| previous.remove(index));
+        let loaded = match loaded {
+            Some(loaded) => LoadedLayer {
+                layer: layer.clone(),
+                ..loaded
+            },
+            None => {
+                let (catalog, error) = match layer.load() {
+                    Ok(catalog) => (catalog, None),
+                    Err(e) => {
+                        println!("Could not load catalog {}: {}", layer.name, e);
+                        (Catalog::default(), Some(e))
+                    },
+                };
+                LoadedLayer {
+                    layer: layer.clone(),
+                    catalog,
+                    error,
+                    positions: Vec::new(),
+                }
+            },
+        };
+        catalogs.layers.push(loaded);
+    }
+    catalogs.outdated = true;
+}
+
+fn update_catalogs(
+    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    telescope_config: Res<TelescopeConfig>,
+    mut catalogs: ResMut<CatalogLayers>,
+    mut countdown: Local<f32>,
+    mut updated: Local<Option<DateTime<Utc>>>,
+) {
+    *countdown -= time.delta_seconds();
+    if *countdown > 0.0
+        && !catalogs.outdated
+        && !telescope_config.is_changed()
+        && !clock.moved_since(*updated, UPDATE_PERIOD_SECS) {
+        return;
+    }
+    *countdown = UPDATE_PERIOD_SECS;
+    *updated = Some(clock.time());
+    catalogs.outdated = false;
+
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        clock.time(),
+    ).with_atmosphere(&telescope_config.atmosphere);
+    for loaded in catalogs.layers.iter_mut().filter(|loaded| loaded.layer.enabled) {
+        loaded.positions = loaded.catalog.sources
+            .iter()
+            .map(|source| sky.horizontal(&source.position))
+            .collect();
+    }
+}
+
+/// Draw the sources of the enabled layers above the horizon, and the terrain
+/// when shown, that pass the filters of the legend, sized and coloured from
+/// their metadata.
+fn draw_catalogs(
+    view_config: Res<ViewConfig>,
+    telescope_config: Res<TelescopeConfig>,
+    catalogs: Res<CatalogLayers>,
+    mut label_anchors: ResMut<LabelAnchors>,
+    mut gizmos: Gizmos,
+) {
+    for (index, loaded) in catalogs.layers.iter().enumerate() {
+        if !loaded.layer.enabled {
+            continue;
+        }
+        let layer_color = LAYER_COLORS[index % LAYER_COLORS.len()];
+        for (source, (azimuth, altitude)) in loaded.catalog.sources.iter().zip(&loaded.positions) {
+            if *altitude < 0.0 || !view_config.source_filter.accepts(&source.info) {
+                continue;
+            }
+            if view_config.show_terrain && *altitude < telescope_config.horizon.terrain_elevation(*azimuth) {
+                continue;
+            }
+            let world_position = horizontal_to_world(*azimuth, *altitude);
+            gizmos.sphere(
+                world_position,
+                Quat::IDENTITY,
+                0.008 * marker_scale(&source.info),
+                marker_color(&source.info, layer_color),
+            ).resolution(8);
+            label_anchors.push(LabelAnchor {
+                name: source.name.clone(),
+                target: AnchorTarget::Target(Target::Catalog {
+                    name: source.name.clone(),
+                    position: source.position,
+                }),
+                info: Some(source.info),
+                kind: "Catalog source",
+                world_position,
+            });
+        }
+    }
+}
+
+pub fn catalogs_tab(
+    ui: &mut egui::Ui,
+    tab: &mut CatalogsTab,
+    catalogs: &CatalogLayers,
+    client_config: &mut ResMut<config::ClientConfig>,
+) {
+    egui::Grid::new("catalog_form_grid")
+        .num_columns(2)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Name:");
+            ui.add(egui::TextEdit::singleline(&mut tab.name).hint_text("Bright stars"));
+            ui.end_row();
+
+            ui.label("File:");
+            ui.add(egui::TextEdit::singleline(&mut tab.path).hint_text("path"));
+            ui.end_row();
+
+            ui.label("Format:");
+            egui::ComboBox::from_id_salt("catalog_format")
+                .selected_text(tab.format.to_string())
+                .show_ui(ui, |ui| {
+                    for format in CatalogFormat::ALL {
+                        ui.selectable_value(&mut tab.format, format, format.to_string());
+                    }
+                });
+            ui.end_row();
+        });
+    if ui.button("Import").clicked() && !tab.path.trim().is_empty() {
+        let path = tab.path.trim().to_string();
+        let name = if tab.name.trim().is_empty() {
+            path.clone()
+        } else {
+            tab.name.trim().to_string()
+        };
+        client_config.catalogs.push(CatalogLayer {
+            name,
+            path,
+            format: tab.format,
+            enabled: true,
+        });
+        tab.name.clear();
+        tab.path.clear();
+        save_client_config(client_config);
+    }
+
+    ui.separator();
+    let mut toggled = None;
+    let mut removed = None;
+    egui::Grid::new("catalogs_grid")
+        .num_columns(5)
+        .spacing([12.0, 4.0])
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Layer");
+            ui.strong("Format");
+            ui.strong("Sources");
+            ui.strong("File");
+            ui.label("");
+            ui.end_row();
+
+            for (index, loaded) in catalogs.layers.iter().enumerate() {
+                let mut enabled = loaded.layer.enabled;
+                if ui.checkbox(&mut enabled, &loaded.layer.name).changed() {
+                    toggled = Some(index);
+                }
+                ui.label(loaded.layer.format.to_string());
+                match &loaded.error {
+                    Some(error) => ui.colored_label(egui::Color32::RED, error),
+                    None if loaded.catalog.skipped > 0 => ui.label(format!("{}", loaded.catalog.sources.len()))
+                        .on_hover_text(format!("{} rows could not be read", loaded.catalog.skipped)),
+                    None => ui.label(format!("{}", loaded.catalog.sources.len())),
+                };
+                ui.label(&loaded.layer.path);
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+    if let Some(index) = toggled {
+        if let Some(layer) = client_config.catalogs.get_mut(index) {
+            layer.enabled = !layer.enabled;
+        }
+        save_client_config(client_config);
+    }
+    if let Some(index) = removed {
+        client_config.catalogs.remove(index);
+        save_client_config(client_config);
+    }
+}
@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use chrono::{
+    DateTime,
+    Duration,
+    Local,
+    NaiveDateTime,
+    TimeZone,
+    Utc,
+};
+
+/// Time the clock moves, in seconds, before the lines of the sky precessed
+/// to its epoch are built again. Precession turns them by about 50" a year.
+pub const EPOCH_PERIOD_SECS: f32 = 365.25 * 86_400.0;
+
+/// Format of the date and time field, in local time.
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Steps of the step buttons, in seconds, with their labels.
+const STEPS: [(i64, &str); 3] = [
+    (86_400, "1d"),
+    (3_600, "1h"),
+    (600, "10m"),
+];
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SimulationClock>()
+            .add_systems(First, advance_clock);
+    }
+}
+
+/// Time at which the sky is shown. It follows the wall clock until it is set
+/// or its rate changed, and then runs on its own.
+#[derive(Resource)]
+pub struct SimulationClock {
+    time: DateTime<Utc>,
+    /// Simulated seconds per real second. Zero stops the clock and negative
+    /// rates run it backwards.
+    rate: f64,
+    /// Whether the clock follows the wall clock.
+    live: bool,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        SimulationClock {
+            time: Utc::now(),
+            rate: 1.0,
+            live: true,
+        }
+    }
+}
+
+impl SimulationClock {
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: DateTime<Utc>) {
+        self.time = time;
+        self.live = false;
+    }
+
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+        self.live = false;
+    }
+
+    /// Whether the clock has moved more than `seconds` away from `time`, in
+    /// either direction, or there is no such time yet.
+    pub fn moved_since(&self, time: Option<DateTime<Utc>>, seconds: f32) -> bool {
+        time.is_none_or(|time| (self.time - time).num_milliseconds().abs() as f32 > seconds * 1000.0)
+    }
+
+    /// Follow the wall clock again.
+    pub fn reset(&mut self) {
+        *self = SimulationClock::default();
+    }
+}
+
+fn advance_clock(
+    time: Res<Time>,
+    mut clock: ResMut<SimulationClock>,
+) {
+    if clock.live {
+        clock.time = Utc::now();
+    } else if clock.rate != 0.0 {
+        let seconds = time.delta_seconds_f64() * clock.rate;
+        clock.time += Duration::microseconds((seconds * 1e6) as i64);
+    }
+}
+
+/// Text of the date and time field while it is edited.
+#[derive(Default)]
+pub struct ClockForm {
+    text: Option<String>,
+    error: bool,
+}
+
+/// Controls of the simulation clock: date and time, step buttons, rate and
+/// a button back to the current time.
+pub fn clock_controls(
+    ui: &mut egui::Ui,
+    form: &mut ClockForm,
+    clock: &mut SimulationClock,
+) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Time");
+        let shown = clock.time.with_timezone(&Local).format(TIME_FORMAT).to_string();
+        let mut text = form.text.clone().unwrap_or(shown);
+        let response = ui.add(egui::TextEdit::singleline(&mut text)
+            .desired_width(140.0)
+            .text_color_opt(form.error.then_some(egui::Color32::LIGHT_RED)));
+        if response.gained_focus() || response.changed() {
+            form.text = Some(text.clone());
+        }
+        if response.lost_focus() {
+            // A time that does not exist locally, in a daylight saving
+            // gap, is rejected with the invalid ones.
+            let parsed = NaiveDateTime::parse_from_str(text.trim(), TIME_FORMAT)
+                .ok()
+                .and_then(|time| Local.from_local_datetime(&time).earliest());
+            form.error = parsed.is_none();
+            if let Some(time) = parsed {
+                clock.set_time(time.with_timezone(&Utc));
+            }
+            form.text = None;
+        }
+
+        for (seconds, label) in STEPS {
+            if ui.button(format!("-{}", label)).clicked() {
+                clock.set_time(clock.time - Duration::seconds(seconds));
+            }
+        }
+        for (seconds, label) in STEPS.iter().rev() {
+            if ui.button(format!("+{}", label)).clicked() {
+                clock.set_time(clock.time + Duration::seconds(*seconds));
+            }
+        }
+
+        ui.label("Rate");
+        let mut rate = clock.rate;
+        if ui.add(egui::DragValue::new(&mut rate)
+            .speed(1.0)
+            .range(-100_000.0..=100_000.0)
+            .prefix("×"))
+            .changed() {
+            clock.set_rate(rate);
+        }
+        let paused = clock.rate == 0.0;
+        if ui.add(egui::widgets::Button::new(if paused { "Run" } else { "Pause" })
+            .selected(paused))
+            .clicked() {
+            clock.set_rate(if paused { 1.0 } else { 0.0 });
+        }
+        if ui.add(egui::widgets::Button::new("Now")
+            .selected(clock.live))
+            .clicked() {
+            clock.reset();
+            form.error = false;
+        }
+    });
+}
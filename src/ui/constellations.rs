@@ -0,0 +1,195 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::PrimitiveTopology,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::astrometry::ApparentFrame;
+use astro::astrometry::precess;
+use astro::constellation;
+use astro::constellation::Boundary;
+use astro::constellation::Constellation;
+use astro::time::J2000;
+use crate::config;
+use crate::ui::ViewConfig;
+use crate::ui::clock::EPOCH_PERIOD_SECS;
+use crate::ui::clock::SimulationClock;
+use crate::ui::grid::GridFrame;
+use crate::ui::sphere::Rotator;
+use crate::ui::sphere::Sky;
+use crate::ui::sphere::Star;
+use crate::ui::sphere::equatorial_to_sky;
+
+/// Longest boundary edge, in degrees, drawn as a single segment. Longer
+/// edges follow their parallel or hour circle in steps.
+const BOUNDARY_STEP: f64 = 2.0;
+
+pub struct ConstellationsPlugin;
+
+impl Plugin for ConstellationsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(Constellations {
+                figures: constellation::bundled_figures(),
+            })
+            .add_systems(Update, (
+                rebuild_constellations,
+                handle_constellation_visibility,
+            ).chain());
+    }
+}
+
+/// Stick figures of the bundled constellations, whose names are labelled.
+#[derive(Resource)]
+pub struct Constellations {
+    pub figures: Vec<Constellation>,
+}
+
+/// Lines of the constellation layer.
+#[derive(Component)]
+enum ConstellationLines {
+    Figures,
+    Boundaries,
+}
+
+/// Point of the sphere, in the frame of the sky entities, of J2000.0
+/// coordinates.
+fn j2000_to_sky(ra: f64, dec: f64, frame: &ApparentFrame) -> [f32; 3] {
+    let (ra, dec) = precess(ra, dec, J2000, frame.julian_date);
+    equatorial_to_sky(ra, dec).to_array()
+}
+
+fn line_list(positions: Vec<[f32; 3]>) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList, bevy::render::render_asset::RenderAssetUsages::default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh
+}
+
+fn figure_segments(figures: &[Constellation], frame: &ApparentFrame) -> Vec<[f32; 3]> {
+    let mut segments = Vec::new();
+    for polyline in figures.iter().flat_map(|constellation| &constellation.figure) {
+        for pair in polyline.windows(2) {
+            segments.push(j2000_to_sky(pair[0].0, pair[0].1, frame));
+            segments.push(j2000_to_sky(pair[1].0, pair[1].1, frame));
+        }
+    }
+    segments
+}
+
+/// Edges of the outlines, split so that edges along a parallel bend with
+/// it instead of cutting across the sphere.
+fn boundary_segments(boundaries: &[Boundary], frame: &ApparentFrame) -> Vec<[f32; 3]> {
+    let mut segments = Vec::new();
+    for boundary in boundaries {
+        let points = &boundary.points;
+        for (i, &(ra, dec)) in points.iter().enumerate() {
+            let (next_ra, next_dec) = points[(i + 1) % points.len()];
+            let delta_ra = (next_ra - ra + 180.0).rem_euclid(360.0) - 180.0;
+            let delta_dec = next_dec - dec;
+            let steps = (delta_ra.abs().max(delta_dec.abs()) / BOUNDARY_STEP).ceil().max(1.0) as usize;
+            let point = |step: usize| {
+                let fraction = step as f64 / steps as f64;
+                j2000_to_sky(ra + delta_ra * fraction, dec + delta_dec * fraction, frame)
+            };
+            for step in 0..steps {
+                segments.push(point(step));
+                segments.push(point(step + 1));
+            }
+        }
+    }
+    segments
+}
+
+/// Spawn the figures and the boundaries, of the configured file or else the
+/// bundled ones, again when the configuration changes the file or the clock
+/// moves to another epoch.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn rebuild_constellations(
+    client_config: Res<config::ClientConfig>,
+    constellations: Res<Constellations>,
+    clock: Res<SimulationClock>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+
+    lines_query: Query<Entity, With<ConstellationLines>>,
+    sky_query: Query<&Transform, (With<Sky>, Without<ConstellationLines>, Without<Star>)>,
+
+    mut commands: Commands,
+    mut built: Local<Option<Option<String>>>,
+    mut epoch: Local<Option<DateTime<Utc>>>,
+) {
+    if built.as_ref() == Some(&client_config.constellation_boundaries)
+        && !clock.moved_since(*epoch, EPOCH_PERIOD_SECS) {
+        return;
+    }
+    for entity in &lines_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let frame = ApparentFrame::new(astro::time::julian_date_tt(clock.time()));
+    let sky_rotation = sky_query.iter().next().map_or(Quat::IDENTITY, |transform| transform.rotation);
+    let layers = [
+        (ConstellationLines::Figures, Color::linear_rgb(0.45, 0.55, 0.75),
+            figure_segments(&constellations.figures, &frame)),
+        (ConstellationLines::Boundaries, Color::linear_rgb(0.4, 0.3, 0.4),
+            boundary_segments(&client_config.constellation_boundaries(), &frame)),
+    ];
+    for (lines, color, positions) in layers {
+        if positions.is_empty() {
+            continue;
+        }
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(line_list(positions)),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    unlit: true,
+                    ..default()
+                }),
+                transform: Transform::from_rotation(sky_rotation),
+                ..default()
+            },
+            lines,
+            Rotator,
+            Sky,
+        ));
+    }
+    *built = Some(client_config.constellation_boundaries.clone());
+    *epoch = Some(clock.time());
+}
+
+fn handle_constellation_visibility(
+    view_config: Res<ViewConfig>,
+
+    mut lines_query: Query<&mut Visibility, With<ConstellationLines>>,
+) {
+    let wanted = if view_config.show_constellations {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for mut visibility in &mut lines_query {
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+/// Name and point of the sphere, in world coordinates, of the label of each
+/// constellation.
+pub fn constellation_labels(constellations: &Constellations, sky: &LocalSky) -> Vec<(String, Vec3)> {
+    constellations.figures
+        .iter()
+        .map(|constellation| {
+            let (ra, dec) = constellation.label_position();
+            let (ra, dec) = precess(ra, dec, J2000, sky.frame.julian_date);
+            (constellation.name.clone(), GridFrame::Equatorial.world_point(ra, dec, sky))
+        })
+        .collect()
+}
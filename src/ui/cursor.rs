@@ -0,0 +1,91 @@
+use bevy::{
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_egui::{
+    egui,
+    EguiContexts,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::angle;
+use astro::galactic;
+use crate::ui::TelescopeConfig;
+use crate::ui::sphere::cursor_ray;
+use crate::ui::sphere::ray_sphere_intersection;
+use crate::ui::sphere::world_to_horizontal;
+
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SkyCursor>()
+            .add_systems(Update, update_sky_cursor);
+    }
+}
+
+/// Point of the sky under the mouse cursor.
+#[derive(Resource, Default)]
+pub struct SkyCursor {
+    /// Azimuth and observed altitude, in degrees, or `None` when the cursor
+    /// is off the sphere or over a panel.
+    pub horizontal: Option<(f64, f64)>,
+}
+
+fn update_sky_cursor(
+    mut contexts: EguiContexts,
+    mut sky_cursor: ResMut<SkyCursor>,
+
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+) {
+    let horizontal = if contexts.ctx_mut().is_pointer_over_area() {
+        None
+    } else {
+        match (windows.get_single(), camera_query.get_single()) {
+            (Ok(window), Ok((camera, camera_transform))) => cursor_ray(window, camera, camera_transform)
+                .and_then(ray_sphere_intersection)
+                .map(world_to_horizontal),
+            _ => None,
+        }
+    };
+    if sky_cursor.horizontal != horizontal {
+        sky_cursor.horizontal = horizontal;
+    }
+}
+
+/// Status bar line with the coordinates of the point under the cursor.
+pub fn cursor_readout(
+    ui: &mut egui::Ui,
+    sky_cursor: &SkyCursor,
+    telescope_config: &TelescopeConfig,
+    time: DateTime<Utc>,
+) {
+    let Some((azimuth, altitude)) = sky_cursor.horizontal else {
+        ui.label("Cursor: -");
+        return;
+    };
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        time,
+    ).with_atmosphere(&telescope_config.atmosphere);
+    let (ra, dec) = sky.horizontal_to_apparent(azimuth, altitude);
+    let hour_angle = (sky.local_sidereal_time - ra + 180.0).rem_euclid(360.0) - 180.0;
+    let (longitude, latitude) = galactic::of_date_to_galactic(ra, dec, sky.frame.julian_date);
+    ui.monospace(format!(
+        "RA {}  Dec {}  |  Alt {:6.2}°  Az {:6.2}°  |  HA {:+6.3}h  |  l {:6.2}°  b {:+6.2}°",
+        angle::format_right_ascension(ra),
+        angle::format_declination(dec),
+        altitude,
+        azimuth,
+        hour_angle / 15.0,
+        longitude,
+        latitude,
+    ));
+}
@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::angle;
+use astro::ephemeris_table::EphemerisTable;
+use astro::target::Target;
+use crate::config;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::clock::SimulationClock;
+use crate::ui::labels::AnchorTarget;
+use crate::ui::labels::LabelAnchor;
+use crate::ui::labels::LabelAnchors;
+use crate::ui::labels::PushLabelAnchors;
+use crate::ui::sphere::horizontal_to_world;
+use crate::ui::stars::save_client_config;
+
+/// Seconds between two updates of the tabulated positions.
+const UPDATE_PERIOD_SECS: f32 = 1.0;
+
+pub struct EphemerisTablesPlugin;
+
+impl Plugin for EphemerisTablesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<EphemerisTables>()
+            .add_systems(Update, (
+                load_ephemeris_tables.run_if(resource_changed::<config::ClientConfig>),
+                update_ephemeris_tables,
+                draw_ephemeris_tables.in_set(PushLabelAnchors),
+            ).chain());
+    }
+}
+
+/// Current position of the object of an ephemeris table.
+#[derive(Clone, Copy)]
+struct TablePosition {
+    ra: f64,
+    dec: f64,
+    azimuth: f64,
+    elevation: f64,
+    /// Distance from the observer, in astronomical units.
+    distance: Option<f64>,
+}
+
+/// Ephemeris tables of the configured files and the current positions of
+/// their objects.
+#[derive(Resource, Default)]
+pub struct EphemerisTables {
+    list: Vec<EphemerisTable>,
+    /// Position of each table of `list`, or `None` outside of its span.
+    positions: Vec<Option<TablePosition>>,
+    selected: Option<String>,
+}
+
+#[derive(Default)]
+pub struct EphemerisTablesTab {
+    ephemeris_file: String,
+}
+
+fn load_ephemeris_tables(
+    client_config: Res<config::ClientConfig>,
+    mut tables: ResMut<EphemerisTables>,
+) {
+    let list = client_config.ephemeris_tables();
+    if list != tables.list {
+        *tables = EphemerisTables {
+            positions: vec![None; list.len()],
+            list,
+            ..default()
+        };
+    }
+}
+
+fn update_ephemeris_tables(
+    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    telescope_config: Res<TelescopeConfig>,
+    mut tables: ResMut<EphemerisTables>,
+    mut countdown: Local<f32>,
+    mut updated: Local<Option<DateTime<Utc>>>,
+) {
+    *countdown -= time.delta_seconds();
+    if *countdown > 0.0 && !clock.moved_since(*updated, UPDATE_PERIOD_SECS) {
+        return;
+    }
+    *countdown = UPDATE_PERIOD_SECS;
+    *updated = Some(clock.time());
+
+    let now = clock.time();
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        now,
+    ).with_atmosphere(&telescope_config.atmosphere);
+    tables.positions = tables.list
+        .iter()
+        .map(|table| {
+            let row = table.interpolate(now).ok()?;
+            let (azimuth, elevation) = sky.target_horizontal(&Target::Table { table: table.clone() });
+            Some(TablePosition {
+                ra: row.ra,
+                dec: row.dec,
+                azimuth,
+                elevation,
+                distance: row.distance,
+            })
+        })
+        .collect();
+}
+
+/// Draw the tabulated objects above the horizon, and the whole tabulated
+/// path of the selected one among the stars.
+fn draw_ephemeris_tables(
+    view_config: Res<ViewConfig>,
+    telescope_config: Res<TelescopeConfig>,
+    clock: Res<SimulationClock>,
+    tables: Res<EphemerisTables>,
+    mut label_anchors: ResMut<LabelAnchors>,
+    mut gizmos: Gizmos,
+) {
+    if !view_config.show_ephemeris_tables {
+        return;
+    }
+    let color = Color::linear_rgb(1.0, 0.6, 0.2);
+    for (table, position) in tables.list.iter().zip(&tables.positions) {
+        let Some(position) = position else {
+            continue;
+        };
+        if position.elevation < 0.0 {
+            continue;
+        }
+        if view_config.show_terrain
+            && position.elevation < telescope_config.horizon.terrain_elevation(position.azimuth) {
+            continue;
+        }
+        let radius = if tables.selected.as_ref() == Some(&table.name) {
+            0.03
+        } else {
+            0.015
+        };
+        let world_position = horizontal_to_world(position.azimuth, position.elevation);
+        gizmos.sphere(world_position, Quat::IDENTITY, radius, color);
+        label_anchors.push(LabelAnchor {
+            name: table.name.clone(),
+            target: AnchorTarget::Target(Target::Table { table: table.clone() }),
+            info: None,
+            kind: "Tabulated object",
+            world_position,
+        });
+    }
+
+    let Some(table) = tables.selected
+        .as_ref()
+        .and_then(|name| tables.list.iter().find(|table| &table.name == name)) else {
+        return;
+    };
+    // The path is drawn on the sky as it is now, like the equatorial grid.
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        clock.time(),
+    );
+    gizmos.linestrip(
+        table.rows.iter().map(|row| {
+            let (azimuth, elevation) = sky.apparent_to_horizontal(row.ra, row.dec);
+            horizontal_to_world(azimuth, elevation)
+        }),
+        color,
+    );
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
+pub fn ephemeris_tables_tab(
+    ui: &mut egui::Ui,
+    tab: &mut EphemerisTablesTab,
+    tables: &mut EphemerisTables,
+    client_config: &mut ResMut<config::ClientConfig>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Ephemeris file:");
+        ui.add(egui::TextEdit::singleline(&mut tab.ephemeris_file).hint_text("Horizons or CSV table"));
+        if ui.button("Add").clicked() && !tab.ephemeris_file.trim().is_empty() {
+            client_config.ephemeris_files.push(tab.ephemeris_file.trim().to_string());
+            tab.ephemeris_file.clear();
+            save_client_config(client_config);
+        }
+    });
+    let mut removed = None;
+    for (index, path) in client_config.ephemeris_files.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(path);
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        client_config.ephemeris_files.remove(index);
+        save_client_config(client_config);
+    }
+
+    ui.separator();
+    let mut selected = None;
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("ephemeris_tables_grid")
+            .num_columns(8)
+            .spacing([12.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("From");
+                ui.strong("Until");
+                ui.strong("RA");
+                ui.strong("Dec");
+                ui.strong("Az");
+                ui.strong("El");
+                ui.strong("Distance");
+                ui.end_row();
+
+                for (table, position) in tables.list.iter().zip(&tables.positions) {
+                    let is_selected = tables.selected.as_ref() == Some(&table.name);
+                    let frame = match (table.apparent, table.topocentric) {
+                        (false, false) => "astrometric J2000, geocentric",
+                        (false, true) => "astrometric J2000, topocentric",
+                        (true, false) => "apparent, geocentric",
+                        (true, true) => "apparent, topocentric",
+                    };
+                    if ui.selectable_label(is_selected, &table.name)
+                        .on_hover_text(format!("{} rows, {}", table.rows.len(), frame))
+                        .clicked() {
+                        selected = Some(table.name.clone());
+                    }
+                    let (from, until) = table.span();
+                    ui.label(format_time(from));
+                    ui.label(format_time(until));
+                    match position {
+                        Some(position) => {
+                            ui.label(angle::format_right_ascension(position.ra));
+                            ui.label(angle::format_declination(position.dec));
+                            ui.label(format!("{:.1}°", position.azimuth));
+                            ui.label(format!("{:.1}°", position.elevation));
+                            ui.label(position.distance.map_or(String::from("-"), |distance| format!("{:.4} AU", distance)));
+                        },
+                        None => {
+                            ui.label("outside table");
+                            for _ in 0..4 {
+                                ui.label("-");
+                            }
+                        },
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+    if let Some(name) = selected {
+        tables.selected = Some(name);
+    }
+}
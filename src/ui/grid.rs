@@ -0,0 +1,429 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::PrimitiveTopology,
+};
+use bevy_egui::egui;
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use astro::LocalSky;
+use astro::astrometry::ApparentFrame;
+use astro::astrometry::precess;
+use astro::ephemeris::ecliptic_to_equatorial;
+use astro::galactic;
+use astro::horizontal_to_hour_angle;
+use astro::hour_angle_to_horizontal;
+use astro::time::J2000;
+use crate::ui::ViewConfig;
+use crate::ui::clock::EPOCH_PERIOD_SECS;
+use crate::ui::clock::SimulationClock;
+use crate::ui::sphere::Rotator;
+use crate::ui::sphere::Sky;
+use crate::ui::sphere::Star;
+use crate::ui::sphere::equatorial_to_sky;
+use crate::ui::sphere::horizontal_to_world;
+use crate::ui::sphere::world_to_horizontal;
+
+pub struct GridPlugin;
+
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            rebuild_grids,
+            handle_grid_visibility,
+        ).chain());
+    }
+}
+
+/// Coordinate frame of a grid of the sky sphere. Entities drawn with a grid
+/// carry its frame.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub enum GridFrame {
+    Equatorial,
+    Azimuthal,
+    Galactic,
+    Ecliptic,
+}
+
+impl GridFrame {
+    pub const ALL: [GridFrame; 4] = [
+        GridFrame::Equatorial,
+        GridFrame::Azimuthal,
+        GridFrame::Galactic,
+        GridFrame::Ecliptic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GridFrame::Equatorial => "Equatorial",
+            GridFrame::Azimuthal => "Azimuthal",
+            GridFrame::Galactic => "Galactic",
+            GridFrame::Ecliptic => "Ecliptic",
+        }
+    }
+
+    /// Right ascension and declination of date, in degrees, of a point given
+    /// in this frame, or `None` for the azimuthal frame, which does not turn
+    /// with the sky.
+    fn to_equatorial(self, longitude: f64, latitude: f64, frame: &ApparentFrame) -> Option<(f64, f64)> {
+        match self {
+            GridFrame::Equatorial => Some((longitude, latitude)),
+            GridFrame::Azimuthal => None,
+            // Precession moves the sky of date away from the J2000.0
+            // galactic frame by about 50" a year.
+            GridFrame::Galactic => {
+                let (ra, dec) = galactic::galactic_to_equatorial(longitude, latitude);
+                Some(precess(ra, dec, J2000, frame.julian_date))
+            },
+            GridFrame::Ecliptic => Some(ecliptic_to_equatorial(longitude, latitude, frame.obliquity)),
+        }
+    }
+
+    /// Longitude and latitude in this frame, in degrees, of a right
+    /// ascension and declination of date, or `None` for the azimuthal frame.
+    fn coordinates_of_equatorial(self, ra: f64, dec: f64, frame: &ApparentFrame) -> Option<(f64, f64)> {
+        match self {
+            GridFrame::Equatorial => Some((ra, dec)),
+            GridFrame::Azimuthal => None,
+            GridFrame::Galactic => Some(galactic::of_date_to_galactic(ra, dec, frame.julian_date)),
+            // Going back from the equator to the ecliptic is the rotation by
+            // the opposite angle.
+            GridFrame::Ecliptic => Some(ecliptic_to_equatorial(ra, dec, -frame.obliquity)),
+        }
+    }
+
+    /// Point of the unit sphere, in the frame of the entities of this grid,
+    /// at the given longitude and latitude.
+    pub fn local_point(self, longitude: f64, latitude: f64, frame: &ApparentFrame) -> Vec3 {
+        match self.to_equatorial(longitude, latitude, frame) {
+            Some((ra, dec)) => equatorial_to_sky(ra, dec),
+            None => horizontal_to_world(longitude, latitude),
+        }
+    }
+
+    /// Point of the unit sphere, in world coordinates, at the given
+    /// longitude and latitude. Like the grids, it follows the geometric sky.
+    pub fn world_point(self, longitude: f64, latitude: f64, sky: &LocalSky) -> Vec3 {
+        match self.to_equatorial(longitude, latitude, &sky.frame) {
+            Some((ra, dec)) => {
+                let (azimuth, altitude) =
+                    hour_angle_to_horizontal(sky.local_sidereal_time - ra, dec, sky.latitude);
+                horizontal_to_world(azimuth, altitude)
+            },
+            None => horizontal_to_world(longitude, latitude),
+        }
+    }
+
+    /// Longitude and latitude in this frame of a point of the unit sphere in
+    /// world coordinates.
+    pub fn coordinates_of_world(self, position: Vec3, sky: &LocalSky) -> (f64, f64) {
+        let (azimuth, altitude) = world_to_horizontal(position);
+        let (hour_angle, dec) = horizontal_to_hour_angle(azimuth, altitude, sky.latitude);
+        self.coordinates_of_equatorial(sky.local_sidereal_time - hour_angle, dec, &sky.frame)
+            .unwrap_or((azimuth, altitude))
+    }
+
+    pub fn longitude_label(self, longitude: f64) -> String {
+        let longitude = (longitude.rem_euclid(360.0) * 100.0).round() / 100.0;
+        match self {
+            GridFrame::Equatorial => {
+                let minutes = (longitude * 4.0).round() as u32;
+                if minutes.is_multiple_of(60) {
+                    format!("{}h", minutes / 60 % 24)
+                } else {
+                    format!("{}h{:02}m", minutes / 60 % 24, minutes % 60)
+                }
+            },
+            GridFrame::Azimuthal if longitude == 0.0 => String::from("N"),
+            GridFrame::Azimuthal if longitude == 90.0 => String::from("E"),
+            GridFrame::Azimuthal if longitude == 180.0 => String::from("S"),
+            GridFrame::Azimuthal if longitude == 270.0 => String::from("W"),
+            _ => format!("{}°", longitude),
+        }
+    }
+
+    pub fn latitude_label(self, latitude: f64) -> String {
+        let latitude = (latitude * 100.0).round() / 100.0;
+        match self {
+            GridFrame::Azimuthal => format!("{}°", latitude),
+            _ => format!("{:+}°", latitude),
+        }
+    }
+}
+
+/// Spacing, density and colour of the lines of a grid.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct GridStyle {
+    /// Angle between two lines, in degrees.
+    pub spacing: f64,
+    /// Every `major_every`-th line is a major line, drawn brighter and
+    /// labelled.
+    pub major_every: u32,
+    /// Number of vertices of a full circle.
+    pub density: u32,
+    /// Colour of the major lines. Minor lines are drawn at half brightness.
+    pub color: [f32; 3],
+}
+
+impl GridStyle {
+    /// Angle between two major lines, in degrees.
+    pub fn major_spacing(&self) -> f64 {
+        self.spacing * self.major_every.max(1) as f64
+    }
+}
+
+/// Style of the grid of each frame.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct GridStyles {
+    equatorial: GridStyle,
+    azimuthal: GridStyle,
+    galactic: GridStyle,
+    ecliptic: GridStyle,
+}
+
+impl Default for GridStyles {
+    fn default() -> Self {
+        GridStyles {
+            equatorial: GridStyle {
+                spacing: 10.0,
+                major_every: 3,
+                density: 100,
+                color: [0.35, 0.35, 0.5],
+            },
+            azimuthal: GridStyle {
+                spacing: 10.0,
+                major_every: 3,
+                density: 100,
+                color: [0.84, 0.6, 0.13],
+            },
+            galactic: GridStyle {
+                spacing: 10.0,
+                major_every: 3,
+                density: 100,
+                color: [0.3, 0.6, 0.45],
+            },
+            ecliptic: GridStyle {
+                spacing: 10.0,
+                major_every: 3,
+                density: 100,
+                color: [0.7, 0.6, 0.25],
+            },
+        }
+    }
+}
+
+impl GridStyles {
+    pub fn get(&self, frame: GridFrame) -> &GridStyle {
+        match frame {
+            GridFrame::Equatorial => &self.equatorial,
+            GridFrame::Azimuthal => &self.azimuthal,
+            GridFrame::Galactic => &self.galactic,
+            GridFrame::Ecliptic => &self.ecliptic,
+        }
+    }
+
+    pub fn get_mut(&mut self, frame: GridFrame) -> &mut GridStyle {
+        match frame {
+            GridFrame::Equatorial => &mut self.equatorial,
+            GridFrame::Azimuthal => &mut self.azimuthal,
+            GridFrame::Galactic => &mut self.galactic,
+            GridFrame::Ecliptic => &mut self.ecliptic,
+        }
+    }
+}
+
+/// Lines of a grid, spawned again whenever its style changes.
+#[derive(Component)]
+struct GridLines;
+
+/// Parallels and meridians of a grid, as minor and major line lists in the
+/// frame of the grid entities.
+fn grid_meshes(frame: GridFrame, style: &GridStyle, apparent_frame: &ApparentFrame) -> [Mesh; 2] {
+    let spacing = style.spacing.max(0.1);
+    let major_every = style.major_every.max(1) as i64;
+    let density = style.density.max(8) as usize;
+    let point = |longitude: f64, latitude: f64| frame.local_point(longitude, latitude, apparent_frame).to_array();
+
+    let mut segments: [Vec<[f32; 3]>; 2] = [Vec::new(), Vec::new()];
+    let mut add_line = |major: bool, points: Vec<[f32; 3]>| {
+        for pair in points.windows(2) {
+            segments[major as usize].extend_from_slice(pair);
+        }
+    };
+
+    let n_parallels = ((90.0 - 1e-6) / spacing).floor() as i64;
+    for parallel in -n_parallels..=n_parallels {
+        let latitude = parallel as f64 * spacing;
+        add_line(parallel % major_every == 0, (0..=density)
+            .map(|vertex| point(360.0 * vertex as f64 / density as f64, latitude))
+            .collect());
+    }
+    let n_meridians = ((360.0 - 1e-6) / spacing).floor() as i64;
+    for meridian in 0..=n_meridians {
+        let longitude = meridian as f64 * spacing;
+        add_line(meridian % major_every == 0, (0..=density / 2)
+            .map(|vertex| point(longitude, 360.0 * vertex as f64 / density as f64 - 90.0))
+            .collect());
+    }
+
+    segments.map(|positions| {
+        let mut mesh = Mesh::new(PrimitiveTopology::LineList, bevy::render::render_asset::RenderAssetUsages::default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh
+    })
+}
+
+/// Spawn the lines of the grids whose style changed since they were last
+/// built, and of the precessed ones when the clock moves to another epoch.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn rebuild_grids(
+    view_config: Res<ViewConfig>,
+    clock: Res<SimulationClock>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+
+    grid_query: Query<(Entity, &GridFrame), With<GridLines>>,
+    sky_query: Query<&Transform, (With<Sky>, Without<GridLines>, Without<Star>)>,
+
+    mut commands: Commands,
+    mut built: Local<Option<GridStyles>>,
+    mut epoch: Local<Option<DateTime<Utc>>>,
+) {
+    let styles = &view_config.grids;
+    let epoch_moved = clock.moved_since(*epoch, EPOCH_PERIOD_SECS);
+    if built.as_ref() == Some(styles) && !epoch_moved {
+        return;
+    }
+    let apparent_frame = ApparentFrame::new(astro::time::julian_date_tt(clock.time()));
+    // New sky lines take the current orientation of the sky, which is not
+    // updated while the time is stopped.
+    let sky_rotation = sky_query.iter().next().map_or(Quat::IDENTITY, |transform| transform.rotation);
+
+    for frame in GridFrame::ALL {
+        let style = styles.get(frame);
+        let precessed = matches!(frame, GridFrame::Galactic | GridFrame::Ecliptic);
+        if built.as_ref().is_some_and(|built| built.get(frame) == style) && !(precessed && epoch_moved) {
+            continue;
+        }
+        for (entity, _) in grid_query.iter().filter(|(_, grid_frame)| **grid_frame == frame) {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        let [red, green, blue] = style.color;
+        let [minor, major] = grid_meshes(frame, style, &apparent_frame);
+        for (mesh, brightness) in [(minor, 0.5), (major, 1.0)] {
+            let bundle = PbrBundle {
+                mesh: meshes.add(mesh),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::linear_rgb(red * brightness, green * brightness, blue * brightness),
+                    unlit: true,
+                    ..default()
+                }),
+                ..default()
+            };
+            if frame == GridFrame::Azimuthal {
+                commands.spawn((bundle, frame, GridLines, Rotator));
+            } else {
+                commands.spawn((
+                    PbrBundle {
+                        transform: Transform::from_rotation(sky_rotation),
+                        ..bundle
+                    },
+                    frame,
+                    GridLines,
+                    Rotator,
+                    Sky,
+                ));
+            }
+        }
+    }
+    *built = Some(styles.clone());
+    *epoch = Some(clock.time());
+}
+
+fn handle_grid_visibility(
+    view_config: Res<ViewConfig>,
+
+    mut grid_query: Query<(&mut Visibility, &GridFrame)>,
+) {
+    for (mut visibility, frame) in &mut grid_query {
+        let shown = match frame {
+            GridFrame::Equatorial => view_config.show_equatorial_grid,
+            GridFrame::Azimuthal => view_config.show_azimuthal_grid,
+            GridFrame::Galactic => view_config.show_galactic_grid,
+            GridFrame::Ecliptic => view_config.show_ecliptic_grid,
+        };
+        let wanted = if shown {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+/// Window to show the grids and change their spacing and colour.
+pub fn grid_settings_window(
+    ctx: &egui::Context,
+    view_config: &mut ViewConfig,
+) {
+    let mut open = view_config.show_grid_settings;
+    egui::Window::new("Grids")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("grid_settings_grid")
+                .num_columns(5)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    ui.strong("Grid");
+                    ui.strong("Spacing");
+                    ui.strong("Major every");
+                    ui.strong("Density");
+                    ui.strong("Colour");
+                    ui.end_row();
+
+                    for frame in GridFrame::ALL {
+                        let shown = match frame {
+                            GridFrame::Equatorial => &mut view_config.show_equatorial_grid,
+                            GridFrame::Azimuthal => &mut view_config.show_azimuthal_grid,
+                            GridFrame::Galactic => &mut view_config.show_galactic_grid,
+                            GridFrame::Ecliptic => &mut view_config.show_ecliptic_grid,
+                        };
+                        ui.checkbox(shown, frame.name());
+                        let style = view_config.grids.get_mut(frame);
+                        ui.add(egui::DragValue::new(&mut style.spacing)
+                            .speed(0.5)
+                            .range(0.5..=90.0)
+                            .suffix("°"),
+                        );
+                        ui.add(egui::DragValue::new(&mut style.major_every)
+                            .range(1..=12)
+                            .suffix(" lines"),
+                        );
+                        ui.add(egui::DragValue::new(&mut style.density)
+                            .speed(4)
+                            .range(24..=720)
+                            .suffix(" vertices"),
+                        );
+                        ui.color_edit_button_rgb(&mut style.color);
+                        ui.end_row();
+                    }
+                });
+            if ui.button("Reset grids").clicked() {
+                view_config.grids = GridStyles::default();
+            }
+        });
+    view_config.show_grid_settings = open;
+}
@@ -0,0 +1,381 @@
+use bevy::{
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_egui::{
+    egui,
+    EguiContexts,
+};
+
+use astro::LocalSky;
+use astro::RiseSet;
+use astro::angle;
+use astro::catalog::SourceInfo;
+use astro::target::Target;
+use crate::ui::OccupiedScreenSpace;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::clock::SimulationClock;
+use crate::ui::constellations::Constellations;
+use crate::ui::constellations::constellation_labels;
+use crate::ui::grid::GridFrame;
+use crate::ui::legend::marker_scale;
+use crate::ui::menus::adjust_viewport_to_egui;
+use crate::ui::satellites::Satellites;
+use crate::ui::sphere::SolarSystemBody;
+use crate::ui::sphere::Star;
+use crate::ui::sphere::world_to_horizontal;
+use crate::ui::stars::describe_source;
+use crate::ui::stars::format_time;
+
+/// Distance, in points, between a marker and its label.
+const LABEL_OFFSET: f32 = 6.0;
+
+/// Largest distance, in points, between the pointer and a marker for its
+/// tooltip to show.
+const HOVER_DISTANCE: f32 = 10.0;
+
+pub struct LabelsPlugin;
+
+impl Plugin for LabelsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<LabelAnchors>()
+            .add_systems(Update, draw_labels.after(adjust_viewport_to_egui).after(PushLabelAnchors));
+    }
+}
+
+/// Systems of the layers drawn with gizmos, which push the anchors of their
+/// markers before the labels are drawn.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PushLabelAnchors;
+
+/// A marker drawn with gizmos, labelled like the stars.
+pub struct LabelAnchor {
+    pub name: String,
+    pub target: AnchorTarget,
+    /// Metadata of a catalog source, described in its tooltip.
+    pub info: Option<SourceInfo>,
+    /// What the object is, in the tooltip of markers without metadata.
+    pub kind: &'static str,
+    pub world_position: Vec3,
+}
+
+/// What a marker drawn with gizmos stands for.
+pub enum AnchorTarget {
+    Target(Target),
+    /// Index of a satellite in [`Satellites`], which is only copied, with its
+    /// propagator, for the tooltip.
+    Satellite(usize),
+}
+
+/// Markers of the layers drawn with gizmos in this frame.
+#[derive(Resource, Default)]
+pub struct LabelAnchors {
+    anchors: Vec<LabelAnchor>,
+}
+
+impl LabelAnchors {
+    pub fn push(&mut self, anchor: LabelAnchor) {
+        self.anchors.push(anchor);
+    }
+}
+
+/// What a marker projected on the screen stands for.
+#[derive(Clone, Copy)]
+enum MarkerTarget<'a> {
+    Target(&'a Target),
+    Satellite(usize),
+}
+
+/// A marker projected on the screen.
+struct ScreenMarker<'a> {
+    name: &'a str,
+    target: MarkerTarget<'a>,
+    info: Option<&'a SourceInfo>,
+    kind: &'static str,
+    position: egui::Pos2,
+    /// Higher priority labels are placed first and win collisions.
+    priority: (bool, bool, f32),
+}
+
+/// Label the markers above the horizon, those of the stars and those
+/// anchored by the layers, the constellations and the lines of the shown
+/// grids, skipping labels that would overlap one already placed,
+/// and show a tooltip for the hovered marker.
+#[allow(clippy::too_many_arguments)]
+fn draw_labels(
+    mut contexts: EguiContexts,
+    view_config: Res<ViewConfig>,
+    telescope_config: Res<TelescopeConfig>,
+    occupied_screen_space: Res<OccupiedScreenSpace>,
+    constellations: Res<Constellations>,
+    clock: Res<SimulationClock>,
+    satellites: Res<Satellites>,
+    mut label_anchors: ResMut<LabelAnchors>,
+
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    star_query: Query<(&GlobalTransform, &InheritedVisibility, &Star, Has<SolarSystemBody>)>,
+) {
+    let anchors = std::mem::take(&mut label_anchors.anchors);
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let viewport = egui::Rect::from_min_size(
+        egui::Pos2::ZERO,
+        egui::vec2(
+            window.width() - occupied_screen_space.right,
+            window.height() - occupied_screen_space.bottom,
+        ),
+    );
+    let forward = camera_transform.forward();
+    let project = |world_position: Vec3| -> Option<egui::Pos2> {
+        let position = camera.world_to_viewport(camera_transform, world_position)?;
+        let position = egui::pos2(position.x, position.y);
+        viewport.contains(position).then_some(position)
+    };
+    let above_horizon = |world_position: Vec3| {
+        let (azimuth, altitude) = world_to_horizontal(world_position);
+        altitude >= 0.0
+            && (!view_config.show_terrain || altitude >= telescope_config.horizon.terrain_elevation(azimuth))
+    };
+    // Markers on the far side of the sphere are seen through it.
+    let facing = |world_position: Vec3| world_position.dot(*forward) <= 0.0;
+
+    let stars = star_query
+        .iter()
+        .filter(|(transform, visibility, _, _)| visibility.get() && above_horizon(transform.translation()))
+        .filter_map(|(transform, _, star, is_body)| {
+            let world_position = transform.translation();
+            Some(ScreenMarker {
+                name: &star.name,
+                target: MarkerTarget::Target(&star.target),
+                info: (!is_body).then_some(&star.info),
+                kind: "Solar system body",
+                position: project(world_position)?,
+                priority: (is_body, facing(world_position), marker_scale(&star.info)),
+            })
+        });
+    let anchored = anchors
+        .iter()
+        .filter(|anchor| above_horizon(anchor.world_position))
+        .filter_map(|anchor| Some(ScreenMarker {
+            name: &anchor.name,
+            target: match &anchor.target {
+                AnchorTarget::Target(target) => MarkerTarget::Target(target),
+                AnchorTarget::Satellite(index) => MarkerTarget::Satellite(*index),
+            },
+            info: anchor.info.as_ref(),
+            kind: anchor.kind,
+            position: project(anchor.world_position)?,
+            priority: (false, facing(anchor.world_position), anchor.info.as_ref().map_or(1.0, marker_scale)),
+        }));
+    let mut markers: Vec<ScreenMarker> = stars.chain(anchored).collect();
+    markers.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap_or(std::cmp::Ordering::Equal));
+
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        clock.time(),
+    ).with_atmosphere(&telescope_config.atmosphere)
+    .with_horizon(&telescope_config.horizon);
+    let ctx = contexts.ctx_mut();
+    let painter = ctx.layer_painter(egui::LayerId::background()).with_clip_rect(viewport);
+    let mut placed: Vec<egui::Rect> = Vec::new();
+
+    if view_config.show_labels {
+        for marker in &markers {
+            place_label(&painter, &mut placed, viewport, marker.position,
+                marker.name.to_string(), egui::Color32::from_gray(220));
+        }
+    }
+
+    if view_config.show_constellations {
+        for (name, world_position) in constellation_labels(&constellations, &sky) {
+            if world_position.y < 0.0 {
+                continue;
+            }
+            if let Some(position) = project(world_position) {
+                place_label(&painter, &mut placed, viewport, position, name, egui::Color32::from_rgb(140, 160, 210));
+            }
+        }
+    }
+
+    // Grid lines are only labelled on the side of the sphere facing the
+    // camera, where they are not mixed up with the lines behind.
+    for (text, world_position, color) in grid_labels(&view_config, &sky, -*forward) {
+        if world_position.dot(*forward) > 0.0 || world_position.y < -0.01 {
+            continue;
+        }
+        if let Some(position) = project(world_position) {
+            place_label(&painter, &mut placed, viewport, position, text, color);
+        }
+    }
+
+    if ctx.is_pointer_over_area() {
+        return;
+    }
+    let Some(pointer) = ctx.input(|input| input.pointer.hover_pos()) else {
+        return;
+    };
+    let hovered = markers
+        .iter()
+        .filter(|marker| marker.position.distance(pointer) < HOVER_DISTANCE)
+        .min_by(|a, b| a.position.distance(pointer).total_cmp(&b.position.distance(pointer)));
+    let Some(marker) = hovered else {
+        return;
+    };
+    let satellite;
+    let target = match marker.target {
+        MarkerTarget::Target(target) => target,
+        MarkerTarget::Satellite(index) => {
+            let Some(target) = satellites.target(index) else {
+                return;
+            };
+            satellite = target;
+            &satellite
+        },
+    };
+    egui::show_tooltip_at_pointer(ctx, egui::LayerId::background(), egui::Id::new("star_tooltip"), |ui| {
+        marker_tooltip(ui, marker, target, &sky);
+    });
+}
+
+/// Paint `text` next to a point of the screen, on the first side where it
+/// does not overlap the labels already placed, or nowhere.
+fn place_label(
+    painter: &egui::Painter,
+    placed: &mut Vec<egui::Rect>,
+    viewport: egui::Rect,
+    center: egui::Pos2,
+    text: String,
+    color: egui::Color32,
+) {
+    let galley = painter.layout_no_wrap(text, egui::FontId::proportional(12.0), color);
+    let size = galley.size();
+    // Right, left, above and below the point, in that order.
+    let candidates = [
+        egui::pos2(center.x + LABEL_OFFSET, center.y - size.y / 2.0),
+        egui::pos2(center.x - LABEL_OFFSET - size.x, center.y - size.y / 2.0),
+        egui::pos2(center.x - size.x / 2.0, center.y - LABEL_OFFSET - size.y),
+        egui::pos2(center.x - size.x / 2.0, center.y + LABEL_OFFSET),
+    ];
+    let free = candidates
+        .into_iter()
+        .map(|corner| egui::Rect::from_min_size(corner, size))
+        .find(|rect| viewport.contains_rect(*rect) && placed.iter().all(|other| !other.intersects(*rect)));
+    if let Some(rect) = free {
+        painter.galley(rect.min, galley, color);
+        placed.push(rect);
+    }
+}
+
+/// Text, point of the sphere and colour of the labels of the major lines of
+/// the shown grids. Longitudes are written along the equator of each grid,
+/// or just above the horizon, and latitudes along the major meridian closest
+/// to `toward_camera`.
+fn grid_labels(view_config: &ViewConfig, sky: &LocalSky, toward_camera: Vec3) -> Vec<(String, Vec3, egui::Color32)> {
+    let mut labels = Vec::new();
+    for frame in GridFrame::ALL {
+        let shown = match frame {
+            GridFrame::Equatorial => view_config.show_equatorial_grid,
+            GridFrame::Azimuthal => view_config.show_azimuthal_grid,
+            GridFrame::Galactic => view_config.show_galactic_grid,
+            GridFrame::Ecliptic => view_config.show_ecliptic_grid,
+        };
+        if !shown {
+            continue;
+        }
+        let style = view_config.grids.get(frame);
+        let spacing = style.major_spacing();
+        // Labels are drawn brighter than the lines.
+        let [red, green, blue] = style.color.map(|channel| ((0.5 + channel / 2.0) * 255.0) as u8);
+        let color = egui::Color32::from_rgb(red, green, blue);
+
+        let equator = if frame == GridFrame::Azimuthal { 1.0 } else { 0.0 };
+        let n_meridians = ((360.0 - 1e-6) / spacing).floor() as i64;
+        for meridian in 0..=n_meridians {
+            let longitude = meridian as f64 * spacing;
+            labels.push((frame.longitude_label(longitude), frame.world_point(longitude, equator, sky), color));
+        }
+
+        let (camera_longitude, _) = frame.coordinates_of_world(toward_camera.normalize(), sky);
+        let longitude = (camera_longitude / spacing).round() * spacing;
+        let n_parallels = ((90.0 - 1e-6) / spacing).floor() as i64;
+        for parallel in (-n_parallels..=n_parallels).filter(|parallel| *parallel != 0) {
+            let latitude = parallel as f64 * spacing;
+            labels.push((frame.latitude_label(latitude), frame.world_point(longitude, latitude, sky), color));
+        }
+    }
+    labels
+}
+
+/// Apparent right ascension and declination of a target, in degrees.
+fn apparent_equatorial(sky: &LocalSky, target: &Target) -> Option<(f64, f64)> {
+    match target {
+        Target::Catalog { position, .. } => Some(sky.apparent_place(position)),
+        Target::Body { body } => {
+            let position = body.apparent_position(&sky.frame);
+            Some((position.ra, position.dec))
+        },
+        Target::MinorBody { body } => {
+            let position = body.apparent_position(&sky.frame);
+            Some((position.ra, position.dec))
+        },
+        Target::Table { table } => table
+            .apparent_position(&sky.frame, sky.time)
+            .ok()
+            .map(|position| (position.ra, position.dec)),
+        Target::Satellite { .. } => None,
+    }
+}
+
+fn marker_tooltip(
+    ui: &mut egui::Ui,
+    marker: &ScreenMarker,
+    target: &Target,
+    sky: &LocalSky,
+) {
+    ui.strong(marker.name);
+    match marker.info {
+        Some(info) => ui.label(describe_source(info)),
+        None => ui.label(marker.kind),
+    };
+    let (azimuth, altitude) = sky.target_horizontal(target);
+    let equatorial = apparent_equatorial(sky, target);
+    egui::Grid::new("star_tooltip_grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            if let Some((ra, dec)) = equatorial {
+                ui.label("RA/Dec");
+                ui.label(format!("{} {}", angle::format_right_ascension(ra), angle::format_declination(dec)));
+                ui.end_row();
+            }
+
+            ui.label("Alt/Az");
+            ui.label(format!("{:.2}° {:.2}°", altitude, azimuth));
+            ui.end_row();
+
+            if let Some((ra, dec)) = equatorial {
+                let rise_set = match target {
+                    Target::Catalog { position, .. } => sky.rise_transit_set(position),
+                    _ => sky.apparent_rise_transit_set(ra, dec),
+                };
+                ui.label("Rise/transit/set");
+                ui.label(match rise_set {
+                    RiseSet::Circumpolar { transit } => format!("circumpolar, transit {}", format_time(Some(transit))),
+                    RiseSet::NeverRises => String::from("never rises"),
+                    RiseSet::Rises { rise, transit, set } => format!("{} / {} / {}",
+                        format_time(Some(rise)),
+                        format_time(Some(transit)),
+                        format_time(Some(set)),
+                    ),
+                });
+                ui.end_row();
+            }
+        });
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use astro::catalog::SourceInfo;
+use astro::catalog::SourceType;
+use crate::ui::ViewConfig;
+
+/// Frequency at which flux densities are compared to size the markers, in
+/// megahertz: the 21 cm hydrogen line.
+pub const DISPLAY_FREQUENCY_MHZ: f64 = 1420.0;
+
+/// Which sources are drawn on the sky.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SourceFilter {
+    hidden_types: Vec<SourceType>,
+    /// Sources fainter than this flux density at `DISPLAY_FREQUENCY_MHZ`, in
+    /// janskys, are hidden. Sources of unknown flux are kept.
+    min_flux: f64,
+    /// Sources fainter than this magnitude are hidden. Sources of unknown
+    /// magnitude are kept.
+    max_magnitude: f64,
+}
+
+impl Default for SourceFilter {
+    fn default() -> Self {
+        SourceFilter {
+            hidden_types: Vec::new(),
+            min_flux: 0.0,
+            max_magnitude: 30.0,
+        }
+    }
+}
+
+impl SourceFilter {
+    pub fn accepts(&self, info: &SourceInfo) -> bool {
+        !self.hidden_types.contains(&info.kind)
+            && info.flux_at(DISPLAY_FREQUENCY_MHZ).is_none_or(|flux| flux >= self.min_flux)
+            && info.magnitude.is_none_or(|magnitude| magnitude <= self.max_magnitude)
+    }
+}
+
+/// Colour of the markers of a type of source, or `None` for sources of
+/// unknown type, which keep the colour of their layer.
+pub fn type_color(kind: SourceType) -> Option<(f32, f32, f32)> {
+    match kind {
+        SourceType::Star => Some((1.0, 0.85, 0.4)),
+        SourceType::Pulsar => Some((0.3, 1.0, 1.0)),
+        SourceType::SupernovaRemnant => Some((1.0, 0.3, 0.2)),
+        SourceType::HiiRegion => Some((1.0, 0.4, 0.8)),
+        SourceType::Galaxy => Some((0.6, 0.6, 1.0)),
+        SourceType::Quasar => Some((0.7, 0.3, 1.0)),
+        SourceType::Maser => Some((0.3, 1.0, 0.4)),
+        SourceType::Unknown => None,
+    }
+}
+
+/// Size of the marker of a source relative to that of a 1 Jy source or a
+/// magnitude 2 star, growing with the logarithm of the brightness.
+pub fn marker_scale(info: &SourceInfo) -> f32 {
+    let scale = match (info.flux_at(DISPLAY_FREQUENCY_MHZ), info.magnitude) {
+        (Some(flux), _) => 1.0 + 0.3 * flux.max(1e-3).log10(),
+        (None, Some(magnitude)) => 1.0 + 0.12 * (2.0 - magnitude),
+        (None, None) => 1.0,
+    };
+    scale.clamp(0.4, 2.5) as f32
+}
+
+/// Colour of a marker, defaulting to `fallback` for unknown types.
+pub fn marker_color(info: &SourceInfo, fallback: (f32, f32, f32)) -> Color {
+    let (red, green, blue) = type_color(info.kind).unwrap_or(fallback);
+    Color::linear_rgb(red, green, blue)
+}
+
+/// Window listing the colour of each type of source, with the filters
+/// applied to the sky.
+pub fn legend_window(
+    ctx: &egui::Context,
+    view_config: &mut ViewConfig,
+) {
+    let mut open = view_config.show_legend;
+    egui::Window::new("Legend")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            let filter = &mut view_config.source_filter;
+            for kind in SourceType::ALL {
+                ui.horizontal(|ui| {
+                    let (red, green, blue) = type_color(kind).unwrap_or((0.6, 0.6, 0.6));
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                    ui.painter().circle_filled(
+                        rect.center(),
+                        5.0,
+                        egui::Color32::from_rgb((red * 255.0) as u8, (green * 255.0) as u8, (blue * 255.0) as u8),
+                    );
+                    let mut shown = !filter.hidden_types.contains(&kind);
+                    if ui.checkbox(&mut shown, kind.to_string()).changed() {
+                        if shown {
+                            filter.hidden_types.retain(|hidden| *hidden != kind);
+                        } else {
+                            filter.hidden_types.push(kind);
+                        }
+                    }
+                });
+            }
+            ui.label("Unknown types keep the colour of their catalog layer.");
+            ui.separator();
+            ui.label(format!("Marker size grows with the flux density at {} MHz, or else with the brightness.",
+                DISPLAY_FREQUENCY_MHZ));
+            egui::Grid::new("legend_filter_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Minimum flux density (Jy)");
+                    ui.add(egui::DragValue::new(&mut filter.min_flux)
+                        .speed(0.1)
+                        .range(0.0..=f64::MAX),
+                    );
+                    ui.end_row();
+
+                    ui.label("Faintest magnitude");
+                    ui.add(egui::DragValue::new(&mut filter.max_magnitude)
+                        .speed(0.1)
+                        .range(-30.0..=30.0),
+                    );
+                    ui.end_row();
+                });
+        });
+    view_config.show_legend = open;
+}
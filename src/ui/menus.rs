@@ -10,13 +10,54 @@ use bevy_egui::{
     EguiContexts,
 };
 
+use astro::refraction::RefractionModel;
+use crate::config;
 use crate::ui::OccupiedScreenSpace;
 use crate::ui::TelescopeConfig;
 use crate::ui::ViewConfig;
 use crate::ui::OriginalCameraConfig;
+use crate::ui::catalogs::CatalogLayers;
+use crate::ui::catalogs::CatalogsTab;
+use crate::ui::catalogs::catalogs_tab;
+use crate::ui::clock::ClockForm;
+use crate::ui::clock::SimulationClock;
+use crate::ui::clock::clock_controls;
+use crate::ui::cursor::SkyCursor;
+use crate::ui::cursor::cursor_readout;
+use crate::ui::ephemeris_tables::EphemerisTables;
+use crate::ui::ephemeris_tables::EphemerisTablesTab;
+use crate::ui::ephemeris_tables::ephemeris_tables_tab;
+use crate::ui::grid::grid_settings_window;
+use crate::ui::legend::legend_window;
+use crate::ui::minor_bodies::MinorBodies;
+use crate::ui::minor_bodies::MinorBodiesTab;
+use crate::ui::minor_bodies::minor_bodies_tab;
+use crate::ui::queue::QueueTab;
+use crate::ui::queue::queue_tab;
+use crate::ui::satellites::Satellites;
+use crate::ui::satellites::SatellitesTab;
+use crate::ui::satellites::satellites_tab;
 use crate::ui::sphere::TopBarTab;
 use crate::ui::sphere::TopBarTabOption;
+use crate::ui::stars::StarsTab;
+use crate::ui::stars::stars_tab;
+use crate::ui::state::UiState;
 
+/// State of the widgets of the panels: the selected tab of the right panel,
+/// each of its tabs and the clock controls.
+#[derive(Default)]
+pub struct Tabs {
+    top_bar: TopBarTab,
+    clock: ClockForm,
+    stars: StarsTab,
+    catalogs: CatalogsTab,
+    satellites: SatellitesTab,
+    minor_bodies: MinorBodiesTab,
+    ephemeris_tables: EphemerisTablesTab,
+    queue: QueueTab,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn egui_system(
     mut contexts: EguiContexts,
 
@@ -24,30 +65,70 @@ pub fn egui_system(
     mut telescope_config: ResMut<TelescopeConfig>,
     mut view_config: ResMut<ViewConfig>,
     original_camera_config: Res<OriginalCameraConfig>,
+    ui_state: Res<UiState>,
+    mut client_config: ResMut<config::ClientConfig>,
+    catalogs: Res<CatalogLayers>,
+    mut satellites: ResMut<Satellites>,
+    mut minor_bodies: ResMut<MinorBodies>,
+    mut ephemeris_tables: ResMut<EphemerisTables>,
+    sky_cursor: Res<SkyCursor>,
+    mut clock: ResMut<SimulationClock>,
 
     mut camera_query: Query<&mut PanOrbitCamera>,
 
-    mut top_bar: Local<TopBarTab>,
+    mut tabs: Local<Tabs>,
+    mut reset_panel_width: Local<bool>,
 ) {
     let ctx = contexts.ctx_mut();
 
-    occupied_screen_space.right = egui::SidePanel::right("right_panel")
+    let mut right_panel = egui::SidePanel::right("right_panel")
         .resizable(true)
         .min_width(450.0)
+        .default_width(ui_state.right_panel_width);
+    if *reset_panel_width {
+        right_panel = right_panel.exact_width(UiState::default().right_panel_width);
+        *reset_panel_width = false;
+    }
+    occupied_screen_space.right = right_panel
         .show(ctx, |ui| {
             egui::TopBottomPanel::top("my_panel")
                 .show_inside(ui, |ui| {
                 ui.horizontal_wrapped(|ui| {
+                    let top_bar = &mut tabs.top_bar;
                     ui.selectable_value(&mut top_bar.tab, TopBarTabOption::Stars, "Stars");
+                    ui.selectable_value(&mut top_bar.tab, TopBarTabOption::Catalogs, "Catalogs");
+                    ui.selectable_value(&mut top_bar.tab, TopBarTabOption::Satellites, "Satellites");
+                    ui.selectable_value(&mut top_bar.tab, TopBarTabOption::MinorBodies, "Comets and asteroids");
+                    ui.selectable_value(&mut top_bar.tab, TopBarTabOption::EphemerisTables, "Ephemeris tables");
+                    ui.selectable_value(&mut top_bar.tab, TopBarTabOption::Queue, "Queue");
                     ui.selectable_value(&mut top_bar.tab, TopBarTabOption::TelescopeControl, "Telescope control");
                 });
             });
 
-            match top_bar.tab {
+            match tabs.top_bar.tab {
                 TopBarTabOption::Stars => {
+                    stars_tab(ui, &mut tabs.stars, &mut client_config, &telescope_config, clock.time());
+                },
+                TopBarTabOption::Catalogs => {
+                    catalogs_tab(ui, &mut tabs.catalogs, &catalogs, &mut client_config);
+                },
+                TopBarTabOption::Satellites => {
+                    satellites_tab(ui, &mut tabs.satellites, &mut satellites, &mut client_config);
+                },
+                TopBarTabOption::MinorBodies => {
+                    minor_bodies_tab(ui, &mut tabs.minor_bodies, &mut minor_bodies, &mut client_config);
+                },
+                TopBarTabOption::EphemerisTables => {
+                    ephemeris_tables_tab(ui, &mut tabs.ephemeris_tables, &mut ephemeris_tables, &mut client_config);
+                },
+                TopBarTabOption::Queue => {
+                    queue_tab(ui, &mut tabs.queue, &client_config, &catalogs);
                 },
                 TopBarTabOption::TelescopeControl => {
                     let mut radio = 0;
+                    // Edit a copy so that the site is only marked as changed,
+                    // which recomputes every position, on actual changes.
+                    let mut edited_config = telescope_config.clone();
 
                     egui::Grid::new("my_grid")
                         .num_columns(3)
@@ -66,24 +147,96 @@ pub fn egui_system(
                             ui.end_row();
 
                             ui.label("SDR++ host:");
-                            ui.add(egui::TextEdit::singleline(&mut telescope_config.sdrpp_url).interactive(true));
+                            ui.add(egui::TextEdit::singleline(&mut edited_config.sdrpp_url).interactive(true));
                             ui.add(egui::widgets::Button::new("Test"));
                             ui.end_row();
 
                             ui.label("Latitude");
-                            ui.add(egui::DragValue::new(&mut telescope_config.latitude)
+                            ui.add(egui::DragValue::new(&mut edited_config.latitude)
                                 .speed(0.01)
                                 .range(-180.0..=180.0),
                             );
                             ui.end_row();
 
                             ui.label("Longitude");
-                            ui.add(egui::DragValue::new(&mut telescope_config.longitude)
+                            ui.add(egui::DragValue::new(&mut edited_config.longitude)
                                 .speed(0.01)
                                 .range(-180.0..=180.0),
                             );
                             ui.end_row();
+
+                            ui.label("Refraction");
+                            let refraction = &mut edited_config.atmosphere.refraction;
+                            egui::ComboBox::from_id_salt("refraction_model")
+                                .selected_text(format!("{refraction:?}"))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(refraction, RefractionModel::None, "None");
+                                    ui.selectable_value(refraction, RefractionModel::Optical, "Optical");
+                                    ui.selectable_value(refraction, RefractionModel::Radio, "Radio");
+                                });
+                            ui.end_row();
+
+                            ui.label("Temperature (°C)");
+                            ui.add(egui::DragValue::new(&mut edited_config.atmosphere.temperature)
+                                .speed(0.1)
+                                .range(-60.0..=60.0),
+                            );
+                            ui.end_row();
+
+                            ui.label("Pressure (hPa)");
+                            ui.add(egui::DragValue::new(&mut edited_config.atmosphere.pressure)
+                                .speed(0.5)
+                                .range(300.0..=1100.0),
+                            );
+                            ui.end_row();
+
+                            ui.label("Relative humidity");
+                            ui.add(egui::DragValue::new(&mut edited_config.atmosphere.humidity)
+                                .speed(0.01)
+                                .range(0.0..=1.0),
+                            );
+                            ui.end_row();
+
+                            ui.label("Weather sensor file");
+                            let mut sensor_file = edited_config.atmosphere.sensor_file.clone()
+                                .unwrap_or_default();
+                            if ui.add(egui::TextEdit::singleline(&mut sensor_file)
+                                .hint_text("none"))
+                                .changed() {
+                                edited_config.atmosphere.sensor_file = if sensor_file.is_empty() {
+                                    None
+                                } else {
+                                    Some(sensor_file)
+                                };
+                            }
+                            ui.end_row();
+
+                            ui.label("Horizon profile");
+                            let horizon = &edited_config.horizon;
+                            ui.label(match (horizon.points.len(), &horizon.file) {
+                                (0, _) => String::from("flat"),
+                                (points, Some(file)) => format!("{} points from {}", points, file),
+                                (points, None) => format!("{} points", points),
+                            });
+                            ui.end_row();
+
+                            ui.label("Elevation limit (°)");
+                            ui.label(match horizon.limit {
+                                Some(limit) => format!("{:.1}", limit),
+                                None => String::from("none"),
+                            });
+                            ui.end_row();
+
+                            ui.label("Cable wrap (°)");
+                            ui.label(match &edited_config.cable_wrap {
+                                Some(wrap) => format!("from {:.1} to {:.1}", wrap.min, wrap.max),
+                                None => String::from("none"),
+                            });
+                            ui.end_row();
                     });
+                    if edited_config != *telescope_config {
+                        *telescope_config = edited_config;
+                    }
                 },
             }
             ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
@@ -94,7 +247,7 @@ pub fn egui_system(
     occupied_screen_space.bottom = egui::TopBottomPanel::bottom("bottom_panel")
         .resizable(false)
         .show(ctx, |ui| {
-            ui.horizontal(|ui| {
+            ui.horizontal_wrapped(|ui| {
                 if ui.add(egui::widgets::Button::new("Reset view")).clicked() {
                     if let Ok(mut camera) = camera_query.get_single_mut() {
                         camera.target_radius = original_camera_config.radius;
@@ -112,23 +265,131 @@ pub fn egui_system(
                     .clicked() {
                     view_config.show_equatorial_grid = !view_config.show_equatorial_grid;
                 };
-
-                let stop_time_button_name = if view_config.time_stopped {
-                    "S"
-                } else {
-                    "R"
+                if ui.add(egui::widgets::Button::new("Galactic grid")
+                    .selected(view_config.show_galactic_grid))
+                    .clicked() {
+                    view_config.show_galactic_grid = !view_config.show_galactic_grid;
+                };
+                if ui.add(egui::widgets::Button::new("Ecliptic grid")
+                    .selected(view_config.show_ecliptic_grid))
+                    .clicked() {
+                    view_config.show_ecliptic_grid = !view_config.show_ecliptic_grid;
+                };
+                if ui.add(egui::widgets::Button::new("Grid settings")
+                    .selected(view_config.show_grid_settings))
+                    .clicked() {
+                    view_config.show_grid_settings = !view_config.show_grid_settings;
                 };
-                if ui.add(egui::widgets::Button::new(stop_time_button_name)
-                    .selected(view_config.time_stopped))
+                if ui.add(egui::widgets::Button::new("Galactic plane")
+                    .selected(view_config.show_galactic_plane))
                     .clicked() {
-                    view_config.time_stopped = !view_config.time_stopped;
+                    view_config.show_galactic_plane = !view_config.show_galactic_plane;
+                };
+                if ui.add(egui::widgets::Button::new("Ecliptic")
+                    .selected(view_config.show_ecliptic))
+                    .clicked() {
+                    view_config.show_ecliptic = !view_config.show_ecliptic;
+                };
+                if ui.add(egui::widgets::Button::new("Equator")
+                    .selected(view_config.show_celestial_equator))
+                    .clicked() {
+                    view_config.show_celestial_equator = !view_config.show_celestial_equator;
+                };
+                if ui.add(egui::widgets::Button::new("Meridian")
+                    .selected(view_config.show_meridian))
+                    .clicked() {
+                    view_config.show_meridian = !view_config.show_meridian;
+                };
+                if ui.add(egui::widgets::Button::new("Horizon")
+                    .selected(view_config.show_horizon))
+                    .clicked() {
+                    view_config.show_horizon = !view_config.show_horizon;
+                };
+                if ui.add(egui::widgets::Button::new("Terrain")
+                    .selected(view_config.show_terrain))
+                    .clicked() {
+                    view_config.show_terrain = !view_config.show_terrain;
+                };
+                if ui.add(egui::widgets::Button::new("Constellations")
+                    .selected(view_config.show_constellations))
+                    .clicked() {
+                    view_config.show_constellations = !view_config.show_constellations;
+                };
+                if ui.add(egui::widgets::Button::new("Satellites")
+                    .selected(view_config.show_satellites))
+                    .clicked() {
+                    view_config.show_satellites = !view_config.show_satellites;
+                };
+                if ui.add(egui::widgets::Button::new("Comets and asteroids")
+                    .selected(view_config.show_minor_bodies))
+                    .clicked() {
+                    view_config.show_minor_bodies = !view_config.show_minor_bodies;
+                };
+                if ui.add(egui::widgets::Button::new("Labels")
+                    .selected(view_config.show_labels))
+                    .clicked() {
+                    view_config.show_labels = !view_config.show_labels;
+                };
+                if ui.add(egui::widgets::Button::new("Legend")
+                    .selected(view_config.show_legend))
+                    .clicked() {
+                    view_config.show_legend = !view_config.show_legend;
+                };
+                if ui.add(egui::widgets::Button::new("Altitude chart")
+                    .selected(view_config.show_altitude_chart))
+                    .clicked() {
+                    view_config.show_altitude_chart = !view_config.show_altitude_chart;
+                };
+                if ui.add(egui::widgets::Button::new("Planner")
+                    .selected(view_config.show_planner))
+                    .clicked() {
+                    view_config.show_planner = !view_config.show_planner;
+                };
+                if ui.add(egui::widgets::Button::new("Ephemeris tables")
+                    .selected(view_config.show_ephemeris_tables))
+                    .clicked() {
+                    view_config.show_ephemeris_tables = !view_config.show_ephemeris_tables;
+                };
+
+                if ui.add(egui::widgets::Button::new("Reset to defaults")).clicked() {
+                    *telescope_config = TelescopeConfig::default();
+                    *view_config = ViewConfig::default();
+                    clock.reset();
+                    *reset_panel_width = true;
+                    if let Ok(mut camera) = camera_query.get_single_mut() {
+                        camera.target_radius = original_camera_config.radius;
+                        camera.target_pitch = original_camera_config.pitch;
+                        camera.target_yaw = original_camera_config.yaw;
+                    }
                 };
             });
+            clock_controls(ui, &mut tabs.clock, &mut clock);
+            cursor_readout(ui, &sky_cursor, &telescope_config, clock.time());
             ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
         })
         .response
         .rect
         .height();
+
+    if view_config.show_grid_settings {
+        // Grids are built again whenever the view changes, so only assign
+        // back actual changes.
+        let mut grid_view_config = view_config.clone();
+        grid_settings_window(ctx, &mut grid_view_config);
+        if grid_view_config != *view_config {
+            *view_config = grid_view_config;
+        }
+    }
+
+    if view_config.show_legend {
+        // Edit a copy so that the view is only marked as changed when the
+        // filters actually change.
+        let mut legend_view_config = view_config.clone();
+        legend_window(ctx, &mut legend_view_config);
+        if legend_view_config != *view_config {
+            *view_config = legend_view_config;
+        }
+    }
 }
 
 pub fn adjust_viewport_to_egui(
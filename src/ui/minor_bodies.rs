@@ -0,0 +1,301 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use chrono::{
+    DateTime,
+    Duration,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::angle;
+use astro::minor_body::MinorBody;
+use astro::target::Target;
+use crate::config;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::clock::SimulationClock;
+use crate::ui::labels::AnchorTarget;
+use crate::ui::labels::LabelAnchor;
+use crate::ui::labels::LabelAnchors;
+use crate::ui::labels::PushLabelAnchors;
+use crate::ui::sphere::horizontal_to_world;
+use crate::ui::stars::save_client_config;
+
+/// Seconds between two updates of the comet and asteroid positions.
+const UPDATE_PERIOD_SECS: f32 = 5.0;
+
+/// Days before and after now of the path drawn for the selected body.
+const TRACK_DAYS: i64 = 30;
+
+/// Rows shown at most in the comet and asteroid table.
+const MAX_ROWS: usize = 200;
+
+pub struct MinorBodiesPlugin;
+
+impl Plugin for MinorBodiesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MinorBodies>()
+            .add_systems(Update, (
+                load_minor_bodies.run_if(resource_changed::<config::ClientConfig>),
+                update_minor_bodies,
+                draw_minor_bodies.in_set(PushLabelAnchors),
+            ).chain());
+    }
+}
+
+/// Current position of a comet or asteroid.
+#[derive(Clone, Copy)]
+struct MinorBodyPosition {
+    ra: f64,
+    dec: f64,
+    azimuth: f64,
+    elevation: f64,
+    /// Distance from the Earth, in astronomical units.
+    distance: f64,
+}
+
+/// Comets and asteroids of the configured MPC files and their current
+/// positions.
+#[derive(Resource, Default)]
+pub struct MinorBodies {
+    list: Vec<MinorBody>,
+    positions: Vec<Option<MinorBodyPosition>>,
+    selected: Option<String>,
+    /// Right ascension and declination of the selected body, a day apart,
+    /// around now.
+    track: Vec<(f64, f64)>,
+    /// Whether the positions must be updated without waiting for the next
+    /// period.
+    outdated: bool,
+}
+
+#[derive(Default)]
+pub struct MinorBodiesTab {
+    search: String,
+    mpc_file: String,
+}
+
+fn load_minor_bodies(
+    client_config: Res<config::ClientConfig>,
+    mut minor_bodies: ResMut<MinorBodies>,
+) {
+    let list = client_config.minor_bodies();
+    if list != minor_bodies.list {
+        *minor_bodies = MinorBodies {
+            positions: vec![None; list.len()],
+            list,
+            outdated: true,
+            ..default()
+        };
+    }
+}
+
+fn update_minor_bodies(
+    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    telescope_config: Res<TelescopeConfig>,
+    mut minor_bodies: ResMut<MinorBodies>,
+    mut countdown: Local<f32>,
+    mut updated: Local<Option<DateTime<Utc>>>,
+) {
+    *countdown -= time.delta_seconds();
+    if *countdown > 0.0 && !minor_bodies.outdated && !clock.moved_since(*updated, UPDATE_PERIOD_SECS) {
+        return;
+    }
+    *countdown = UPDATE_PERIOD_SECS;
+    *updated = Some(clock.time());
+    minor_bodies.outdated = false;
+
+    let now = clock.time();
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        now,
+    ).with_atmosphere(&telescope_config.atmosphere);
+    let positions = minor_bodies.list
+        .iter()
+        .map(|body| {
+            let position = body.apparent_position(&sky.frame);
+            let (azimuth, elevation) = sky.body_horizontal(&position);
+            Some(MinorBodyPosition {
+                ra: position.ra,
+                dec: position.dec,
+                azimuth,
+                elevation,
+                distance: position.distance,
+            })
+        })
+        .collect();
+    minor_bodies.positions = positions;
+
+    let track = match minor_bodies.selected
+        .as_ref()
+        .and_then(|name| minor_bodies.list.iter().find(|body| &body.name == name)) {
+        Some(body) => (-TRACK_DAYS..=TRACK_DAYS)
+            .map(|day| {
+                let sky = LocalSky::new(sky.latitude, sky.longitude, now + Duration::days(day));
+                let position = body.apparent_position(&sky.frame);
+                (position.ra, position.dec)
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    minor_bodies.track = track;
+}
+
+/// Draw the comets and asteroids above the horizon, and the path of the
+/// selected one among the stars.
+fn draw_minor_bodies(
+    view_config: Res<ViewConfig>,
+    telescope_config: Res<TelescopeConfig>,
+    clock: Res<SimulationClock>,
+    minor_bodies: Res<MinorBodies>,
+    mut label_anchors: ResMut<LabelAnchors>,
+    mut gizmos: Gizmos,
+) {
+    if !view_config.show_minor_bodies {
+        return;
+    }
+    for (body, position) in minor_bodies.list.iter().zip(&minor_bodies.positions) {
+        let Some(position) = position else {
+            continue;
+        };
+        if position.elevation < 0.0 {
+            continue;
+        }
+        if view_config.show_terrain
+            && position.elevation < telescope_config.horizon.terrain_elevation(position.azimuth) {
+            continue;
+        }
+        let selected = minor_bodies.selected.as_ref() == Some(&body.name);
+        let (radius, color) = if selected {
+            (0.03, Color::linear_rgb(0.2, 1.0, 1.0))
+        } else {
+            (0.012, Color::linear_rgb(0.5, 0.8, 1.0))
+        };
+        let world_position = horizontal_to_world(position.azimuth, position.elevation);
+        gizmos.sphere(world_position, Quat::IDENTITY, radius, color);
+        label_anchors.push(LabelAnchor {
+            name: body.name.clone(),
+            target: AnchorTarget::Target(Target::MinorBody { body: body.clone() }),
+            info: None,
+            kind: "Comet or asteroid",
+            world_position,
+        });
+    }
+
+    // The path is drawn on the sky as it is now, like the equatorial grid.
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        clock.time(),
+    );
+    gizmos.linestrip(
+        minor_bodies.track.iter().map(|(ra, dec)| {
+            let (azimuth, elevation) = sky.apparent_to_horizontal(*ra, *dec);
+            horizontal_to_world(azimuth, elevation)
+        }),
+        Color::linear_rgb(0.2, 1.0, 1.0),
+    );
+}
+
+pub fn minor_bodies_tab(
+    ui: &mut egui::Ui,
+    tab: &mut MinorBodiesTab,
+    minor_bodies: &mut MinorBodies,
+    client_config: &mut ResMut<config::ClientConfig>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("MPC file:");
+        ui.add(egui::TextEdit::singleline(&mut tab.mpc_file).hint_text("path"));
+        if ui.button("Add").clicked() && !tab.mpc_file.trim().is_empty() {
+            client_config.mpc_files.push(tab.mpc_file.trim().to_string());
+            tab.mpc_file.clear();
+            save_client_config(client_config);
+        }
+    });
+    let mut removed = None;
+    for (index, path) in client_config.mpc_files.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(path);
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        client_config.mpc_files.remove(index);
+        save_client_config(client_config);
+    }
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Search:");
+        ui.add(egui::TextEdit::singleline(&mut tab.search).hint_text("name"));
+        ui.label(format!("{} comets and asteroids", minor_bodies.list.len()));
+    });
+
+    let search = tab.search.to_lowercase();
+    let mut rows: Vec<(usize, f64)> = minor_bodies.list
+        .iter()
+        .enumerate()
+        .filter(|(_, body)| body.name.to_lowercase().contains(&search))
+        .map(|(index, _)| {
+            let elevation = minor_bodies.positions.get(index).copied().flatten().map_or(-90.0, |position| position.elevation);
+            (index, elevation)
+        })
+        .collect();
+    // Highest first, so that the bodies in view come on top.
+    rows.sort_by(|a, b| b.1.total_cmp(&a.1));
+    rows.truncate(MAX_ROWS);
+
+    let mut selected = None;
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("minor_bodies_grid")
+            .num_columns(7)
+            .spacing([12.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("RA");
+                ui.strong("Dec");
+                ui.strong("Az");
+                ui.strong("El");
+                ui.strong("Distance");
+                ui.strong("H");
+                ui.end_row();
+
+                for (index, _) in rows {
+                    let body = &minor_bodies.list[index];
+                    let is_selected = minor_bodies.selected.as_ref() == Some(&body.name);
+                    if ui.selectable_label(is_selected, &body.name)
+                        .on_hover_text(format!("q {:.4} AU, e {:.4}, i {:.2}°",
+                            body.orbit.perihelion_distance, body.orbit.eccentricity, body.orbit.inclination))
+                        .clicked() {
+                        selected = Some(body.name.clone());
+                    }
+                    match minor_bodies.positions.get(index).copied().flatten() {
+                        Some(position) => {
+                            ui.label(angle::format_right_ascension(position.ra));
+                            ui.label(angle::format_declination(position.dec));
+                            ui.label(format!("{:.1}°", position.azimuth));
+                            ui.label(format!("{:.1}°", position.elevation));
+                            ui.label(format!("{:.3} AU", position.distance));
+                        },
+                        None => {
+                            for _ in 0..5 {
+                                ui.label("-");
+                            }
+                        },
+                    }
+                    ui.label(body.magnitude.map_or(String::from("-"), |magnitude| format!("{:.1}", magnitude)));
+                    ui.end_row();
+                }
+            });
+    });
+    if let Some(name) = selected {
+        minor_bodies.selected = Some(name);
+        minor_bodies.outdated = true;
+    }
+}
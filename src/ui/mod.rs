@@ -1,3 +1,5 @@
+use std::sync::mpsc;
+
 use bevy::{
     prelude::*,
     render::camera::ScalingMode,
@@ -9,26 +11,84 @@ use bevy_panorbit_camera::{
 use bevy_egui::{
     EguiPlugin
 };
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use astro::cable_wrap::CableWrap;
+use astro::horizon::HorizonProfile;
+use astro::refraction::Atmosphere;
+use crate::config;
+use crate::protocol;
+use crate::protocol::Request;
+use crate::protocol::Response;
 
+mod altitude_chart;
+mod catalogs;
+mod clock;
+mod constellations;
+mod cursor;
+mod ephemeris_tables;
+mod grid;
+mod labels;
+mod legend;
 mod menus;
+mod planner;
+mod queue;
+mod minor_bodies;
+mod satellites;
 mod sphere;
+mod stars;
+mod state;
+mod terrain;
 
+use crate::ui::altitude_chart::AltitudeChartPlugin;
+use crate::ui::catalogs::CatalogsPlugin;
+use crate::ui::clock::ClockPlugin;
+use crate::ui::constellations::ConstellationsPlugin;
+use crate::ui::cursor::CursorPlugin;
+use crate::ui::ephemeris_tables::EphemerisTablesPlugin;
+use crate::ui::grid::GridPlugin;
+use crate::ui::grid::GridStyles;
+use crate::ui::labels::LabelsPlugin;
+use crate::ui::legend::SourceFilter;
 use crate::ui::menus::egui_system;
 use crate::ui::menus::adjust_viewport_to_egui;
+use crate::ui::minor_bodies::MinorBodiesPlugin;
+use crate::ui::planner::PlannerPlugin;
+use crate::ui::satellites::SatellitesPlugin;
 use crate::ui::sphere::SkySpherePlugin;
+use crate::ui::state::UiState;
+use crate::ui::state::save_ui_state;
+use crate::ui::terrain::TerrainPlugin;
 
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
+        let ui_state = UiState::load();
         app
             .add_plugins(DefaultPlugins)
             .add_plugins(EguiPlugin)
             .add_plugins(PanOrbitCameraPlugin)
+            .add_plugins(ClockPlugin)
             .add_plugins(SkySpherePlugin)
+            .add_plugins(GridPlugin)
+            .add_plugins(TerrainPlugin)
+            .add_plugins(CatalogsPlugin)
+            .add_plugins(ConstellationsPlugin)
+            .add_plugins(SatellitesPlugin)
+            .add_plugins(MinorBodiesPlugin)
+            .add_plugins(EphemerisTablesPlugin)
+            .add_plugins(LabelsPlugin)
+            .add_plugins(CursorPlugin)
+            .add_plugins(AltitudeChartPlugin)
+            .add_plugins(PlannerPlugin)
             .insert_resource(ClearColor(Color::rgb(0.1059, 0.1059, 0.1059)))
-            .init_resource::<TelescopeConfig>()
-            .init_resource::<ViewConfig>()
+            .insert_resource(ui_state.telescope.clone())
+            .insert_resource(ui_state.view.clone())
+            .insert_resource(ui_state)
             .init_resource::<OriginalCameraConfig>()
             .init_resource::<OccupiedScreenSpace>()
             .add_systems(Startup, (
@@ -37,7 +97,10 @@ impl Plugin for UiPlugin {
             .add_systems(Update, (
                 egui_system,
                 adjust_viewport_to_egui,
-            ));
+                save_ui_state,
+            ).chain())
+            .add_systems(Update, read_weather_sensor)
+            .add_systems(Update, fetch_site);
     }
 }
 
@@ -47,12 +110,25 @@ struct OccupiedScreenSpace {
     bottom: f32,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
 struct TelescopeConfig {
     serial_path: String,
     sdrpp_url: String,
     latitude: f32,
     longitude: f32,
+    atmosphere: Atmosphere,
+    /// Horizon profile drawn as terrain, which sources rise above and set
+    /// below. Fetched from the daemon, which is the one enforcing it.
+    #[serde(skip)]
+    horizon: HorizonProfile,
+    /// Stops of the azimuth axis, fetched from the daemon.
+    #[serde(skip)]
+    cable_wrap: Option<CableWrap>,
+    /// Closest distance to the Sun at which sources are planned, in degrees.
+    sun_separation: f64,
+    /// Closest distance to the Moon at which sources are planned, in degrees.
+    moon_separation: f64,
 }
 
 impl Default for TelescopeConfig {
@@ -62,15 +138,124 @@ impl Default for TelescopeConfig {
             longitude: -8.74306467245,
             sdrpp_url: "https://localhost:7777".to_string(),
             serial_path: "/dev/sTTY_ACM0".to_string(),
+            atmosphere: Atmosphere::default(),
+            horizon: HorizonProfile::default(),
+            cable_wrap: None,
+            sun_separation: 10.0,
+            moon_separation: 5.0,
+        }
+    }
+}
+
+/// Seconds between two reads of the weather sensor file.
+const WEATHER_PERIOD_SECS: f32 = 10.0;
+
+/// Periodically update the site weather from the configured sensor file.
+fn read_weather_sensor(
+    time: Res<Time>,
+    mut telescope_config: ResMut<TelescopeConfig>,
+    mut countdown: Local<f32>,
+) {
+    *countdown -= time.delta_seconds();
+    if *countdown > 0.0 || telescope_config.atmosphere.sensor_file.is_none() {
+        return;
+    }
+    *countdown = WEATHER_PERIOD_SECS;
+
+    let mut atmosphere = telescope_config.atmosphere.clone();
+    match atmosphere.read_sensor_file() {
+        Ok(()) => {
+            if atmosphere != telescope_config.atmosphere {
+                telescope_config.atmosphere = atmosphere;
+            }
+        },
+        Err(e) => println!("Weather sensor: {}", e),
+    }
+}
+
+/// Seconds between two requests of the site profile to the daemon.
+const SITE_PERIOD_SECS: f32 = 30.0;
+
+/// Request of the site profile being waited for on a thread, so that a slow
+/// daemon never freezes the interface.
+#[derive(Default)]
+struct SiteFetch {
+    pending: Option<mpsc::Receiver<std::io::Result<Response>>>,
+    countdown: f32,
+    /// Last error, logged only once until it changes.
+    error: Option<String>,
+}
+
+/// Periodically fetch the horizon profile and cable wrap from the daemon.
+fn fetch_site(
+    time: Res<Time>,
+    client_config: Res<config::ClientConfig>,
+    mut telescope_config: ResMut<TelescopeConfig>,
+    mut fetch: Local<SiteFetch>,
+) {
+    if let Some(receiver) = &fetch.pending {
+        let result = match receiver.try_recv() {
+            Ok(result) => result.map_err(|e| format!("Could not reach daemon at {}: {}", client_config.remote_url, e)),
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err(String::from("The request to the daemon was lost")),
+        };
+        fetch.pending = None;
+        let error = match result {
+            Ok(Response::Site { horizon, cable_wrap }) => {
+                if horizon != telescope_config.horizon || cable_wrap != telescope_config.cable_wrap {
+                    telescope_config.horizon = horizon;
+                    telescope_config.cable_wrap = cable_wrap;
+                }
+                None
+            },
+            Ok(Response::Error { message }) => Some(format!("The daemon refused: {}", message)),
+            Ok(response) => Some(format!("Unexpected answer from the daemon: {:?}", response)),
+            Err(e) => Some(e),
+        };
+        if let Some(e) = error.as_ref().filter(|e| fetch.error.as_ref() != Some(e)) {
+            println!("Site profile: {}", e);
         }
+        fetch.error = error;
+    }
+
+    fetch.countdown -= time.delta_seconds();
+    if fetch.countdown > 0.0 {
+        return;
     }
+    fetch.countdown = SITE_PERIOD_SECS;
+
+    let (sender, receiver) = mpsc::channel();
+    let remote = client_config.remote_url.clone();
+    std::thread::spawn(move || {
+        let _ = sender.send(protocol::send(&remote, &Request::Site));
+    });
+    fetch.pending = Some(receiver);
 }
 
-#[derive(Resource)]
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
 struct ViewConfig {
     show_azimuthal_grid: bool,
     show_equatorial_grid: bool,
-    time_stopped: bool,
+    show_galactic_grid: bool,
+    show_ecliptic_grid: bool,
+    show_grid_settings: bool,
+    grids: GridStyles,
+    show_galactic_plane: bool,
+    show_ecliptic: bool,
+    show_celestial_equator: bool,
+    show_meridian: bool,
+    show_horizon: bool,
+    show_terrain: bool,
+    show_constellations: bool,
+    show_satellites: bool,
+    show_minor_bodies: bool,
+    show_ephemeris_tables: bool,
+    show_legend: bool,
+    show_altitude_chart: bool,
+    show_planner: bool,
+    show_labels: bool,
+    source_filter: SourceFilter,
 }
 
 impl Default for ViewConfig {
@@ -78,12 +263,31 @@ impl Default for ViewConfig {
         ViewConfig {
             show_azimuthal_grid: true,
             show_equatorial_grid: false,
-            time_stopped: false,
+            show_galactic_grid: false,
+            show_ecliptic_grid: false,
+            show_grid_settings: false,
+            grids: GridStyles::default(),
+            show_galactic_plane: true,
+            show_ecliptic: true,
+            show_celestial_equator: false,
+            show_meridian: false,
+            show_horizon: true,
+            show_terrain: true,
+            show_constellations: false,
+            show_satellites: true,
+            show_minor_bodies: true,
+            show_ephemeris_tables: true,
+            show_legend: false,
+            show_altitude_chart: false,
+            show_planner: false,
+            show_labels: true,
+            source_filter: SourceFilter::default(),
         }
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
 struct OriginalCameraConfig {
     radius: f32,
     pitch: f32,
@@ -102,7 +306,7 @@ impl Default for OriginalCameraConfig {
 
 fn setup_camera(
     mut commands: Commands,
-    original_camera_config: Res<OriginalCameraConfig>,
+    ui_state: Res<UiState>,
 ) {
     commands.spawn((
         Camera3dBundle {
@@ -125,9 +329,9 @@ fn setup_camera(
             //zoom_lower_limit: 1.5,
             zoom_upper_limit: Some(20.0),
             button_orbit: MouseButton::Right,
-            radius: Some(original_camera_config.radius),
-            pitch: Some(original_camera_config.pitch),
-            yaw: Some(original_camera_config.yaw),
+            radius: Some(ui_state.camera.radius),
+            pitch: Some(ui_state.camera.pitch),
+            yaw: Some(ui_state.camera.yaw),
             ..default()
         },
     ));
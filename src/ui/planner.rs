@@ -0,0 +1,434 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui,
+    EguiContexts,
+};
+use chrono::{
+    Datelike,
+    DateTime,
+    Duration,
+    NaiveDate,
+    Timelike,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::ephemeris::Body;
+use astro::observability;
+use astro::observability::Constraints;
+use astro::observability::Window;
+use astro::target::Target;
+use crate::config;
+use crate::protocol;
+use crate::protocol::BlockState;
+use crate::protocol::ObservationBlock;
+use crate::protocol::ObservationMode;
+use crate::protocol::Request;
+use crate::protocol::Response;
+use crate::protocol::StartConstraints;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::altitude_chart::night_of;
+use crate::ui::altitude_chart::night_start;
+use crate::ui::altitude_chart::twilight_color;
+use crate::ui::clock::SimulationClock;
+use crate::ui::stars::format_time;
+
+/// Time between two samples of the targets, in minutes.
+const SAMPLE_MINUTES: i64 = 5;
+
+/// Time cut from both ends of an exported window, in minutes, so that its
+/// block is not refused for the differences between the samples of the
+/// planner and those checked by the daemon.
+const EXPORT_MARGIN_MINUTES: i64 = 2;
+
+/// Longest range that can be planned, in nights.
+const MAX_NIGHTS: i64 = 31;
+
+/// Width of the column of target names, in points.
+const NAME_WIDTH: f32 = 160.0;
+
+/// Height of a row of the chart, in points.
+const ROW_HEIGHT: f32 = 18.0;
+
+pub struct PlannerPlugin;
+
+impl Plugin for PlannerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, planner_window);
+    }
+}
+
+/// Windows of one target over the planned range.
+struct PlanRow {
+    name: String,
+    target: Target,
+    windows: Vec<Window>,
+    /// Whether the windows are sent to the daemon on export.
+    export: bool,
+}
+
+/// Observable windows of every star of the list and every ephemeris table
+/// over a range of nights.
+struct Plan {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    /// Spans of equal twilight, shading the rows.
+    shading: Vec<(DateTime<Utc>, DateTime<Utc>, egui::Color32)>,
+    rows: Vec<PlanRow>,
+    /// Shortest window kept, in minutes.
+    min_minutes: u32,
+    constraints: Constraints,
+}
+
+/// State of the planner window.
+struct Planner {
+    /// First and last night of the range, as local dates of their evenings.
+    from: String,
+    to: String,
+    /// Shortest window kept, in minutes.
+    min_minutes: u32,
+    plan: Option<Plan>,
+    /// Outcome of the last plan or export.
+    message: String,
+}
+
+impl Default for Planner {
+    fn default() -> Self {
+        Planner {
+            from: String::new(),
+            to: String::new(),
+            min_minutes: 30,
+            plan: None,
+            message: String::new(),
+        }
+    }
+}
+
+/// Windows of each star of the list and each ephemeris table, within its
+/// span, from the evening of `from` to the morning after `to`.
+fn plan(
+    client_config: &config::ClientConfig,
+    telescope_config: &TelescopeConfig,
+    from: NaiveDate,
+    to: NaiveDate,
+    min_minutes: u32,
+) -> Plan {
+    let start = night_start(from);
+    let end = night_start(to) + Duration::days(1);
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        start,
+    ).with_atmosphere(&telescope_config.atmosphere)
+    .with_horizon(&telescope_config.horizon);
+    let skies = observability::skies(&sky, start, end, Duration::minutes(SAMPLE_MINUTES));
+    let sun = observability::sample_skies(&skies, &Target::Body { body: Body::Sun });
+    let moon = observability::sample_skies(&skies, &Target::Body { body: Body::Moon });
+    let constraints = Constraints {
+        sun_separation: telescope_config.sun_separation,
+        moon_separation: telescope_config.moon_separation,
+        cable_wrap: telescope_config.cable_wrap.clone(),
+    };
+
+    let mut shading: Vec<(DateTime<Utc>, DateTime<Utc>, egui::Color32)> = Vec::new();
+    for pair in sun.windows(2) {
+        let color = twilight_color(pair[0].altitude);
+        match shading.last_mut() {
+            Some((_, end, last)) if *last == color => *end = pair[1].time,
+            _ => shading.push((pair[0].time, pair[1].time, color)),
+        }
+    }
+
+    let stars = client_config.stars
+        .iter()
+        .map(|star| Target::Catalog {
+            name: star.name.clone(),
+            position: star.position(),
+        });
+    let tables = client_config.ephemeris_tables()
+        .into_iter()
+        .map(|table| Target::Table { table });
+    let rows = stars
+        .chain(tables)
+        .map(|target| {
+            let samples = observability::sample_skies(&skies, &target);
+            let windows = observability::observable_windows(&samples, &sun, &moon, &constraints)
+                .into_iter()
+                .filter_map(|window| clip_to_span(&target, window))
+                .filter(|window| window.duration() >= Duration::minutes(min_minutes as i64))
+                .collect();
+            PlanRow {
+                name: target.name(),
+                target,
+                windows,
+                export: false,
+            }
+        })
+        .collect();
+
+    Plan {
+        start,
+        end,
+        shading,
+        rows,
+        min_minutes,
+        constraints,
+    }
+}
+
+/// Part of `window` within the span of an ephemeris table, since the
+/// position of its object is unknown outside of it.
+fn clip_to_span(target: &Target, window: Window) -> Option<Window> {
+    let Target::Table { table } = target else {
+        return Some(window);
+    };
+    let (first, last) = table.span();
+    let window = Window {
+        start: window.start.max(first),
+        end: window.end.min(last),
+    };
+    (window.start < window.end).then_some(window)
+}
+
+fn format_window(window: &Window) -> String {
+    let minutes = window.duration().num_minutes();
+    format!("{} {} – {} ({}h{:02}m)",
+        window.start.with_timezone(&chrono::Local).format("%a %d"),
+        format_time(Some(window.start)),
+        format_time(Some(window.end)),
+        minutes / 60,
+        minutes % 60,
+    )
+}
+
+/// Queue the windows of the checked targets on the daemon, each as a block
+/// tracking the target until the end of its window. The block may start
+/// late, as long as it still lasts the shortest window planned, and keeps
+/// the distances to the Sun and the Moon that were planned.
+fn export(plan: &Plan, remote: &str) -> String {
+    for row in plan.rows.iter().filter(|row| row.export) {
+        for window in &row.windows {
+            if let Err(e) = row.target.check_interval(window.start, window.end) {
+                return format!("Cannot export {}: {}", row.name, e);
+            }
+        }
+    }
+    let blocks: Vec<ObservationBlock> = plan.rows
+        .iter()
+        .filter(|row| row.export)
+        .flat_map(|row| row.windows.iter().map(|window| {
+            let not_before = window.start + Duration::minutes(EXPORT_MARGIN_MINUTES);
+            let end_by = window.end - Duration::minutes(EXPORT_MARGIN_MINUTES);
+            let not_after = (end_by - Duration::minutes(plan.min_minutes as i64)).max(not_before);
+            ObservationBlock {
+                id: 0,
+                target: row.target.clone(),
+                mode: ObservationMode::Track,
+                duration: (end_by - not_before).num_seconds() as f64,
+                constraints: StartConstraints {
+                    not_before: Some(protocol::format_time(not_before)),
+                    not_after: Some(protocol::format_time(not_after)),
+                    end_by: Some(protocol::format_time(end_by)),
+                    min_elevation: None,
+                    sun_separation: Some(plan.constraints.sun_separation),
+                    moon_separation: Some(plan.constraints.moon_separation),
+                },
+                priority: 0,
+                state: BlockState::Queued,
+            }
+        }))
+        .collect();
+    if blocks.is_empty() {
+        return String::from("Check the targets whose windows to export");
+    }
+    let count = blocks.len();
+    match protocol::send(remote, &Request::QueueAdd { blocks }) {
+        Ok(Response::Queue { .. }) => format!("Queued {} observations on {}", count, remote),
+        Ok(Response::Error { message }) => format!("The daemon refused the plan: {}", message),
+        Ok(response) => format!("Unexpected answer from the daemon: {:?}", response),
+        Err(e) => format!("Could not reach daemon at {}: {}", remote, e),
+    }
+}
+
+/// Hours between two ticks of the time axis, so that their labels fit.
+fn tick_hours(plan: &Plan, width: f32) -> i64 {
+    let hours = (plan.end - plan.start).num_hours().max(1) as f32;
+    [1, 2, 3, 6, 12, 24, 48, 96]
+        .into_iter()
+        .find(|step| width / hours * *step as f32 >= 48.0)
+        .unwrap_or(168)
+}
+
+/// Strip of the chart for one row, or for the time axis when `row` is
+/// `None`.
+fn chart_strip(ui: &mut egui::Ui, plan: &Plan, row: Option<&PlanRow>) -> egui::Response {
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width().max(200.0), ROW_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let rect = response.rect;
+    let span = (plan.end - plan.start).num_seconds() as f32;
+    let x = |time: DateTime<Utc>| rect.left() + rect.width() * (time - plan.start).num_seconds() as f32 / span;
+
+    let step = tick_hours(plan, rect.width());
+    let ticks = (0..=(plan.end - plan.start).num_hours())
+        .map(|hour| plan.start + Duration::hours(hour))
+        .filter(|time| {
+            let local = time.with_timezone(&chrono::Local);
+            let hours = local.hour() as i64 + local.num_days_from_ce() as i64 * 24;
+            hours % step == 0
+        });
+
+    let Some(row) = row else {
+        for time in ticks {
+            let local = time.with_timezone(&chrono::Local);
+            let label = if local.hour() == 0 {
+                local.format("%a %d").to_string()
+            } else {
+                local.format("%H:%M").to_string()
+            };
+            painter.text(egui::pos2(x(time), rect.center().y), egui::Align2::CENTER_CENTER,
+                label, egui::FontId::proportional(11.0), ui.visuals().text_color());
+        }
+        return response;
+    };
+
+    for (start, end, color) in &plan.shading {
+        painter.rect_filled(egui::Rect::from_x_y_ranges(x(*start)..=x(*end), rect.y_range()), 0.0, *color);
+    }
+    for time in ticks {
+        painter.vline(x(time), rect.y_range(), egui::Stroke::new(0.5, egui::Color32::from_gray(80)));
+    }
+    let color = if row.export {
+        egui::Color32::from_rgb(110, 210, 255)
+    } else {
+        egui::Color32::from_rgb(90, 150, 180)
+    };
+    for window in &row.windows {
+        painter.rect_filled(
+            egui::Rect::from_x_y_ranges(x(window.start)..=x(window.end), rect.shrink(3.0).y_range()),
+            2.0,
+            color,
+        );
+    }
+    response
+}
+
+/// Window planning the nights of a date range: when each star of the list and
+/// each tabulated object is observable, as a Gantt chart, and the export of
+/// the plan to the daemon.
+fn planner_window(
+    mut contexts: EguiContexts,
+
+    mut view_config: ResMut<ViewConfig>,
+    mut telescope_config: ResMut<TelescopeConfig>,
+    client_config: Res<config::ClientConfig>,
+    clock: Res<SimulationClock>,
+
+    mut planner: Local<Planner>,
+) {
+    if !view_config.show_planner {
+        return;
+    }
+    if planner.from.is_empty() {
+        planner.from = night_of(clock.time()).format("%Y-%m-%d").to_string();
+        planner.to = planner.from.clone();
+    }
+
+    let mut open = true;
+    // Edit a copy so that the configuration is only marked as changed when
+    // the separations actually change.
+    let mut constraints_config = telescope_config.clone();
+    egui::Window::new("Planner")
+        .open(&mut open)
+        .default_width(720.0)
+        .show(contexts.ctx_mut(), |ui| {
+            let planner = &mut *planner;
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Nights from");
+                ui.add(egui::TextEdit::singleline(&mut planner.from).desired_width(80.0));
+                ui.label("to");
+                ui.add(egui::TextEdit::singleline(&mut planner.to).desired_width(80.0));
+                ui.separator();
+                ui.label("Sun ≥");
+                ui.add(egui::DragValue::new(&mut constraints_config.sun_separation)
+                    .speed(0.5)
+                    .range(0.0..=180.0)
+                    .suffix("°"));
+                ui.label("Moon ≥");
+                ui.add(egui::DragValue::new(&mut constraints_config.moon_separation)
+                    .speed(0.5)
+                    .range(0.0..=180.0)
+                    .suffix("°"));
+                ui.label("Windows ≥");
+                ui.add(egui::DragValue::new(&mut planner.min_minutes)
+                    .speed(1.0)
+                    .range(0..=720)
+                    .suffix(" min"));
+                if ui.button("Plan").clicked() {
+                    let parse = |text: &str| NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d");
+                    planner.message = match (parse(&planner.from), parse(&planner.to)) {
+                        (Ok(from), Ok(to)) if to < from => String::from("The last night is before the first"),
+                        (Ok(from), Ok(to)) if (to - from).num_days() >= MAX_NIGHTS => {
+                            format!("At most {} nights can be planned at once", MAX_NIGHTS)
+                        },
+                        (Ok(from), Ok(to)) => {
+                            let new_plan = plan(&client_config, &constraints_config, from, to, planner.min_minutes);
+                            let count: usize = new_plan.rows.iter().map(|row| row.windows.len()).sum();
+                            planner.plan = Some(new_plan);
+                            format!("{} windows", count)
+                        },
+                        _ => String::from("Dates are written as 2024-03-01"),
+                    };
+                }
+            });
+            ui.label(match &constraints_config.cable_wrap {
+                Some(wrap) => format!("Above the horizon profile and elevation limit, inside the cable wrap ({:.0}° to {:.0}°).",
+                    wrap.min, wrap.max),
+                None => String::from("Above the horizon profile and elevation limit."),
+            });
+
+            if let Some(plan) = &mut planner.plan {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add_space(NAME_WIDTH + ui.spacing().item_spacing.x);
+                    chart_strip(ui, plan, None);
+                });
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for index in 0..plan.rows.len() {
+                        ui.horizontal(|ui| {
+                            ui.allocate_ui_with_layout(
+                                egui::vec2(NAME_WIDTH, ROW_HEIGHT),
+                                egui::Layout::left_to_right(egui::Align::Center),
+                                |ui| {
+                                    let row = &mut plan.rows[index];
+                                    ui.checkbox(&mut row.export, &row.name);
+                                },
+                            );
+                            let row = &plan.rows[index];
+                            let response = chart_strip(ui, plan, Some(row));
+                            if row.windows.is_empty() {
+                                response.on_hover_text("Not observable");
+                            } else {
+                                response.on_hover_text(row.windows.iter().map(format_window).collect::<Vec<_>>().join("\n"));
+                            }
+                        });
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Export checked to daemon").clicked() {
+                        planner.message = export(plan, &client_config.remote_url);
+                    }
+                });
+            }
+            ui.label(&planner.message);
+        });
+
+    if constraints_config != *telescope_config {
+        *telescope_config = constraints_config;
+    }
+    if !open {
+        view_config.show_planner = false;
+    }
+}
@@ -0,0 +1,417 @@
+use std::sync::mpsc;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use bevy_egui::egui;
+
+use astro::target::Target;
+use crate::cli::find_target_with;
+use crate::config;
+use crate::protocol;
+use crate::protocol::BlockState;
+use crate::protocol::ObservationBlock;
+use crate::protocol::ObservationMode;
+use crate::protocol::Request;
+use crate::protocol::Response;
+use crate::protocol::StartConstraints;
+use crate::ui::catalogs::CatalogLayers;
+
+/// Time between two refreshes of the queue from the daemon.
+const REFRESH_PERIOD: Duration = Duration::from_secs(5);
+
+/// The observation queue of the daemon, as last received, and the form
+/// adding or editing a block.
+pub struct QueueTab {
+    enabled: bool,
+    blocks: Vec<ObservationBlock>,
+    /// Answer to the request sent to the daemon, which is waited for on a
+    /// thread so that a slow daemon never freezes the interface.
+    pending: Option<mpsc::Receiver<Result<Response, String>>>,
+    /// Whether the pending request comes from the form.
+    form_sent: bool,
+    refreshed: Option<Instant>,
+    message: String,
+    form: BlockForm,
+}
+
+impl Default for QueueTab {
+    fn default() -> Self {
+        QueueTab {
+            enabled: true,
+            blocks: Vec::new(),
+            pending: None,
+            form_sent: false,
+            refreshed: None,
+            message: String::new(),
+            form: BlockForm::default(),
+        }
+    }
+}
+
+struct BlockForm {
+    /// Id of the block being edited, or `None` for a new block.
+    editing: Option<u64>,
+    /// Target of the edited block, kept as long as its name is not changed
+    /// since it may not be found again by name.
+    target: Option<Target>,
+    name: String,
+    mode: ObservationMode,
+    minutes: f64,
+    priority: i32,
+    not_before: String,
+    not_after: String,
+    end_by: String,
+    min_elevation: Option<f64>,
+    sun_separation: Option<f64>,
+    moon_separation: Option<f64>,
+}
+
+impl Default for BlockForm {
+    fn default() -> Self {
+        BlockForm {
+            editing: None,
+            target: None,
+            name: String::new(),
+            mode: ObservationMode::Track,
+            minutes: 30.0,
+            priority: 0,
+            not_before: String::new(),
+            not_after: String::new(),
+            end_by: String::new(),
+            min_elevation: None,
+            sun_separation: None,
+            moon_separation: None,
+        }
+    }
+}
+
+impl BlockForm {
+    fn edit(block: &ObservationBlock) -> Self {
+        BlockForm {
+            editing: Some(block.id),
+            target: Some(block.target.clone()),
+            name: block.target.name(),
+            mode: block.mode.clone(),
+            minutes: block.duration / 60.0,
+            priority: block.priority,
+            not_before: local_time(&block.constraints.not_before),
+            not_after: local_time(&block.constraints.not_after),
+            end_by: local_time(&block.constraints.end_by),
+            min_elevation: block.constraints.min_elevation,
+            sun_separation: block.constraints.sun_separation,
+            moon_separation: block.constraints.moon_separation,
+        }
+    }
+
+    fn block(&self, client_config: &config::ClientConfig, catalogs: &CatalogLayers) -> Result<ObservationBlock, String> {
+        let target = match &self.target {
+            Some(target) if target.name() == self.name => target.clone(),
+            _ => find_target_with(client_config, self.name.trim(), |name| catalogs.find_source(name))
+                .ok_or(format!("Unknown target {}", self.name.trim()))?,
+        };
+        let time = |text: &str| match text.trim() {
+            "" => Ok(None),
+            text => protocol::parse_time(text).map(|time| Some(protocol::format_time(time))),
+        };
+        Ok(ObservationBlock {
+            id: self.editing.unwrap_or(0),
+            target,
+            mode: self.mode.clone(),
+            duration: self.minutes * 60.0,
+            constraints: StartConstraints {
+                not_before: time(&self.not_before)?,
+                not_after: time(&self.not_after)?,
+                end_by: time(&self.end_by)?,
+                min_elevation: self.min_elevation,
+                sun_separation: self.sun_separation,
+                moon_separation: self.moon_separation,
+            },
+            priority: self.priority,
+            state: BlockState::Queued,
+        })
+    }
+}
+
+/// A time of the protocol as local time, as typed in the form.
+fn local_time(time: &Option<String>) -> String {
+    let Some(time) = time else {
+        return String::new();
+    };
+    protocol::parse_time(time)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or(time.clone())
+}
+
+fn short_time(time: &str) -> String {
+    protocol::parse_time(time)
+        .map(|time| time.with_timezone(&chrono::Local).format("%d %H:%M").to_string())
+        .unwrap_or(time.to_string())
+}
+
+/// Checkbox enabling a limit in degrees, set to `default` when enabled, and
+/// its value.
+fn optional_degrees(ui: &mut egui::Ui, value: &mut Option<f64>, default: f64, range: std::ops::RangeInclusive<f64>) {
+    ui.horizontal(|ui| {
+        let mut limited = value.is_some();
+        if ui.checkbox(&mut limited, "").changed() {
+            *value = limited.then_some(default);
+        }
+        if let Some(value) = value {
+            ui.add(egui::DragValue::new(value).speed(0.5).range(range));
+        }
+    });
+}
+
+fn describe_mode(mode: &ObservationMode) -> String {
+    match mode {
+        ObservationMode::Track => String::from("Track"),
+        ObservationMode::Drift => String::from("Drift"),
+        ObservationMode::Scan { size, step, .. } => format!("Scan {}° by {}°", size, step),
+    }
+}
+
+fn send(tab: &mut QueueTab, remote: &str, request: Request) {
+    let (sender, receiver) = mpsc::channel();
+    let remote = remote.to_string();
+    std::thread::spawn(move || {
+        let result = protocol::send(&remote, &request)
+            .map_err(|e| format!("Could not reach daemon at {}: {}", remote, e));
+        let _ = sender.send(result);
+    });
+    tab.pending = Some(receiver);
+    tab.refreshed = Some(Instant::now());
+}
+
+fn receive(tab: &mut QueueTab) {
+    let Some(receiver) = &tab.pending else {
+        return;
+    };
+    let result = match receiver.try_recv() {
+        Ok(result) => result,
+        Err(mpsc::TryRecvError::Empty) => return,
+        Err(mpsc::TryRecvError::Disconnected) => Err(String::from("The request to the daemon was lost")),
+    };
+    tab.pending = None;
+    let form_sent = std::mem::take(&mut tab.form_sent);
+    match result {
+        Ok(Response::Queue { enabled, blocks }) => {
+            tab.enabled = enabled;
+            tab.blocks = blocks;
+            tab.message.clear();
+            if form_sent {
+                tab.form = BlockForm::default();
+            }
+        },
+        Ok(Response::Error { message }) => tab.message = format!("The daemon refused: {}", message),
+        Ok(response) => tab.message = format!("Unexpected answer from the daemon: {:?}", response),
+        Err(e) => tab.message = e,
+    }
+}
+
+pub fn queue_tab(
+    ui: &mut egui::Ui,
+    tab: &mut QueueTab,
+    client_config: &config::ClientConfig,
+    catalogs: &CatalogLayers,
+) {
+    let remote = &client_config.remote_url;
+    receive(tab);
+    if tab.pending.is_none() && tab.refreshed.is_none_or(|refreshed| refreshed.elapsed() >= REFRESH_PERIOD) {
+        send(tab, remote, Request::QueueList);
+    }
+
+    let mut request = None;
+    ui.horizontal(|ui| {
+        let mut enabled = tab.enabled;
+        if ui.checkbox(&mut enabled, "Run the queue")
+            .on_hover_text("Start the queued blocks whenever the telescope is idle or parked")
+            .changed() {
+            request = Some(Request::SetScheduler { enabled });
+        }
+        if ui.button("Refresh").clicked() {
+            request = Some(Request::QueueList);
+        }
+        if ui.button("Clear finished").clicked() {
+            request = Some(Request::QueueClear);
+        }
+        ui.label(format!("on {}", remote));
+    });
+    if !tab.message.is_empty() {
+        ui.label(&tab.message);
+    }
+
+    let mut edited = None;
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            egui::Grid::new("queue_grid")
+                .num_columns(8)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Id");
+                    ui.strong("Target");
+                    ui.strong("Mode");
+                    ui.strong("Duration");
+                    ui.strong("Start");
+                    ui.strong("Priority");
+                    ui.strong("State");
+                    ui.label("");
+                    ui.end_row();
+
+                    for block in &tab.blocks {
+                        ui.label(block.id.to_string());
+                        ui.label(block.target.name());
+                        ui.label(describe_mode(&block.mode));
+                        ui.label(format!("{:.0} min", block.duration / 60.0));
+                        let constraints = &block.constraints;
+                        let mut start = format!("{} – {}",
+                            constraints.not_before.as_deref().map_or(String::from("now"), short_time),
+                            constraints.not_after.as_deref().map_or(String::from("any time"), short_time));
+                        if let Some(end_by) = &constraints.end_by {
+                            start += &format!(", over by {}", short_time(end_by));
+                        }
+                        if let Some(min_elevation) = constraints.min_elevation {
+                            start += &format!(", above {:.0}°", min_elevation);
+                        }
+                        ui.label(start);
+                        ui.label(block.priority.to_string());
+                        let running = matches!(block.state, BlockState::Running { .. });
+                        match &block.state {
+                            BlockState::Queued => ui.label("Queued"),
+                            BlockState::Running { started } => ui.label(format!("Running since {}", short_time(started))),
+                            BlockState::Done { finished } => ui.label(format!("Done at {}", short_time(finished))),
+                            BlockState::Failed { reason } => ui.label("Failed").on_hover_text(reason),
+                        };
+                        ui.horizontal(|ui| {
+                            ui.add_enabled_ui(!running, |ui| {
+                                let edit = if block.state == BlockState::Queued { "Edit" } else { "Retry" };
+                                if ui.small_button(edit).clicked() {
+                                    edited = Some(BlockForm::edit(block));
+                                }
+                                if ui.small_button("Remove").clicked() {
+                                    request = Some(Request::QueueRemove { id: block.id });
+                                }
+                            });
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+    if tab.blocks.is_empty() {
+        ui.label("No blocks in the queue");
+    }
+    if let Some(form) = edited {
+        tab.form = form;
+    }
+
+    ui.separator();
+    let form = &mut tab.form;
+    match form.editing {
+        Some(id) => ui.strong(format!("Block {}", id)),
+        None => ui.strong("New block"),
+    };
+    egui::Grid::new("queue_form")
+        .num_columns(2)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Target:");
+            ui.add(egui::TextEdit::singleline(&mut form.name).hint_text("star, planet, source, satellite…"));
+            ui.end_row();
+
+            ui.label("Mode:");
+            egui::ComboBox::from_id_salt("queue_mode")
+                .selected_text(describe_mode(&form.mode))
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(form.mode == ObservationMode::Track, "Track").clicked() {
+                        form.mode = ObservationMode::Track;
+                    }
+                    if ui.selectable_label(form.mode == ObservationMode::Drift, "Drift").clicked() {
+                        form.mode = ObservationMode::Drift;
+                    }
+                    let scan = matches!(form.mode, ObservationMode::Scan { .. });
+                    if ui.selectable_label(scan, "Scan").clicked() && !scan {
+                        form.mode = ObservationMode::Scan {
+                            size: 5.0,
+                            step: 1.0,
+                            dwell: 5.0,
+                        };
+                    }
+                });
+            ui.end_row();
+
+            if let ObservationMode::Scan { size, step, dwell } = &mut form.mode {
+                ui.label("Scan size, step (°):");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(size).speed(0.1).range(0.1..=90.0));
+                    ui.add(egui::DragValue::new(step).speed(0.05).range(0.01..=10.0));
+                });
+                ui.end_row();
+                ui.label("Dwell (s):");
+                ui.add(egui::DragValue::new(dwell).speed(0.5).range(0.0..=3600.0));
+                ui.end_row();
+            }
+
+            ui.label("Duration (min):");
+            ui.add(egui::DragValue::new(&mut form.minutes).speed(1.0).range(1.0..=1440.0));
+            ui.end_row();
+
+            ui.label("Priority:");
+            ui.add(egui::DragValue::new(&mut form.priority).speed(0.1));
+            ui.end_row();
+
+            ui.label("Not before:");
+            ui.add(egui::TextEdit::singleline(&mut form.not_before).hint_text("YYYY-MM-DD HH:MM"));
+            ui.end_row();
+
+            ui.label("Not after:");
+            ui.add(egui::TextEdit::singleline(&mut form.not_after).hint_text("YYYY-MM-DD HH:MM"));
+            ui.end_row();
+
+            ui.label("End by:");
+            ui.add(egui::TextEdit::singleline(&mut form.end_by).hint_text("YYYY-MM-DD HH:MM"));
+            ui.end_row();
+
+            ui.label("Min elevation (°):");
+            optional_degrees(ui, &mut form.min_elevation, 20.0, -5.0..=90.0);
+            ui.end_row();
+
+            ui.label("Min Sun distance (°):");
+            optional_degrees(ui, &mut form.sun_separation, 10.0, 0.0..=180.0);
+            ui.end_row();
+
+            ui.label("Min Moon distance (°):");
+            optional_degrees(ui, &mut form.moon_separation, 5.0, 0.0..=180.0);
+            ui.end_row();
+        });
+
+    let mut submitted = false;
+    ui.horizontal(|ui| {
+        let label = if form.editing.is_some() { "Save" } else { "Add" };
+        if ui.button(label).clicked() {
+            submitted = true;
+        }
+        if form.editing.is_some() && ui.button("Cancel").clicked() {
+            *form = BlockForm::default();
+        }
+    });
+    if submitted {
+        match form.block(client_config, catalogs) {
+            Ok(block) => {
+                request = Some(match form.editing {
+                    Some(_) => Request::QueueUpdate { block },
+                    None => Request::QueueAdd {
+                        blocks: vec![block],
+                    },
+                });
+                tab.form_sent = true;
+            },
+            Err(e) => tab.message = e,
+        }
+    }
+
+    if let Some(request) = request {
+        send(tab, remote, request);
+    }
+}
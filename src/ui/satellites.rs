@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use chrono::{
+    DateTime,
+    Duration,
+    Utc,
+};
+
+use astro::satellite::Pass;
+use astro::satellite::Satellite;
+use astro::target::Target;
+use crate::config;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::clock::SimulationClock;
+use crate::ui::labels::AnchorTarget;
+use crate::ui::labels::LabelAnchor;
+use crate::ui::labels::LabelAnchors;
+use crate::ui::labels::PushLabelAnchors;
+use crate::ui::sphere::horizontal_to_world;
+use crate::ui::stars::save_client_config;
+
+/// Seconds between two updates of the satellite positions.
+const UPDATE_PERIOD_SECS: f32 = 1.0;
+
+/// Seconds between two predictions of the passes of the selected satellite.
+const PASS_PERIOD_SECS: i64 = 60;
+
+/// Hours of passes predicted for the selected satellite.
+const PASS_HOURS: i64 = 24;
+
+/// Rows shown at most in the satellite table.
+const MAX_ROWS: usize = 200;
+
+pub struct SatellitesPlugin;
+
+impl Plugin for SatellitesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Satellites>()
+            .add_systems(Update, (
+                load_satellites.run_if(resource_changed::<config::ClientConfig>),
+                update_satellites,
+                draw_satellites.in_set(PushLabelAnchors),
+            ).chain());
+    }
+}
+
+/// Satellites of the configured TLE files and their current positions.
+#[derive(Resource, Default)]
+pub struct Satellites {
+    list: Vec<Satellite>,
+    /// Azimuth and elevation of each satellite of `list`, or `None` when it
+    /// could not be propagated.
+    positions: Vec<Option<(f64, f64)>>,
+    selected: Option<String>,
+    /// Next passes of the selected satellite.
+    passes: Vec<Pass>,
+    /// Azimuth and elevation along the current or next pass of the selected
+    /// satellite.
+    track: Vec<(f64, f64)>,
+    passes_time: Option<DateTime<Utc>>,
+}
+
+impl Satellites {
+    /// The satellite at `index` of the loaded ones, as a target.
+    pub fn target(&self, index: usize) -> Option<Target> {
+        self.list.get(index).map(|satellite| Target::Satellite { satellite: satellite.clone() })
+    }
+}
+
+#[derive(Default)]
+pub struct SatellitesTab {
+    search: String,
+    tle_file: String,
+}
+
+fn load_satellites(
+    client_config: Res<config::ClientConfig>,
+    mut satellites: ResMut<Satellites>,
+) {
+    let list = client_config.satellites();
+    if list != satellites.list {
+        *satellites = Satellites {
+            positions: vec![None; list.len()],
+            list,
+            ..default()
+        };
+    }
+}
+
+fn update_satellites(
+    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    telescope_config: Res<TelescopeConfig>,
+    mut satellites: ResMut<Satellites>,
+    mut countdown: Local<f32>,
+    mut updated: Local<Option<DateTime<Utc>>>,
+) {
+    *countdown -= time.delta_seconds();
+    if *countdown > 0.0 && !clock.moved_since(*updated, UPDATE_PERIOD_SECS) {
+        return;
+    }
+    *countdown = UPDATE_PERIOD_SECS;
+    *updated = Some(clock.time());
+
+    let latitude = telescope_config.latitude as f64;
+    let longitude = telescope_config.longitude as f64;
+    let now = clock.time();
+    satellites.positions = satellites.list
+        .iter()
+        .map(|satellite| {
+            satellite.topocentric(latitude, longitude, now)
+                .ok()
+                .map(|(azimuth, elevation, _)| (azimuth, elevation))
+        })
+        .collect();
+
+    let outdated = satellites.passes_time
+        .is_none_or(|passes_time| (now - passes_time).abs() > Duration::seconds(PASS_PERIOD_SECS));
+    if !outdated {
+        return;
+    }
+    satellites.passes_time = Some(now);
+    satellites.passes.clear();
+    satellites.track.clear();
+    let Some(satellite) = satellites.selected
+        .as_ref()
+        .and_then(|name| satellites.list.iter().find(|satellite| &satellite.name == name))
+        .cloned() else {
+        return;
+    };
+
+    match satellite.passes(latitude, longitude, now, now + Duration::hours(PASS_HOURS), 0.0) {
+        Ok(passes) => {
+            if let Some(pass) = passes.first() {
+                let mut time = pass.rise;
+                while time <= pass.set {
+                    if let Ok((azimuth, elevation, _)) = satellite.topocentric(latitude, longitude, time) {
+                        satellites.track.push((azimuth, elevation));
+                    }
+                    time += Duration::seconds(10);
+                }
+            }
+            satellites.passes = passes;
+        },
+        Err(e) => println!("Could not predict passes of {}: {}", satellite.name, e),
+    }
+}
+
+/// Draw the satellites above the horizon, and the track of the selected one.
+fn draw_satellites(
+    view_config: Res<ViewConfig>,
+    telescope_config: Res<TelescopeConfig>,
+    satellites: Res<Satellites>,
+    mut label_anchors: ResMut<LabelAnchors>,
+    mut gizmos: Gizmos,
+) {
+    if !view_config.show_satellites {
+        return;
+    }
+    for (index, (satellite, position)) in satellites.list.iter().zip(&satellites.positions).enumerate() {
+        let Some((azimuth, elevation)) = *position else {
+            continue;
+        };
+        if elevation < 0.0 {
+            continue;
+        }
+        if view_config.show_terrain && elevation < telescope_config.horizon.terrain_elevation(azimuth) {
+            continue;
+        }
+        let selected = satellites.selected.as_ref() == Some(&satellite.name);
+        let (radius, color) = if selected {
+            (0.03, Color::linear_rgb(1.0, 0.2, 1.0))
+        } else {
+            (0.012, Color::linear_rgb(0.6, 0.9, 0.6))
+        };
+        let world_position = horizontal_to_world(azimuth, elevation);
+        gizmos.sphere(world_position, Quat::IDENTITY, radius, color);
+        label_anchors.push(LabelAnchor {
+            name: satellite.name.clone(),
+            target: AnchorTarget::Satellite(index),
+            info: None,
+            kind: "Satellite",
+            world_position,
+        });
+    }
+    gizmos.linestrip(
+        satellites.track.iter().map(|(azimuth, elevation)| horizontal_to_world(*azimuth, *elevation)),
+        Color::linear_rgb(1.0, 0.2, 1.0),
+    );
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&chrono::Local).format("%d %H:%M:%S").to_string()
+}
+
+pub fn satellites_tab(
+    ui: &mut egui::Ui,
+    tab: &mut SatellitesTab,
+    satellites: &mut Satellites,
+    client_config: &mut ResMut<config::ClientConfig>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("TLE file:");
+        ui.add(egui::TextEdit::singleline(&mut tab.tle_file).hint_text("path"));
+        if ui.button("Add").clicked() && !tab.tle_file.trim().is_empty() {
+            client_config.tle_files.push(tab.tle_file.trim().to_string());
+            tab.tle_file.clear();
+            save_client_config(client_config);
+        }
+    });
+    let mut removed = None;
+    for (index, path) in client_config.tle_files.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(path);
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        client_config.tle_files.remove(index);
+        save_client_config(client_config);
+    }
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Search:");
+        ui.add(egui::TextEdit::singleline(&mut tab.search).hint_text("name"));
+        ui.label(format!("{} satellites", satellites.list.len()));
+    });
+
+    let search = tab.search.to_lowercase();
+    let mut rows: Vec<(usize, f64)> = satellites.list
+        .iter()
+        .enumerate()
+        .filter(|(_, satellite)| satellite.name.to_lowercase().contains(&search))
+        .map(|(index, _)| (index, satellites.positions.get(index).copied().flatten().map_or(-90.0, |(_, el)| el)))
+        .collect();
+    // Highest first, so that the satellites in view come on top.
+    rows.sort_by(|a, b| b.1.total_cmp(&a.1));
+    rows.truncate(MAX_ROWS);
+
+    let mut selected = None;
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            egui::Grid::new("satellites_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Name");
+                    ui.strong("Azimuth");
+                    ui.strong("Elevation");
+                    ui.end_row();
+
+                    for (index, _) in rows {
+                        let satellite = &satellites.list[index];
+                        let is_selected = satellites.selected.as_ref() == Some(&satellite.name);
+                        if ui.selectable_label(is_selected, &satellite.name).clicked() {
+                            selected = Some(satellite.name.clone());
+                        }
+                        match satellites.positions.get(index).copied().flatten() {
+                            Some((azimuth, elevation)) => {
+                                ui.label(format!("{:.2}°", azimuth));
+                                ui.label(format!("{:.2}°", elevation));
+                            },
+                            None => {
+                                ui.label("-");
+                                ui.label("-");
+                            },
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    if let Some(name) = selected {
+        satellites.selected = Some(name);
+        satellites.passes_time = None;
+    }
+
+    let Some(name) = &satellites.selected else {
+        return;
+    };
+    ui.separator();
+    ui.strong(format!("Passes of {} in the next {} hours", name, PASS_HOURS));
+    egui::Grid::new("passes_grid")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Rise (AOS)");
+            ui.strong("Culmination");
+            ui.strong("Max elevation");
+            ui.strong("Set (LOS)");
+            ui.end_row();
+            for pass in &satellites.passes {
+                ui.label(format_time(pass.rise));
+                ui.label(format_time(pass.culmination));
+                ui.label(format!("{:.1}°", pass.max_elevation));
+                ui.label(format_time(pass.set));
+                ui.end_row();
+            }
+        });
+}
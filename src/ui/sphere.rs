@@ -6,17 +6,28 @@ use bevy::{
     },
     window::PrimaryWindow,
 };
+use bevy_egui::EguiContexts;
 use bevy_panorbit_camera::{
     PanOrbitCamera,
 };
 use chrono::{
-    Timelike,
-    Datelike,
+    DateTime,
+    Utc,
 };
-
+use astro::LocalSky;
+use astro::astrometry::ApparentFrame;
+use astro::catalog::SourceInfo;
+use astro::ephemeris::Body;
+use astro::target::Target;
 use crate::config;
 use crate::ui::TelescopeConfig;
 use crate::ui::ViewConfig;
+use crate::ui::altitude_chart::SelectedTarget;
+use crate::ui::clock::EPOCH_PERIOD_SECS;
+use crate::ui::clock::SimulationClock;
+use crate::ui::grid::GridFrame;
+use crate::ui::legend::marker_color;
+use crate::ui::legend::marker_scale;
 
 pub struct SkySpherePlugin;
 
@@ -25,14 +36,21 @@ impl Plugin for SkySpherePlugin {
         app
             .add_systems(Startup, (
                 setup_sky_sphere,
-                setup_default_stars,
                 setup_line,
-                setup_telescope_floor,
+                setup_reference_lines,
+                setup_bodies,
             ))
             .add_systems(Update, (
                 handle_star_clicks,
-                handle_grid_visibility,
                 (
+                    rebuild_precessed_lines,
+                    handle_reference_line_visibility,
+                ).chain(),
+                (
+                    sync_stars.run_if(resource_changed::<config::ClientConfig>),
+                    apply_source_filter.run_if(
+                        resource_changed::<ViewConfig>.or_else(resource_changed::<config::ClientConfig>)
+                    ),
                     handle_sky_rotation,
                     handle_stars_size,
                 ).chain()
@@ -70,31 +88,18 @@ fn setup_line(
     ));
 }
 
-fn setup_telescope_floor(
+/// Spawn the marks of the north celestial pole and of the equinox, drawn
+/// with the equatorial grid.
+fn setup_sky_sphere(
     mut commands: Commands,
+
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let n_vertices = 24;
-    let mut positions = Vec::new();
-    let radius = 0.75;
-    for j in 0..n_vertices {
-        positions.push([0.0, 0.0, 0.0]);
-
-        let theta = 2.0 * std::f32::consts::PI * j as f32 / n_vertices as f32;
-        positions.push([
-            radius * theta.cos(),
-            0.0,
-            radius * theta.sin(),
-        ]);
-
-        let theta = 2.0 * std::f32::consts::PI * (j + 1) as f32 / n_vertices as f32;
-        positions.push([
-            radius * theta.cos(),
-            0.0,
-            radius * theta.sin(),
-        ]);
-    }
+    let positions = Vec::from([
+        [0.0, 1.0, 0.0],
+        [0.0, 1.25, 0.0],
+    ]);
     let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
@@ -103,7 +108,7 @@ fn setup_telescope_floor(
         PbrBundle {
             mesh: meshes.add(mesh),
             material: materials.add(StandardMaterial {
-                base_color: Color::linear_rgba(0.0, 1.0, 0.0, 0.5).into(),
+                base_color: Color::linear_rgb(0.7, 0.7, 1.0),
                 emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
                 unlit: true,
                 ..default()
@@ -111,195 +116,166 @@ fn setup_telescope_floor(
             ..default()
         },
         Rotator,
+        Sky,
+        GridFrame::Equatorial,
+    ));
+
+    let positions = Vec::from([
+        [-1.0, 0.0, 0.0],
+        [-1.25, 0.0, 0.0],
+    ]);
+    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                base_color: Color::linear_rgb(0.7, 0.7, 1.0),
+                emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        },
+        Rotator,
+        Sky,
+        GridFrame::Equatorial,
     ));
 }
 
-fn setup_sky_sphere(
-    mut commands: Commands,
+/// Line through the given points of the sky sphere.
+pub fn sky_curve(positions: Vec<[f32; 3]>) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh
+}
 
+/// Spawn the galactic equator, a marker at the galactic centre and the
+/// ecliptic, turning with the sky, again whenever the clock moves to another
+/// epoch.
+#[allow(clippy::type_complexity)]
+fn rebuild_precessed_lines(
+    clock: Res<SimulationClock>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    let n_vertices = 100;
-    let n_circles = 36;
-
-    for circle in 0..=n_circles/2 {
-        let mut positions = Vec::new();
-        for vertex in 0..n_vertices {
-            let phi = circle as f32 / n_circles as f32 * std::f32::consts::PI * 2.0;
-            let theta = (vertex as f32 / n_vertices as f32 * 2.0 - 1.0) * std::f32::consts::PI;
-            positions.push([
-                phi.sin() * theta.cos(),
-                phi.cos(),
-                phi.sin() * theta.sin(),
-            ]);
-        }
-        positions.push(positions[0]);
-        let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
-        commands.spawn((
-            PbrBundle {
-                mesh: meshes.add(mesh),
-                material: materials.add(StandardMaterial {
-                    base_color: Color::linear_rgba(0.175, 0.175, 0.25, 0.1),
-                    emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
-                    unlit: true,
-                    ..default()
-                }),
-                ..default()
-            },
-            Rotator,
-            Sky,
-            EquatorialGrid,
-        ));
-    }
 
-    for circle in 0..=n_circles/2 {
-        let mut positions = Vec::new();
-        for vertex in 0..n_vertices {
-            let phi = circle as f32 / n_circles as f32 * std::f32::consts::PI * 2.0;
-            let theta = (vertex as f32 / n_vertices as f32 * 2.0 - 1.0) * std::f32::consts::PI;
-            positions.push([
-                theta.sin() * phi.sin(),
-                theta.cos(),
-                theta.sin() * phi.cos(),
-            ]);
-        }
-        positions.push(positions[0]);
-        let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
-        commands.spawn((
-            PbrBundle {
-                mesh: meshes.add(mesh),
-                material: materials.add(StandardMaterial {
-                    base_color: Color::linear_rgb(0.175, 0.175, 0.25),
-                    emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
-                    unlit: true,
-                    ..default()
-                }),
-                ..default()
-            },
-            Rotator,
-            Sky,
-            EquatorialGrid,
-        ));
-    }
+    line_query: Query<(Entity, &ReferenceLine)>,
+    sky_query: Query<&Transform, (With<Sky>, Without<ReferenceLine>, Without<Star>)>,
 
-    for circle in 0..=n_circles/2 {
-        let mut positions = Vec::new();
-        for vertex in 0..n_vertices {
-            let phi = circle as f32 / n_circles as f32 * std::f32::consts::PI * 2.0;
-            let theta = (vertex as f32 / n_vertices as f32 * 2.0 - 1.0) * std::f32::consts::PI;
-            positions.push([
-                phi.sin() * theta.cos(),
-                phi.cos(),
-                phi.sin() * theta.sin(),
-            ]);
+    mut commands: Commands,
+    mut built: Local<Option<DateTime<Utc>>>,
+) {
+    if !clock.moved_since(*built, EPOCH_PERIOD_SECS) {
+        return;
+    }
+    for (entity, line) in &line_query {
+        if matches!(line, ReferenceLine::GalacticPlane | ReferenceLine::Ecliptic) {
+            commands.entity(entity).despawn_recursive();
         }
-        positions.push(positions[0]);
-        let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
-        commands.spawn((
-            PbrBundle {
-                mesh: meshes.add(mesh),
-                material: materials.add(StandardMaterial {
-                    base_color: Color::linear_rgb(0.8431372549, 0.6, 0.1294117647),
-                    emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
-                    unlit: true,
-                    ..default()
-                }),
-                ..default()
-            },
-            Rotator,
-            AzimuthalGrid,
-        ));
     }
 
-    for circle in 0..=n_circles/2 {
-        let mut positions = Vec::new();
-        for vertex in 0..n_vertices {
-            let phi = circle as f32 / n_circles as f32 * std::f32::consts::PI * 2.0;
-            let theta = (vertex as f32 / n_vertices as f32 * 2.0 - 1.0) * std::f32::consts::PI;
-            positions.push([
-                theta.sin() * phi.sin(),
-                theta.cos(),
-                theta.sin() * phi.cos(),
-            ]);
-        }
-        positions.push(positions[0]);
-        let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    let frame = ApparentFrame::new(astro::time::julian_date_tt(clock.time()));
+    let sky_rotation = sky_query.iter().next().map_or(Quat::IDENTITY, |transform| transform.rotation);
+    let galactic_to_sky = |longitude: f64, latitude: f64| -> [f32; 3] {
+        GridFrame::Galactic.local_point(longitude, latitude, &frame).to_array()
+    };
+    let n_vertices = 100;
+    let angles = || (0..=n_vertices).map(|vertex| 360.0 * vertex as f64 / n_vertices as f64);
+
+    let plane_color = Color::linear_rgb(0.6, 0.45, 0.9);
+    let lines = [
+        (ReferenceLine::GalacticPlane, plane_color, angles()
+            .map(|longitude| galactic_to_sky(longitude, 0.0))
+            .collect::<Vec<_>>()),
+        (ReferenceLine::Ecliptic, Color::linear_rgb(0.9, 0.8, 0.2), angles()
+            .map(|longitude| GridFrame::Ecliptic.local_point(longitude, 0.0, &frame).to_array())
+            .collect()),
+    ];
+    for (line, color, positions) in lines {
         commands.spawn((
             PbrBundle {
-                mesh: meshes.add(mesh),
+                mesh: meshes.add(sky_curve(positions)),
                 material: materials.add(StandardMaterial {
-                    base_color: Color::linear_rgb(0.8431372549, 0.6, 0.1294117647),
-                    emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
+                    base_color: color,
                     unlit: true,
                     ..default()
                 }),
+                transform: Transform::from_rotation(sky_rotation),
                 ..default()
             },
             Rotator,
-            AzimuthalGrid,
+            Sky,
+            line,
         ));
     }
-
-    let positions = Vec::from([
-        [0.0, 1.0, 0.0],
-        [0.0, 1.25, 0.0],
-    ]);
-    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
     commands.spawn((
         PbrBundle {
-            mesh: meshes.add(mesh),
+            // The transform of the sky entities is overwritten as the sky
+            // turns, so the marker is moved in its mesh.
+            mesh: meshes.add(Mesh::from(bevy::math::primitives::Sphere {
+                radius: 0.03,
+            }).translated_by(Vec3::from_array(galactic_to_sky(0.0, 0.0)))),
             material: materials.add(StandardMaterial {
-                base_color: Color::linear_rgb(0.7, 0.7, 1.0),
-                emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
+                base_color: plane_color,
+                emissive: plane_color.into(),
                 unlit: true,
                 ..default()
             }),
+            transform: Transform::from_rotation(sky_rotation),
             ..default()
         },
         Rotator,
         Sky,
-        EquatorialGrid,
+        ReferenceLine::GalacticPlane,
     ));
+    *built = Some(clock.time());
+}
 
-    let positions = Vec::from([
-        [-1.0, 0.0, 0.0],
-        [-1.25, 0.0, 0.0],
-    ]);
-    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip, bevy::render::render_asset::RenderAssetUsages::default());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[1.0, 0.0, 0.0]; positions.len()]);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(mesh),
+/// Spawn the celestial equator, which turns with the sky, and the local
+/// meridian and the horizon, which stay fixed.
+fn setup_reference_lines(
+    mut commands: Commands,
+
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let n_vertices = 100;
+    let angles = || (0..=n_vertices).map(|vertex| 360.0 * vertex as f64 / n_vertices as f64);
+
+    let lines = [
+        (ReferenceLine::Equator, Color::linear_rgb(0.3, 0.6, 1.0), angles()
+            .map(|ra| equatorial_to_sky(ra, 0.0).to_array())
+            .collect::<Vec<_>>()),
+        // The meridian runs from the north point through the zenith to the
+        // south point and on below the horizon.
+        (ReferenceLine::Meridian, Color::linear_rgb(0.9, 0.3, 0.3), angles()
+            .map(|angle| horizontal_to_world(0.0, angle).to_array())
+            .collect()),
+        (ReferenceLine::Horizon, Color::linear_rgb(0.3, 0.9, 0.4), angles()
+            .map(|azimuth| horizontal_to_world(azimuth, 0.0).to_array())
+            .collect()),
+    ];
+    for (line, color, positions) in lines {
+        let bundle = PbrBundle {
+            mesh: meshes.add(sky_curve(positions)),
             material: materials.add(StandardMaterial {
-                base_color: Color::linear_rgb(0.7, 0.7, 1.0),
-                emissive: Color::linear_rgb(0.2, 0.2, 0.5).into(),
+                base_color: color,
                 unlit: true,
                 ..default()
             }),
             ..default()
-        },
-        Rotator,
-        Sky,
-        EquatorialGrid,
-    ));
+        };
+        match line {
+            ReferenceLine::Ecliptic | ReferenceLine::Equator | ReferenceLine::GalacticPlane => {
+                commands.spawn((bundle, line, Rotator, Sky))
+            },
+            ReferenceLine::Meridian | ReferenceLine::Horizon => commands.spawn((bundle, line, Rotator)),
+        };
+    }
 }
 
 #[derive(Resource)]
@@ -315,8 +291,13 @@ impl Default for TimeTracker {
     }
 }
 
+/// Select the clicked marker, and center the camera on it on a double
+/// click.
+#[allow(clippy::too_many_arguments)]
 fn handle_star_clicks(
+    mut contexts: EguiContexts,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
+    mut selected: ResMut<SelectedTarget>,
 
     mut panorbit_camera_query: Query<&mut PanOrbitCamera>,
     windows: Query<&Window, With<PrimaryWindow>>,
@@ -325,27 +306,25 @@ fn handle_star_clicks(
 
     mut double_click_time: Local<TimeTracker>,
 ) {
-    if mouse_button_input.just_pressed(MouseButton::Left) {
+    if mouse_button_input.just_pressed(MouseButton::Left) && !contexts.ctx_mut().is_pointer_over_area() {
         let time_since_last_click = double_click_time.start.elapsed().as_millis();
         double_click_time.start = bevy::utils::Instant::now();
 
         let window = windows.single();
-        if let Some(cursor_position) = window.cursor_position() {
-            let (camera, camera_transform) = camera.single();
-
-            if let Some(ray) = camera.viewport_to_world(camera_transform, cursor_position) {
-                for (_entity, transform, star) in star_query.iter() {
-                    let star_pos = transform.translation();
-                    let distance = ray.direction.cross(star_pos - ray.origin).length();
-
-                    if distance < 0.05 {
-                        println!("Star {} {} {}", star.name, star.right_ascension, star.declination);
-                        if time_since_last_click < 200 {
-                            if let Ok(mut panorbit_camera) = panorbit_camera_query.get_single_mut() {
-                                let position = transform.translation();
-                                panorbit_camera.target_yaw = position.x.atan2(position.z);
-                                panorbit_camera.target_pitch = position.y.asin();
-                            }
+        let (camera, camera_transform) = camera.single();
+        if let Some(ray) = cursor_ray(window, camera, camera_transform) {
+            for (_entity, transform, star) in star_query.iter() {
+                let star_pos = transform.translation();
+                let distance = ray.direction.cross(star_pos - ray.origin).length();
+
+                if distance < 0.05 {
+                    selected.name = star.name.clone();
+                    selected.target = Some(star.target.clone());
+                    if time_since_last_click < 200 {
+                        if let Ok(mut panorbit_camera) = panorbit_camera_query.get_single_mut() {
+                            let position = transform.translation();
+                            panorbit_camera.target_yaw = position.x.atan2(position.z);
+                            panorbit_camera.target_pitch = position.y.asin();
                         }
                     }
                 }
@@ -354,22 +333,20 @@ fn handle_star_clicks(
     }
 }
 
-fn handle_grid_visibility(
+fn handle_reference_line_visibility(
     view_config: Res<ViewConfig>,
 
-    mut equatorial_grid_query: Query<&mut Visibility, (With<EquatorialGrid>, Without<AzimuthalGrid>)>,
-    mut azimuthal_grid_query: Query<&mut Visibility, (With<AzimuthalGrid>, Without<EquatorialGrid>)>,
+    mut line_query: Query<(&mut Visibility, &ReferenceLine)>,
 ) {
-    for mut visibility in &mut equatorial_grid_query {
-        *visibility = if view_config.show_equatorial_grid {
-            Visibility::Visible
-        } else {
-            Visibility::Hidden
-        }
-    }
-
-    for mut visibility in &mut azimuthal_grid_query {
-        *visibility = if view_config.show_azimuthal_grid {
+    for (mut visibility, line) in &mut line_query {
+        let shown = match line {
+            ReferenceLine::Ecliptic => view_config.show_ecliptic,
+            ReferenceLine::Equator => view_config.show_celestial_equator,
+            ReferenceLine::Meridian => view_config.show_meridian,
+            ReferenceLine::Horizon => view_config.show_horizon,
+            ReferenceLine::GalacticPlane => view_config.show_galactic_plane,
+        };
+        *visibility = if shown {
             Visibility::Visible
         } else {
             Visibility::Hidden
@@ -380,6 +357,11 @@ fn handle_grid_visibility(
 #[derive(PartialEq)]
 pub enum TopBarTabOption {
     Stars,
+    Catalogs,
+    Satellites,
+    MinorBodies,
+    EphemerisTables,
+    Queue,
     TelescopeControl,
 }
 
@@ -396,11 +378,23 @@ impl Default for TopBarTab {
     }
 }
 
+/// Scale the markers with the zoom, and shrink the ones behind the terrain
+/// to nothing.
 fn handle_stars_size(
+    telescope_config: Res<TelescopeConfig>,
+    view_config: Res<ViewConfig>,
+
     mut star_query: Query<&mut Transform, With<Star>>,
     camera_query: Query<&PanOrbitCamera>,
 ) {
     for mut star_transform in star_query.iter_mut() {
+        if view_config.show_terrain {
+            let (azimuth, altitude) = world_to_horizontal(star_transform.translation);
+            if altitude < telescope_config.horizon.terrain_elevation(azimuth) {
+                star_transform.scale = Vec3::ZERO;
+                continue;
+            }
+        }
         if let Ok(camera) = camera_query.get_single() {
             let radius = 1.5*camera.radius.unwrap()/5.0;
             if radius < 1.5 {
@@ -413,115 +407,169 @@ fn handle_stars_size(
 }
 
 #[derive(Component)]
-struct Sky;
+pub struct Sky;
 
 #[derive(Component)]
-struct Rotator;
+pub struct Rotator;
+
+
+#[derive(Component, Clone, Copy)]
+enum ReferenceLine {
+    Ecliptic,
+    /// The celestial equator.
+    Equator,
+    /// The local meridian.
+    Meridian,
+    Horizon,
+    /// The galactic equator and the marker of the galactic centre.
+    GalacticPlane,
+}
 
+/// Marker on the sky sphere. Stars come from the client configuration,
+/// solar system bodies are always shown.
 #[derive(Component)]
-struct AzimuthalGrid;
+pub struct Star {
+    pub name: String,
+    pub target: Target,
+    pub info: SourceInfo,
+}
 
 #[derive(Component)]
-struct EquatorialGrid;
+pub struct SolarSystemBody;
+
+/// Point of the unit sky sphere at the given azimuth and altitude, in
+/// degrees.
+pub fn horizontal_to_world(azimuth: f64, altitude: f64) -> Vec3 {
+    let rotation_z = Quat::from_rotation_z(-altitude.to_radians() as f32);
+    let rotation_y = Quat::from_rotation_y(-azimuth.to_radians() as f32);
+    rotation_y * rotation_z * Vec3::new(-1.0, 0.0, 0.0)
+}
 
-#[derive(Component)]
-struct Star {
-    name: String,
-    right_ascension: f32,
-    declination: f32,
+/// Point of the unit sky sphere, in the frame of the entities that turn
+/// with the sky, at the given right ascension and declination of date, in
+/// degrees.
+pub fn equatorial_to_sky(ra: f64, dec: f64) -> Vec3 {
+    let (sin_ra, cos_ra) = ra.to_radians().sin_cos();
+    let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+    Vec3::new((-cos_dec * cos_ra) as f32, sin_dec as f32, (cos_dec * sin_ra) as f32)
+}
+
+/// Azimuth and altitude, in degrees, of a point of the unit sky sphere.
+pub fn world_to_horizontal(position: Vec3) -> (f64, f64) {
+    let azimuth = (-position.z).atan2(-position.x).to_degrees() as f64;
+    let altitude = position.y.clamp(-1.0, 1.0).asin().to_degrees() as f64;
+    (azimuth.rem_euclid(360.0), altitude)
+}
+
+/// Ray from the camera through the mouse cursor, if it is over the window.
+pub fn cursor_ray(window: &Window, camera: &Camera, camera_transform: &GlobalTransform) -> Option<Ray3d> {
+    let cursor_position = window.cursor_position()?;
+    camera.viewport_to_world(camera_transform, cursor_position)
+}
+
+/// Point where a ray first meets the unit sky sphere ahead of its origin,
+/// which may be inside the sphere, or `None` if it misses it.
+pub fn ray_sphere_intersection(ray: Ray3d) -> Option<Vec3> {
+    let b = ray.origin.dot(*ray.direction);
+    let c = ray.origin.length_squared() - 1.0;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [-b - root, -b + root]
+        .into_iter()
+        .find(|distance| *distance >= 0.0)
+        .map(|distance| ray.get_point(distance))
 }
 
-/// Rotate sky to match current location and time.
+/// Rotate sky to match current location and the time of the simulation
+/// clock.
 fn handle_sky_rotation(
     telescope_config: Res<TelescopeConfig>,
-    view_config: Res<ViewConfig>,
+    clock: Res<SimulationClock>,
 
     mut sky_sphere_query: Query<&mut Transform, (With<Sky>, Without<Star>)>,
     mut sky_star_query: Query<(&mut Transform, &Star), With<Star>>,
 ) {
-    if !view_config.time_stopped {
-        let now = chrono::prelude::Local::now();
-        let astro_time = astronav::time::AstroTime {
-            day: now.day() as u8,
-            month: now.month() as u8,
-            year: now.year() as u16,
-            hour: now.hour() as u8,
-            min: now.minute() as u8,
-            sec: now.second() as u8,
-            timezone: now.offset().local_minus_utc() as f32 / 3600.0,
-        };
-        let lmst = astro_time.lmst_in_degrees(telescope_config.longitude as f64);
-        let generic_alt_az = astronav::coords::star::AltAzBuilder::new()
-                .lat(telescope_config.latitude as f64)
-                .lmst(lmst);
-
-        let sky_alt_az = generic_alt_az.clone()
-                .dec(90.0f64)
-                .ra(0.0f64)
-                .seal()
-                .build();
-        let sky_alt_az_2 = generic_alt_az.clone()
-                .dec(0.0f64)
-                .ra(0.0f64)
-                .seal()
-                .build();
-
-        for mut transform in &mut sky_sphere_query {
-            let model_fix = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
-            let mut new_transform = Transform::from_xyz(0.0, 0.0, 0.0);
-
-            let alt_rad = -sky_alt_az.get_altitude().to_radians() as f32;
-            let rotation_z = Quat::from_rotation_z(alt_rad);
-
-            let az_rad  = -sky_alt_az_2.get_azimuth().to_radians() as f32;
-            let rotation_y = Quat::from_rotation_y(az_rad);
-
-            new_transform.rotate_around(Vec3::ZERO,
-                                        model_fix * rotation_z * rotation_y);
-
-            *transform = new_transform;
-        }
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        clock.time(),
+    );
+    let (_, pole_altitude) = sky.apparent_to_horizontal(0.0, 90.0);
 
+    for mut transform in &mut sky_sphere_query {
+        let model_fix = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let mut new_transform = Transform::from_xyz(0.0, 0.0, 0.0);
 
-        for (mut transform, star) in &mut sky_star_query {
-            let alt_az = generic_alt_az.clone()
-                .dec(star.declination as f64)
-                .ra(star.right_ascension as f64)
-                .seal()
-                .build();
+        let alt_rad = -pole_altitude.to_radians() as f32;
+        let rotation_z = Quat::from_rotation_z(alt_rad);
 
-            let mut new_transform = Transform::from_xyz(-1.0, 0.0, 0.0);
+        // Turn the equinox, at `equatorial_to_sky(0.0, 0.0)`, to its hour
+        // angle, the local sidereal time.
+        let hour_angle_rad = (std::f64::consts::PI - sky.local_sidereal_time.to_radians()) as f32;
+        let rotation_y = Quat::from_rotation_y(hour_angle_rad);
 
-            let alt_rad = -alt_az.get_altitude().to_radians() as f32;
-            let rotation_z = Quat::from_rotation_z(alt_rad);
+        new_transform.rotate_around(Vec3::ZERO,
+                                    model_fix * rotation_z * rotation_y);
 
-            let az_rad  = -alt_az.get_azimuth().to_radians() as f32;
-            let rotation_y = Quat::from_rotation_y(az_rad);
+        *transform = new_transform;
+    }
 
-            new_transform.rotate_around(Vec3::ZERO, rotation_y * rotation_z);
 
-            *transform = new_transform;
-        }
+    // The grids follow the geometric sky, only the stars are refracted.
+    let sky = sky.with_atmosphere(&telescope_config.atmosphere);
+    for (mut transform, star) in &mut sky_star_query {
+        let (azimuth, altitude) = sky.target_horizontal(&star.target);
+        *transform = Transform::from_translation(horizontal_to_world(azimuth, altitude));
     }
 }
 
-/// Add default stars to the system, so the software can display something.
-fn setup_default_stars(
+/// Spawn, update and despawn star entities so they match the client
+/// configuration.
+fn sync_stars(
     client_config: Res<config::ClientConfig>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 
+    mut star_query: Query<(Entity, &mut Star), Without<SolarSystemBody>>,
+
     mut commands: Commands,
 ) {
-    for star in &client_config.stars {
+    let mut existing = Vec::new();
+    for (entity, mut star) in star_query.iter_mut() {
+        match client_config.stars.iter().find(|s| s.name == star.name) {
+            Some(config_star) => {
+                let target = Target::Catalog {
+                    name: config_star.name.clone(),
+                    position: config_star.position(),
+                };
+                // The marker size and colour come from the source metadata,
+                // so a star whose metadata changed is spawned again.
+                if star.info != config_star.info {
+                    commands.entity(entity).despawn_recursive();
+                    continue;
+                }
+                if star.target != target {
+                    star.target = target;
+                }
+                existing.push(star.name.clone());
+            },
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+
+    for star in client_config.stars.iter().filter(|s| !existing.contains(&s.name)) {
+        let color = marker_color(&star.info, (1.0, 0.7, 0.5));
         commands.spawn((
             PbrBundle {
                 mesh: meshes.add(bevy::math::primitives::Sphere {
-                    radius: 0.05,
+                    radius: 0.05 * marker_scale(&star.info),
                 }),
                 material: materials.add(StandardMaterial {
-                    base_color: Color::linear_rgb(1.0, 0.7, 0.5),
-                    emissive: Color::linear_rgba(1.0, 1.0, 0.0, 1.0).into(),
+                    base_color: color,
+                    emissive: color.into(),
                     unlit: true,
                     ..default()
                 }),
@@ -530,9 +578,77 @@ fn setup_default_stars(
             },
             Star {
                 name: star.name.clone(),
-                right_ascension: star.ra,
-                declination: star.dec,
+                target: Target::Catalog {
+                    name: star.name.clone(),
+                    position: star.position(),
+                },
+                info: star.info,
+            },
+            Rotator,
+            Sky,
+        ));
+    }
+}
+
+/// Hide the stars left out by the filters of the legend. Solar system bodies
+/// are always shown.
+fn apply_source_filter(
+    view_config: Res<ViewConfig>,
+    mut star_query: Query<(&Star, &mut Visibility), Without<SolarSystemBody>>,
+) {
+    for (star, mut visibility) in &mut star_query {
+        let shown = view_config.source_filter.accepts(&star.info);
+        let wanted = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+/// Spawn a marker for the Sun, the Moon and each planet, with a size and
+/// colour telling them apart from the stars.
+fn setup_bodies(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+
+    mut commands: Commands,
+) {
+    for body in Body::ALL {
+        let (radius, color) = match body {
+            Body::Sun => (0.09, Color::linear_rgb(1.0, 0.9, 0.3)),
+            Body::Moon => (0.08, Color::linear_rgb(0.85, 0.85, 0.8)),
+            Body::Mercury => (0.035, Color::linear_rgb(0.6, 0.55, 0.5)),
+            Body::Venus => (0.04, Color::linear_rgb(1.0, 0.95, 0.75)),
+            Body::Mars => (0.04, Color::linear_rgb(0.9, 0.3, 0.15)),
+            Body::Jupiter => (0.045, Color::linear_rgb(0.85, 0.7, 0.5)),
+            Body::Saturn => (0.045, Color::linear_rgb(0.9, 0.8, 0.45)),
+            Body::Uranus => (0.035, Color::linear_rgb(0.55, 0.85, 0.9)),
+            Body::Neptune => (0.035, Color::linear_rgb(0.3, 0.45, 1.0)),
+        };
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(bevy::math::primitives::Sphere {
+                    radius,
+                }),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    emissive: color.into(),
+                    unlit: true,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(1.0, 0.0, 0.0),
+                ..default()
+            },
+            Star {
+                name: String::from(body.name()),
+                target: Target::Body { body },
+                info: SourceInfo::default(),
             },
+            SolarSystemBody,
             Rotator,
             Sky,
         ));
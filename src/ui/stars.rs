@@ -0,0 +1,628 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use astro::LocalSky;
+use astro::RiseSet;
+use astro::angle;
+use astro::angle::Epoch;
+use astro::astrometry::to_j2000;
+use astro::catalog::SourceInfo;
+use astro::catalog::SourceType;
+use astro::galactic;
+use crate::config;
+use crate::ui::TelescopeConfig;
+
+/// Seconds of simulated time after which the positions of the table are
+/// computed again.
+const ROWS_PERIOD_SECS: i64 = 5;
+
+#[derive(PartialEq, Clone, Copy, Default)]
+enum SortColumn {
+    #[default]
+    Name,
+    RightAscension,
+    Declination,
+    Altitude,
+    Azimuth,
+    Rise,
+    Transit,
+    Set,
+}
+
+/// Values of the add/edit star form.
+struct StarForm {
+    /// Name of the star being edited, or `None` when adding a new one.
+    editing: Option<String>,
+    name: String,
+    /// Right ascension as typed, in degrees or sexagesimal hours.
+    ra: String,
+    /// Declination as typed, in degrees or sexagesimal degrees.
+    dec: String,
+    /// Whether `ra` and `dec` hold a galactic longitude and latitude, which
+    /// are converted to J2000.0 coordinates when the star is saved.
+    galactic: bool,
+    epoch: String,
+    pm_ra: f64,
+    pm_dec: f64,
+    parallax: f64,
+    /// Flux density, reference frequency, spectral index and magnitude as
+    /// typed, empty when unknown.
+    flux: String,
+    frequency: String,
+    spectral_index: String,
+    magnitude: String,
+    kind: SourceType,
+    error: Option<String>,
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map_or(String::new(), |value| value.to_string())
+}
+
+/// Parse an optional number of the star form, empty when unknown.
+fn parse_optional(text: &str, what: &str) -> Result<Option<f64>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    text.parse().map(Some).map_err(|_| format!("Invalid {} \"{}\".", what, text))
+}
+
+#[derive(Default)]
+pub struct StarsTab {
+    search: String,
+    sort_column: SortColumn,
+    sort_descending: bool,
+    form: Option<StarForm>,
+    /// Rows as last computed, since finding the rise and set of every star
+    /// against the horizon profile is too slow to do every frame.
+    rows: Option<StarRows>,
+}
+
+/// Sorted rows of the table, with what they were computed from.
+struct StarRows {
+    time: DateTime<Utc>,
+    stars: Vec<config::Star>,
+    telescope_config: TelescopeConfig,
+    search: String,
+    sort_column: SortColumn,
+    sort_descending: bool,
+    rows: Vec<StarRow>,
+}
+
+impl StarRows {
+    fn is_outdated(
+        &self,
+        tab: &StarsTab,
+        stars: &[config::Star],
+        telescope_config: &TelescopeConfig,
+        time: DateTime<Utc>,
+    ) -> bool {
+        (time - self.time).num_seconds().abs() >= ROWS_PERIOD_SECS
+            || self.stars != stars
+            || self.telescope_config != *telescope_config
+            || self.search != tab.search
+            || self.sort_column != tab.sort_column
+            || self.sort_descending != tab.sort_descending
+    }
+}
+
+/// A configured star together with its current position.
+struct StarRow {
+    star: config::Star,
+    altitude: f64,
+    azimuth: f64,
+    rise_set: RiseSet,
+    /// Whether the star is above the horizon profile.
+    visible: bool,
+}
+
+impl StarRow {
+    fn rise(&self) -> Option<DateTime<Utc>> {
+        match self.rise_set {
+            RiseSet::Rises { rise, .. } => Some(rise),
+            _ => None,
+        }
+    }
+
+    fn transit(&self) -> Option<DateTime<Utc>> {
+        match self.rise_set {
+            RiseSet::Rises { transit, .. } | RiseSet::Circumpolar { transit } => Some(transit),
+            RiseSet::NeverRises => None,
+        }
+    }
+
+    fn set(&self) -> Option<DateTime<Utc>> {
+        match self.rise_set {
+            RiseSet::Rises { set, .. } => Some(set),
+            _ => None,
+        }
+    }
+}
+
+/// Score how well `query` matches `text` as a case-insensitive subsequence.
+/// Consecutive matches and matches at word starts score higher. Returns
+/// `None` if `text` does not contain every character of `query` in order.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = text[position..].iter().position(|c| *c == query_char)? + position;
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+/// One-line summary of what is known of a source.
+pub fn describe_source(info: &SourceInfo) -> String {
+    let mut parts = vec![info.kind.to_string()];
+    if let Some(flux) = info.flux {
+        parts.push(match info.frequency {
+            Some(frequency) => format!("{} Jy at {} MHz", flux, frequency),
+            None => format!("{} Jy", flux),
+        });
+    }
+    if let Some(index) = info.spectral_index {
+        parts.push(format!("α = {}", index));
+    }
+    if let Some(magnitude) = info.magnitude {
+        parts.push(format!("magnitude {}", magnitude));
+    }
+    parts.join(", ")
+}
+
+pub fn format_time(time: Option<DateTime<Utc>>) -> String {
+    match time {
+        Some(time) => time.with_timezone(&chrono::Local).format("%H:%M").to_string(),
+        None => String::from("-"),
+    }
+}
+
+/// Position, rise and set of the stars matching the search of the tab,
+/// best matches first, then sorted by the selected column.
+fn star_rows(
+    tab: &StarsTab,
+    stars: &[config::Star],
+    telescope_config: &TelescopeConfig,
+    time: DateTime<Utc>,
+) -> StarRows {
+    let sky = LocalSky::new(
+        telescope_config.latitude as f64,
+        telescope_config.longitude as f64,
+        time,
+    ).with_atmosphere(&telescope_config.atmosphere)
+    .with_horizon(&telescope_config.horizon);
+
+    let mut rows: Vec<(i32, StarRow)> = stars
+        .iter()
+        .filter_map(|star| {
+            let score = fuzzy_score(&tab.search, &star.name)?;
+            let position = star.position();
+            let (azimuth, altitude) = sky.horizontal(&position);
+            Some((score, StarRow {
+                star: star.clone(),
+                altitude,
+                azimuth,
+                rise_set: sky.rise_transit_set(&position),
+                visible: telescope_config.horizon.is_visible(azimuth, altitude),
+            }))
+        })
+        .collect();
+
+    rows.sort_by(|(score_a, a), (score_b, b)| {
+        let ordering = match tab.sort_column {
+            SortColumn::Name => a.star.name.cmp(&b.star.name),
+            SortColumn::RightAscension => a.star.ra.total_cmp(&b.star.ra),
+            SortColumn::Declination => a.star.dec.total_cmp(&b.star.dec),
+            SortColumn::Altitude => a.altitude.total_cmp(&b.altitude),
+            SortColumn::Azimuth => a.azimuth.total_cmp(&b.azimuth),
+            SortColumn::Rise => a.rise().cmp(&b.rise()),
+            SortColumn::Transit => a.transit().cmp(&b.transit()),
+            SortColumn::Set => a.set().cmp(&b.set()),
+        };
+        let ordering = if tab.sort_descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        // Best search matches first, then the selected column.
+        score_b.cmp(score_a).then(ordering)
+    });
+
+    StarRows {
+        time,
+        stars: stars.to_vec(),
+        telescope_config: telescope_config.clone(),
+        search: tab.search.clone(),
+        sort_column: tab.sort_column,
+        sort_descending: tab.sort_descending,
+        rows: rows.into_iter().map(|(_, row)| row).collect(),
+    }
+}
+
+pub fn stars_tab(
+    ui: &mut egui::Ui,
+    tab: &mut StarsTab,
+    client_config: &mut ResMut<config::ClientConfig>,
+    telescope_config: &TelescopeConfig,
+    time: DateTime<Utc>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Search:");
+        ui.add(egui::TextEdit::singleline(&mut tab.search).hint_text("name"));
+        if ui.button("Add star").clicked() {
+            tab.form = Some(StarForm {
+                editing: None,
+                name: String::new(),
+                ra: String::new(),
+                dec: String::new(),
+                epoch: Epoch::default().to_string(),
+                pm_ra: 0.0,
+                pm_dec: 0.0,
+                parallax: 0.0,
+                flux: String::new(),
+                frequency: String::new(),
+                spectral_index: String::new(),
+                magnitude: String::new(),
+                kind: SourceType::Unknown,
+                galactic: false,
+                error: None,
+            });
+        }
+    });
+
+    if tab.form.is_some() {
+        star_form(ui, tab, client_config);
+    }
+    ui.separator();
+
+    let rows = match tab.rows.take() {
+        Some(rows) if !rows.is_outdated(tab, &client_config.stars, telescope_config, time) => rows,
+        _ => star_rows(tab, &client_config.stars, telescope_config, time),
+    };
+
+    let mut edit = None;
+    let mut delete = None;
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("stars_grid")
+            .num_columns(10)
+            .spacing([12.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (column, title) in [
+                    (SortColumn::Name, "Name"),
+                    (SortColumn::RightAscension, "RA"),
+                    (SortColumn::Declination, "Dec"),
+                    (SortColumn::Altitude, "Alt"),
+                    (SortColumn::Azimuth, "Az"),
+                    (SortColumn::Rise, "Rise"),
+                    (SortColumn::Transit, "Transit"),
+                    (SortColumn::Set, "Set"),
+                ] {
+                    let title = if tab.sort_column != column {
+                        title.to_string()
+                    } else if tab.sort_descending {
+                        format!("{} ⏷", title)
+                    } else {
+                        format!("{} ⏶", title)
+                    };
+                    if ui.selectable_label(tab.sort_column == column, title).clicked() {
+                        if tab.sort_column == column {
+                            tab.sort_descending = !tab.sort_descending;
+                        } else {
+                            tab.sort_column = column;
+                            tab.sort_descending = false;
+                        }
+                    }
+                }
+                ui.label("Visible");
+                ui.label("");
+                ui.end_row();
+
+                for row in &rows.rows {
+                    ui.label(&row.star.name).on_hover_text(describe_source(&row.star.info));
+                    ui.label(format!("{} ({:.4}°)", angle::format_right_ascension(row.star.ra), row.star.ra))
+                        .on_hover_text(format!("Epoch {}", row.star.epoch));
+                    ui.label(format!("{} ({:.4}°)", angle::format_declination(row.star.dec), row.star.dec))
+                        .on_hover_text(format!("Epoch {}", row.star.epoch));
+                    ui.label(format!("{:.1}°", row.altitude));
+                    ui.label(format!("{:.1}°", row.azimuth));
+                    match row.rise_set {
+                        RiseSet::Circumpolar { .. } => ui.label("circumpolar"),
+                        RiseSet::NeverRises => ui.label("never rises"),
+                        RiseSet::Rises { rise, .. } => ui.label(format_time(Some(rise))),
+                    };
+                    ui.label(format_time(row.transit()));
+                    ui.label(format_time(row.set()));
+                    ui.label(if row.visible { "yes" } else { "no" });
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Edit").clicked() {
+                            edit = Some(row.star.clone());
+                        }
+                        if ui.small_button("Delete").clicked() {
+                            delete = Some(row.star.name.clone());
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+    });
+    tab.rows = Some(rows);
+
+    if let Some(star) = edit {
+        tab.form = Some(StarForm {
+            editing: Some(star.name.clone()),
+            name: star.name,
+            ra: angle::format_right_ascension(star.ra),
+            dec: angle::format_declination(star.dec),
+            epoch: star.epoch.to_string(),
+            pm_ra: star.pm_ra,
+            pm_dec: star.pm_dec,
+            parallax: star.parallax,
+            flux: format_optional(star.info.flux),
+            frequency: format_optional(star.info.frequency),
+            spectral_index: format_optional(star.info.spectral_index),
+            magnitude: format_optional(star.info.magnitude),
+            kind: star.info.kind,
+            galactic: false,
+            error: None,
+        });
+    }
+    if let Some(name) = delete {
+        client_config.stars.retain(|star| star.name != name);
+        save_client_config(client_config);
+    }
+}
+
+fn star_form(
+    ui: &mut egui::Ui,
+    tab: &mut StarsTab,
+    client_config: &mut ResMut<config::ClientConfig>,
+) {
+    let Some(form) = tab.form.as_mut() else {
+        return;
+    };
+    let mut close = false;
+
+    ui.group(|ui| {
+        egui::Grid::new("star_form_grid")
+            .num_columns(3)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut form.name);
+                ui.label("");
+                ui.end_row();
+
+                ui.label("Coordinates:");
+                if ui.checkbox(&mut form.galactic, "Galactic").changed() {
+                    convert_form_coordinates(form);
+                }
+                ui.label("");
+                ui.end_row();
+
+                if form.galactic {
+                    ui.label("Galactic longitude:");
+                    ui.add(egui::TextEdit::singleline(&mut form.ra).hint_text("209.01"));
+                    ui.label(match angle::parse_galactic_longitude(&form.ra) {
+                        Ok(longitude) => format!("l = {:.4}°", longitude),
+                        Err(_) => String::new(),
+                    });
+                    ui.end_row();
+
+                    ui.label("Galactic latitude:");
+                    ui.add(egui::TextEdit::singleline(&mut form.dec).hint_text("-19.38"));
+                    ui.label(match angle::parse_galactic_latitude(&form.dec) {
+                        Ok(latitude) => format!("b = {:.4}°", latitude),
+                        Err(_) => String::new(),
+                    });
+                    ui.end_row();
+                } else {
+                    ui.label("Right ascension:");
+                    ui.add(egui::TextEdit::singleline(&mut form.ra).hint_text("05h35m17s or 83.82"));
+                    ui.label(match angle::parse_right_ascension(&form.ra) {
+                        Ok(ra) => format!("{} = {:.4}°", angle::format_right_ascension(ra), ra),
+                        Err(_) => String::new(),
+                    });
+                    ui.end_row();
+
+                    ui.label("Declination:");
+                    ui.add(egui::TextEdit::singleline(&mut form.dec).hint_text("-05°23'28\" or -5.39"));
+                    ui.label(match angle::parse_declination(&form.dec) {
+                        Ok(dec) => format!("{} = {:.4}°", angle::format_declination(dec), dec),
+                        Err(_) => String::new(),
+                    });
+                    ui.end_row();
+                }
+
+                ui.label("Epoch:");
+                ui.add_enabled(!form.galactic, egui::TextEdit::singleline(&mut form.epoch).hint_text("J2000"));
+                ui.label("");
+                ui.end_row();
+
+                ui.label("Proper motion:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut form.pm_ra).speed(0.1).prefix("RA·cos Dec "));
+                    ui.add(egui::DragValue::new(&mut form.pm_dec).speed(0.1).prefix("Dec "));
+                });
+                ui.label("mas/yr");
+                ui.end_row();
+
+                ui.label("Parallax:");
+                ui.add(egui::DragValue::new(&mut form.parallax)
+                    .speed(0.1)
+                    .range(0.0..=f64::MAX),
+                );
+                ui.label("mas");
+                ui.end_row();
+
+                ui.label("Type:");
+                egui::ComboBox::from_id_salt("star_form_type")
+                    .selected_text(form.kind.to_string())
+                    .show_ui(ui, |ui| {
+                        for kind in SourceType::ALL {
+                            ui.selectable_value(&mut form.kind, kind, kind.to_string());
+                        }
+                    });
+                ui.label("");
+                ui.end_row();
+
+                ui.label("Flux density:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut form.flux).hint_text("Jy").desired_width(80.0));
+                    ui.label("at");
+                    ui.add(egui::TextEdit::singleline(&mut form.frequency).hint_text("MHz").desired_width(80.0));
+                });
+                ui.label("Jy at MHz");
+                ui.end_row();
+
+                ui.label("Spectral index:");
+                ui.add(egui::TextEdit::singleline(&mut form.spectral_index).hint_text("-0.7"));
+                ui.label("");
+                ui.end_row();
+
+                ui.label("Magnitude:");
+                ui.add(egui::TextEdit::singleline(&mut form.magnitude).hint_text("visual"));
+                ui.label("");
+                ui.end_row();
+            });
+
+        if let Some(error) = &form.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.horizontal(|ui| {
+            let save_label = if form.editing.is_some() { "Save" } else { "Add" };
+            if ui.button(save_label).clicked() {
+                match parse_star_form(form, &client_config.stars) {
+                    Err(e) => form.error = Some(e),
+                    Ok(star) => {
+                        match &form.editing {
+                            Some(original_name) => {
+                                if let Some(existing) = client_config.stars
+                                    .iter_mut()
+                                    .find(|star| &star.name == original_name) {
+                                    *existing = star;
+                                }
+                            },
+                            None => client_config.stars.push(star),
+                        }
+                        save_client_config(client_config);
+                        close = true;
+                    },
+                }
+            }
+            if ui.button("Cancel").clicked() {
+                close = true;
+            }
+        });
+    });
+
+    if close {
+        tab.form = None;
+    }
+}
+
+/// Rewrite the coordinates of the form in the frame it was just switched
+/// to, leaving them as typed when they can not be read.
+fn convert_form_coordinates(form: &mut StarForm) {
+    if form.galactic {
+        let equatorial = angle::parse_right_ascension(&form.ra)
+            .and_then(|ra| Ok((ra, angle::parse_declination(&form.dec)?, Epoch::parse(&form.epoch)?)));
+        if let Ok((ra, dec, epoch)) = equatorial {
+            let (ra, dec) = to_j2000(ra, dec, epoch);
+            let (longitude, latitude) = galactic::equatorial_to_galactic(ra, dec);
+            form.ra = format!("{:.4}", longitude);
+            form.dec = format!("{:.4}", latitude);
+        }
+    } else {
+        let galactic = angle::parse_galactic_longitude(&form.ra)
+            .and_then(|longitude| Ok((longitude, angle::parse_galactic_latitude(&form.dec)?)));
+        if let Ok((longitude, latitude)) = galactic {
+            let (ra, dec) = galactic::galactic_to_equatorial(longitude, latitude);
+            form.ra = angle::format_right_ascension(ra);
+            form.dec = angle::format_declination(dec);
+            form.epoch = Epoch::default().to_string();
+        }
+    }
+}
+
+/// Validate the form values and turn them into a star.
+fn parse_star_form(form: &StarForm, stars: &[config::Star]) -> Result<config::Star, String> {
+    let name = form.name.trim().to_string();
+    if name.is_empty() {
+        return Err(String::from("The name can not be empty."));
+    }
+    if stars.iter().any(|star| star.name == name && form.editing.as_ref() != Some(&star.name)) {
+        return Err(format!("There is already a star named {}.", name));
+    }
+    let (ra, dec, epoch) = if form.galactic {
+        let (ra, dec) = galactic::galactic_to_equatorial(
+            angle::parse_galactic_longitude(&form.ra)?,
+            angle::parse_galactic_latitude(&form.dec)?,
+        );
+        (ra, dec, Epoch::default())
+    } else {
+        (
+            angle::parse_right_ascension(&form.ra)?,
+            angle::parse_declination(&form.dec)?,
+            Epoch::parse(&form.epoch)?,
+        )
+    };
+    Ok(config::Star {
+        name,
+        ra,
+        dec,
+        epoch,
+        pm_ra: form.pm_ra,
+        pm_dec: form.pm_dec,
+        parallax: form.parallax,
+        info: SourceInfo {
+            flux: parse_optional(&form.flux, "flux density")?,
+            frequency: parse_optional(&form.frequency, "frequency")?,
+            spectral_index: parse_optional(&form.spectral_index, "spectral index")?,
+            magnitude: parse_optional(&form.magnitude, "magnitude")?,
+            kind: form.kind,
+        },
+    })
+}
+
+pub fn save_client_config(client_config: &config::ClientConfig) {
+    if let Err(e) = client_config.save() {
+        println!("Could not save client configuration: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_fuzzy_matches() {
+        assert_eq!(fuzzy_score("", "Sirius"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "Sirius"), None);
+        // Every character must come in order.
+        assert_eq!(fuzzy_score("suis", "Sirius"), None);
+        assert!(fuzzy_score("SIR", "sirius").is_some());
+
+        // Consecutive matches beat scattered ones, and word starts beat
+        // matches inside a word.
+        assert!(fuzzy_score("ori", "Orion Nebula") > fuzzy_score("ori", "Procyon Bright"));
+        assert!(fuzzy_score("neb", "Nebula") > fuzzy_score("neb", "Canebula"));
+    }
+}
@@ -0,0 +1,115 @@
+use bevy::{
+    app::AppExit,
+    prelude::*,
+};
+use bevy_panorbit_camera::{
+    PanOrbitCamera,
+};
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::ui::OccupiedScreenSpace;
+use crate::ui::OriginalCameraConfig;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+
+/// Time the UI has to stay unchanged before the state file is written, so
+/// dragging the camera or a panel does not write it every frame.
+const SAVE_DELAY_SECS: f32 = 1.0;
+
+/// UI settings that are kept between runs.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct UiState {
+    pub telescope: TelescopeConfig,
+    pub view: ViewConfig,
+    pub camera: OriginalCameraConfig,
+    pub right_panel_width: f32,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        UiState {
+            telescope: TelescopeConfig::default(),
+            view: ViewConfig::default(),
+            camera: OriginalCameraConfig::default(),
+            right_panel_width: 450.0,
+        }
+    }
+}
+
+fn state_file_path() -> (String, String) {
+    let dir = std::env::var("XDG_STATE_HOME").unwrap_or(
+        std::env::var("HOME").unwrap_or(String::from("~")) + "/.local/state"
+    ) + "/radio-telescope-controller/";
+    let path = dir.clone() + "ui.toml";
+    (dir, path)
+}
+
+impl UiState {
+    /// Load the state saved by a previous run, falling back to the defaults
+    /// if there is none or it can not be read.
+    pub fn load() -> Self {
+        let (_, path) = state_file_path();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                println!("Ignoring invalid UI state file {}: {}", path, e);
+                UiState::default()
+            }),
+            Err(_) => UiState::default(),
+        }
+    }
+
+    fn save(&self) {
+        let (dir, path) = state_file_path();
+        let result = std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(&path, toml::to_string_pretty(self).unwrap()));
+        if let Err(e) = result {
+            println!("Could not save UI state to {}: {}", path, e);
+        }
+    }
+}
+
+/// Write the UI state to disk once it stops changing, and on exit.
+#[allow(clippy::too_many_arguments)]
+pub fn save_ui_state(
+    time: Res<Time>,
+    telescope_config: Res<TelescopeConfig>,
+    view_config: Res<ViewConfig>,
+    occupied_screen_space: Res<OccupiedScreenSpace>,
+    mut ui_state: ResMut<UiState>,
+    mut exit_events: EventReader<AppExit>,
+
+    camera_query: Query<&PanOrbitCamera>,
+
+    mut pending_since: Local<Option<f32>>,
+) {
+    let mut current_state = UiState {
+        telescope: telescope_config.clone(),
+        view: view_config.clone(),
+        camera: ui_state.camera.clone(),
+        right_panel_width: occupied_screen_space.right,
+    };
+    if let Ok(camera) = camera_query.get_single() {
+        current_state.camera = OriginalCameraConfig {
+            radius: camera.target_radius,
+            pitch: camera.target_pitch,
+            yaw: camera.target_yaw,
+        };
+    }
+
+    if current_state != *ui_state {
+        *ui_state = current_state;
+        *pending_since = Some(time.elapsed_seconds());
+    }
+
+    let exiting = exit_events.read().count() > 0;
+    if let Some(since) = *pending_since {
+        if exiting || time.elapsed_seconds() - since > SAVE_DELAY_SECS {
+            ui_state.save();
+            *pending_since = None;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::PrimitiveTopology,
+};
+
+use astro::horizon::HorizonProfile;
+use crate::ui::TelescopeConfig;
+use crate::ui::ViewConfig;
+use crate::ui::sphere::Rotator;
+use crate::ui::sphere::horizontal_to_world;
+
+/// Azimuth step, in degrees, of the silhouette between measured points.
+const SILHOUETTE_STEP: f64 = 1.0;
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            rebuild_terrain.run_if(resource_changed::<TelescopeConfig>),
+            handle_terrain_visibility,
+        ).chain());
+    }
+}
+
+/// Ground and horizon silhouette around the telescope.
+#[derive(Component)]
+struct Terrain;
+
+fn triangle_list(positions: Vec<[f32; 3]>) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, bevy::render::render_asset::RenderAssetUsages::default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh
+}
+
+/// Azimuths at which the silhouette is drawn: regular steps and every
+/// measured point, so that sharp corners of the profile are kept.
+fn silhouette_azimuths(horizon: &HorizonProfile) -> Vec<f64> {
+    let n_steps = (360.0 / SILHOUETTE_STEP) as usize;
+    let mut azimuths: Vec<f64> = (0..n_steps).map(|step| step as f64 * SILHOUETTE_STEP).collect();
+    azimuths.extend(horizon.azimuths());
+    azimuths.sort_by(f64::total_cmp);
+    azimuths.dedup();
+    azimuths.push(360.0);
+    azimuths
+}
+
+/// Quads on the sphere from the horizon up to the profile, wherever the
+/// profile rises above it.
+fn silhouette_mesh(horizon: &HorizonProfile) -> Mesh {
+    let mut positions = Vec::new();
+    for pair in silhouette_azimuths(horizon).windows(2) {
+        let [start, end] = [pair[0], pair[1]];
+        let (start_elevation, end_elevation) = (horizon.terrain_elevation(start), horizon.terrain_elevation(end));
+        if start_elevation <= 0.0 && end_elevation <= 0.0 {
+            continue;
+        }
+        let bottom_start = horizontal_to_world(start, 0.0).to_array();
+        let bottom_end = horizontal_to_world(end, 0.0).to_array();
+        let top_start = horizontal_to_world(start, start_elevation.max(0.0)).to_array();
+        let top_end = horizontal_to_world(end, end_elevation.max(0.0)).to_array();
+        positions.extend_from_slice(&[bottom_start, bottom_end, top_end, bottom_start, top_end, top_start]);
+    }
+    triangle_list(positions)
+}
+
+/// Disk of the ground, filling the horizon.
+fn ground_mesh() -> Mesh {
+    let n_vertices = 72;
+    let mut positions = Vec::new();
+    for vertex in 0..n_vertices {
+        let start = 360.0 * vertex as f64 / n_vertices as f64;
+        let end = 360.0 * (vertex + 1) as f64 / n_vertices as f64;
+        positions.extend_from_slice(&[
+            [0.0, 0.0, 0.0],
+            horizontal_to_world(start, 0.0).to_array(),
+            horizontal_to_world(end, 0.0).to_array(),
+        ]);
+    }
+    triangle_list(positions)
+}
+
+/// Spawn the ground and the silhouette of the horizon profile again when it
+/// changes.
+fn rebuild_terrain(
+    telescope_config: Res<TelescopeConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+
+    terrain_query: Query<Entity, With<Terrain>>,
+
+    mut commands: Commands,
+    mut built: Local<Option<HorizonProfile>>,
+) {
+    if built.as_ref() == Some(&telescope_config.horizon) {
+        return;
+    }
+    for entity in &terrain_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let parts = [
+        (ground_mesh(), Color::linear_rgba(0.05, 0.2, 0.05, 0.35), AlphaMode::Blend),
+        (silhouette_mesh(&telescope_config.horizon), Color::linear_rgb(0.08, 0.12, 0.06), AlphaMode::Opaque),
+    ];
+    for (mesh, color, alpha_mode) in parts {
+        if mesh.count_vertices() == 0 {
+            continue;
+        }
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(mesh),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    alpha_mode,
+                    unlit: true,
+                    double_sided: true,
+                    cull_mode: None,
+                    ..default()
+                }),
+                ..default()
+            },
+            Terrain,
+            Rotator,
+        ));
+    }
+    *built = Some(telescope_config.horizon.clone());
+}
+
+fn handle_terrain_visibility(
+    view_config: Res<ViewConfig>,
+
+    mut terrain_query: Query<&mut Visibility, With<Terrain>>,
+) {
+    let wanted = if view_config.show_terrain {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for mut visibility in &mut terrain_query {
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}